@@ -13,19 +13,94 @@ pub const MAX_MESSAGE_LEN: usize = 2 * 1024 * 1024;
 
 /// The current network protocol version number.
 pub const PROTOCOL_VERSION: u32 = 170_120;
+/// The lowest protocol version supporting NU5 on mainnet.
+pub const NU5_MAINNET_PROTOCOL_VERSION: u32 = 170_100;
+/// The lowest protocol version supporting NU5 on testnet.
+pub const NU5_TESTNET_PROTOCOL_VERSION: u32 = 170_050;
 /// The current network version identifier.
 pub const MAGIC_TESTNET: [u8; MAGIC_LEN] = [0xfa, 0x1a, 0xf9, 0xbf];
 pub const MAGIC_MAINNET: [u8; MAGIC_LEN] = [0x24, 0xe9, 0x27, 0x64];
+pub const MAGIC_REGTEST: [u8; MAGIC_LEN] = [0xaa, 0xe8, 0x3f, 0x5f];
 
 /// Version message user agent
 pub const USER_AGENT: &str = "MagicBean:5.4.2";
+/// Maximum user agent length accepted by peers (256 bytes).
+pub const MAX_USER_AGENT_LEN: usize = 256;
+/// Maximum number of addresses in a single `Addr` message (1000 entries).
+pub const MAX_ADDRS: usize = 1000;
+/// Maximum number of block hashes in the `Inv` replying to a `GetBlocks` (500 entries).
+pub const MAX_GETBLOCKS_INV: usize = 500;
+/// Maximum number of headers in the `Headers` replying to a `GetHeaders` (160 entries).
+pub const MAX_GETHEADERS_RESULTS: usize = 160;
 
+/// A Zcash network, which determines the magic of the message headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    Regtest,
+}
+
+impl Network {
+    /// Returns the magic identifying the network's messages.
+    pub const fn magic(&self) -> [u8; MAGIC_LEN] {
+        match self {
+            Self::Mainnet => MAGIC_MAINNET,
+            Self::Testnet => MAGIC_TESTNET,
+            Self::Regtest => MAGIC_REGTEST,
+        }
+    }
+
+    /// Returns the network's default P2P port.
+    pub const fn default_port(&self) -> u16 {
+        match self {
+            Self::Mainnet => 8233,
+            Self::Testnet => 18233,
+            Self::Regtest => 18344,
+        }
+    }
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        DEFAULT_NETWORK
+    }
+}
+
+impl std::str::FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mainnet" => Ok(Self::Mainnet),
+            "testnet" => Ok(Self::Testnet),
+            "regtest" => Ok(Self::Regtest),
+            _ => Err(format!(
+                "unknown network {s:?}, expected mainnet, testnet or regtest"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mainnet => f.write_str("mainnet"),
+            Self::Testnet => f.write_str("testnet"),
+            Self::Regtest => f.write_str("regtest"),
+        }
+    }
+}
+
+/// The network messages are encoded for unless another one is chosen, the tests run against
+/// testnet nodes.
 #[cfg(test)]
-pub const MAGIC: [u8; MAGIC_LEN] = MAGIC_TESTNET;
-#[cfg(all(not(test), not(feature = "crawler")))]
-pub const MAGIC: [u8; MAGIC_LEN] = MAGIC_MAINNET;
-#[cfg(all(not(test), feature = "crawler"))]
-pub const MAGIC: [u8; MAGIC_LEN] = MAGIC_MAINNET;
+pub const DEFAULT_NETWORK: Network = Network::Testnet;
+#[cfg(not(test))]
+pub const DEFAULT_NETWORK: Network = Network::Mainnet;
+
+/// The magic of the [`DEFAULT_NETWORK`].
+pub const MAGIC: [u8; MAGIC_LEN] = DEFAULT_NETWORK.magic();
 
 pub const COMMAND_LEN: usize = 12;
 
@@ -50,3 +125,9 @@ pub const FILTERLOAD_COMMAND: [u8; COMMAND_LEN] = *b"filterload\0\0";
 pub const FILTERADD_COMMAND: [u8; COMMAND_LEN] = *b"filteradd\0\0\0";
 pub const FILTERCLEAR_COMMAND: [u8; COMMAND_LEN] = *b"filterclear\0";
 pub const ALERT_COMMAND: [u8; COMMAND_LEN] = *b"alert\0\0\0\0\0\0\0";
+pub const ADDRV2_COMMAND: [u8; COMMAND_LEN] = *b"addrv2\0\0\0\0\0\0";
+pub const SENDADDRV2_COMMAND: [u8; COMMAND_LEN] = *b"sendaddrv2\0\0";
+pub const SENDCMPCT_COMMAND: [u8; COMMAND_LEN] = *b"sendcmpct\0\0\0";
+pub const CMPCTBLOCK_COMMAND: [u8; COMMAND_LEN] = *b"cmpctblock\0\0";
+pub const GETBLOCKTXN_COMMAND: [u8; COMMAND_LEN] = *b"getblocktxn\0";
+pub const BLOCKTXN_COMMAND: [u8; COMMAND_LEN] = *b"blocktxn\0\0\0\0";
@@ -6,13 +6,15 @@ use std::io;
 
 use bytes::{Buf, BufMut, BytesMut};
 use sha2::{Digest, Sha256};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
 use crate::protocol::{
     message::constants::*,
     payload::{
         block::{Block, Headers, LocatorHashes},
         codec::Codec,
-        Addr, FilterAdd, FilterLoad, Inv, Nonce, Reject, Tx, Version,
+        Addr, AddrV2, BlockTxn, CmpctBlock, FilterAdd, FilterLoad, GetBlockTxn, Inv, Nonce, Reject,
+        SendCmpct, Tx, Version,
     },
 };
 
@@ -62,8 +64,13 @@ impl Codec for MessageHeader {
 impl MessageHeader {
     /// Returns a `MessageHeader` constructed from the message body.
     pub fn new(command: [u8; COMMAND_LEN], body: &[u8]) -> Self {
+        Self::new_for_network(DEFAULT_NETWORK, command, body)
+    }
+
+    /// Returns a `MessageHeader` of the given network constructed from the message body.
+    pub fn new_for_network(network: Network, command: [u8; COMMAND_LEN], body: &[u8]) -> Self {
         MessageHeader {
-            magic: MAGIC,
+            magic: network.magic(),
             command,
             body_length: body.len() as u32,
             checksum: checksum(body),
@@ -83,6 +90,8 @@ pub enum Message {
     Pong(Nonce),
     GetAddr,
     Addr(Addr),
+    AddrV2(AddrV2),
+    SendAddrV2,
     GetHeaders(LocatorHashes),
     Headers(Headers),
     GetBlocks(LocatorHashes),
@@ -96,19 +105,23 @@ pub enum Message {
     FilterLoad(FilterLoad),
     FilterAdd(FilterAdd),
     FilterClear,
+    SendCmpct(SendCmpct),
+    CmpctBlock(Box<CmpctBlock>),
+    GetBlockTxn(GetBlockTxn),
+    BlockTxn(BlockTxn),
     Alert,
 }
 
 macro_rules! encode_with_header_prefix {
-    ($command:expr, $buffer:expr) => {{
-        let header = MessageHeader::new($command, &[]);
+    ($network:expr, $command:expr, $buffer:expr) => {{
+        let header = MessageHeader::new_for_network($network, $command, &[]);
         header.encode($buffer)?;
     }};
 
-    ($command:expr, $buffer:expr, $payload:expr) => {{
+    ($network:expr, $command:expr, $buffer:expr, $payload:expr) => {{
         $payload.encode($buffer)?;
         let serialized_payload = $buffer.split_to($buffer.len()).freeze();
-        let header = MessageHeader::new($command, &serialized_payload);
+        let header = MessageHeader::new_for_network($network, $command, &serialized_payload);
         header.encode($buffer)?;
         $buffer.put_slice(&serialized_payload);
     }};
@@ -117,63 +130,86 @@ macro_rules! encode_with_header_prefix {
 impl Message {
     /// Encodes a message into the supplied buffer and returns its header.
     pub fn encode(&self, buffer: &mut BytesMut) -> io::Result<()> {
+        self.encode_for_network(DEFAULT_NETWORK, buffer)
+    }
+
+    /// Encodes a message with the header magic of the given network into the supplied buffer.
+    pub fn encode_for_network(&self, network: Network, buffer: &mut BytesMut) -> io::Result<()> {
         match self {
             Self::Version(version) => {
-                encode_with_header_prefix!(VERSION_COMMAND, buffer, version);
+                encode_with_header_prefix!(network, VERSION_COMMAND, buffer, version);
             }
             Self::Verack => {
-                encode_with_header_prefix!(VERACK_COMMAND, buffer);
+                encode_with_header_prefix!(network, VERACK_COMMAND, buffer);
             }
             Self::Ping(nonce) => {
-                encode_with_header_prefix!(PING_COMMAND, buffer, nonce);
+                encode_with_header_prefix!(network, PING_COMMAND, buffer, nonce);
             }
             Self::Pong(nonce) => {
-                encode_with_header_prefix!(PONG_COMMAND, buffer, nonce);
+                encode_with_header_prefix!(network, PONG_COMMAND, buffer, nonce);
             }
             Self::GetAddr => {
-                encode_with_header_prefix!(GETADDR_COMMAND, buffer);
+                encode_with_header_prefix!(network, GETADDR_COMMAND, buffer);
             }
             Self::Addr(addr) => {
-                encode_with_header_prefix!(ADDR_COMMAND, buffer, addr);
+                encode_with_header_prefix!(network, ADDR_COMMAND, buffer, addr);
+            }
+            Self::AddrV2(addrv2) => {
+                encode_with_header_prefix!(network, ADDRV2_COMMAND, buffer, addrv2);
+            }
+            Self::SendAddrV2 => {
+                encode_with_header_prefix!(network, SENDADDRV2_COMMAND, buffer);
             }
             Self::GetHeaders(locator_hashes) => {
-                encode_with_header_prefix!(GETHEADERS_COMMAND, buffer, locator_hashes);
+                encode_with_header_prefix!(network, GETHEADERS_COMMAND, buffer, locator_hashes);
             }
             Self::Headers(headers) => {
-                encode_with_header_prefix!(HEADERS_COMMAND, buffer, headers);
+                encode_with_header_prefix!(network, HEADERS_COMMAND, buffer, headers);
             }
             Self::GetBlocks(locator_hashes) => {
-                encode_with_header_prefix!(GETBLOCKS_COMMAND, buffer, locator_hashes);
+                encode_with_header_prefix!(network, GETBLOCKS_COMMAND, buffer, locator_hashes);
             }
             Self::Block(block) => {
-                encode_with_header_prefix!(BLOCK_COMMAND, buffer, block);
+                encode_with_header_prefix!(network, BLOCK_COMMAND, buffer, block);
             }
             Self::GetData(inv) => {
-                encode_with_header_prefix!(GETDATA_COMMAND, buffer, inv);
+                encode_with_header_prefix!(network, GETDATA_COMMAND, buffer, inv);
             }
             Self::Inv(inv) => {
-                encode_with_header_prefix!(INV_COMMAND, buffer, inv);
+                encode_with_header_prefix!(network, INV_COMMAND, buffer, inv);
             }
             Self::NotFound(inv) => {
-                encode_with_header_prefix!(NOTFOUND_COMMAND, buffer, inv);
+                encode_with_header_prefix!(network, NOTFOUND_COMMAND, buffer, inv);
             }
             Self::MemPool => {
-                encode_with_header_prefix!(MEMPOOL_COMMAND, buffer);
+                encode_with_header_prefix!(network, MEMPOOL_COMMAND, buffer);
             }
             Self::Tx(tx) => {
-                encode_with_header_prefix!(TX_COMMAND, buffer, tx);
+                encode_with_header_prefix!(network, TX_COMMAND, buffer, tx);
             }
             Self::Reject(reject) => {
-                encode_with_header_prefix!(REJECT_COMMAND, buffer, reject);
+                encode_with_header_prefix!(network, REJECT_COMMAND, buffer, reject);
             }
             Self::FilterLoad(filter_load) => {
-                encode_with_header_prefix!(FILTERLOAD_COMMAND, buffer, filter_load);
+                encode_with_header_prefix!(network, FILTERLOAD_COMMAND, buffer, filter_load);
             }
             Self::FilterAdd(filter) => {
-                encode_with_header_prefix!(FILTERADD_COMMAND, buffer, filter);
+                encode_with_header_prefix!(network, FILTERADD_COMMAND, buffer, filter);
             }
             Self::FilterClear => {
-                encode_with_header_prefix!(FILTERCLEAR_COMMAND, buffer);
+                encode_with_header_prefix!(network, FILTERCLEAR_COMMAND, buffer);
+            }
+            Self::SendCmpct(send_cmpct) => {
+                encode_with_header_prefix!(network, SENDCMPCT_COMMAND, buffer, send_cmpct);
+            }
+            Self::CmpctBlock(cmpct_block) => {
+                encode_with_header_prefix!(network, CMPCTBLOCK_COMMAND, buffer, cmpct_block);
+            }
+            Self::GetBlockTxn(get_block_txn) => {
+                encode_with_header_prefix!(network, GETBLOCKTXN_COMMAND, buffer, get_block_txn);
+            }
+            Self::BlockTxn(block_txn) => {
+                encode_with_header_prefix!(network, BLOCKTXN_COMMAND, buffer, block_txn);
             }
             // Don't send deprecated alert messages.
             Self::Alert => (),
@@ -182,25 +218,124 @@ impl Message {
         Ok(())
     }
 
+    /// Returns the fully encoded message, header and body.
+    pub fn to_vec(&self) -> io::Result<Vec<u8>> {
+        let mut buffer = BytesMut::new();
+        self.encode(&mut buffer)?;
+
+        Ok(buffer.to_vec())
+    }
+
+    /// Writes the fully encoded message, header and body, to the supplied writer.
+    pub async fn write_to<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> io::Result<()> {
+        let mut buffer = BytesMut::new();
+        self.encode(&mut buffer)?;
+
+        writer.write_all(&buffer).await
+    }
+
+    /// Compares the messages structurally, treating the nonces of `Ping`, `Pong` and `Version`
+    /// messages as wildcards.
+    pub fn eq_ignoring_nonce(&self, other: &Message) -> bool {
+        match (self, other) {
+            (Self::Ping(_), Self::Ping(_)) | (Self::Pong(_), Self::Pong(_)) => true,
+            (Self::Version(version), Self::Version(other_version)) => {
+                Version {
+                    nonce: other_version.nonce,
+                    ..version.clone()
+                } == *other_version
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Returns the message's command string as it appears in the header, without the padding.
+    pub fn command(&self) -> &'static str {
+        match self {
+            Self::Version(_) => "version",
+            Self::Verack => "verack",
+            Self::Ping(_) => "ping",
+            Self::Pong(_) => "pong",
+            Self::GetAddr => "getaddr",
+            Self::Addr(_) => "addr",
+            Self::AddrV2(_) => "addrv2",
+            Self::SendAddrV2 => "sendaddrv2",
+            Self::GetHeaders(_) => "getheaders",
+            Self::Headers(_) => "headers",
+            Self::GetBlocks(_) => "getblocks",
+            Self::Block(_) => "block",
+            Self::GetData(_) => "getdata",
+            Self::Inv(_) => "inv",
+            Self::NotFound(_) => "notfound",
+            Self::MemPool => "mempool",
+            Self::Tx(_) => "tx",
+            Self::Reject(_) => "reject",
+            Self::FilterLoad(_) => "filterload",
+            Self::FilterAdd(_) => "filteradd",
+            Self::FilterClear => "filterclear",
+            Self::SendCmpct(_) => "sendcmpct",
+            Self::CmpctBlock(_) => "cmpctblock",
+            Self::GetBlockTxn(_) => "getblocktxn",
+            Self::BlockTxn(_) => "blocktxn",
+            Self::Alert => "alert",
+        }
+    }
+
     /// Decodes the bytes into a message.
     pub fn decode<B: Buf>(command: [u8; 12], bytes: &mut B) -> io::Result<Self> {
         let message = match command {
             VERSION_COMMAND => Self::Version(Version::decode(bytes)?),
-            VERACK_COMMAND => Self::Verack,
+            VERACK_COMMAND => {
+                ensure_empty_body("Verack", bytes)?;
+                Self::Verack
+            }
             PING_COMMAND => Self::Ping(Nonce::decode(bytes)?),
             PONG_COMMAND => Self::Pong(Nonce::decode(bytes)?),
-            GETADDR_COMMAND => Self::GetAddr,
+            GETADDR_COMMAND => {
+                ensure_empty_body("GetAddr", bytes)?;
+                Self::GetAddr
+            }
             ADDR_COMMAND => Self::Addr(Addr::decode(bytes)?),
+            ADDRV2_COMMAND => Self::AddrV2(AddrV2::decode(bytes)?),
+            SENDADDRV2_COMMAND => {
+                ensure_empty_body("SendAddrV2", bytes)?;
+                Self::SendAddrV2
+            }
             GETHEADERS_COMMAND => Self::GetHeaders(LocatorHashes::decode(bytes)?),
             HEADERS_COMMAND => Self::Headers(Headers::decode(bytes)?),
             GETBLOCKS_COMMAND => Self::GetBlocks(LocatorHashes::decode(bytes)?),
-            BLOCK_COMMAND => Self::Block(Box::new(Block::decode(bytes)?)),
+            BLOCK_COMMAND => {
+                let block = Block::decode(bytes)?;
+
+                // The frame contains exactly one block, anything left over is garbage.
+                if bytes.has_remaining() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Block message has {} trailing byte(s)", bytes.remaining()),
+                    ));
+                }
+
+                Self::Block(Box::new(block))
+            }
             GETDATA_COMMAND => Self::GetData(Inv::decode(bytes)?),
             INV_COMMAND => Self::Inv(Inv::decode(bytes)?),
             NOTFOUND_COMMAND => Self::NotFound(Inv::decode(bytes)?),
-            MEMPOOL_COMMAND => Self::MemPool,
+            MEMPOOL_COMMAND => {
+                ensure_empty_body("MemPool", bytes)?;
+                Self::MemPool
+            }
             TX_COMMAND => Self::Tx(Tx::decode(bytes)?),
             REJECT_COMMAND => Self::Reject(Reject::decode(bytes)?),
+            SENDCMPCT_COMMAND => Self::SendCmpct(SendCmpct::decode(bytes)?),
+            CMPCTBLOCK_COMMAND => Self::CmpctBlock(Box::new(CmpctBlock::decode(bytes)?)),
+            GETBLOCKTXN_COMMAND => Self::GetBlockTxn(GetBlockTxn::decode(bytes)?),
+            BLOCKTXN_COMMAND => Self::BlockTxn(BlockTxn::decode(bytes)?),
+            FILTERLOAD_COMMAND => Self::FilterLoad(FilterLoad::decode(bytes)?),
+            FILTERADD_COMMAND => Self::FilterAdd(FilterAdd::decode(bytes)?),
+            FILTERCLEAR_COMMAND => {
+                ensure_empty_body("FilterClear", bytes)?;
+                Self::FilterClear
+            }
             // Explicitly ignore alert messages since they are deprecated.
             ALERT_COMMAND => {
                 bytes.advance(bytes.remaining());
@@ -227,6 +362,8 @@ impl std::fmt::Display for Message {
             Message::Pong(nonce) => f.write_fmt(format_args!("Pong({nonce:?})")),
             Message::GetAddr => f.write_str("GetAddr"),
             Message::Addr(_) => f.write_str("Addr"),
+            Message::AddrV2(_) => f.write_str("AddrV2"),
+            Message::SendAddrV2 => f.write_str("SendAddrV2"),
             Message::GetHeaders(_) => f.write_str("GetHeaders"),
             Message::Headers(_) => f.write_str("Headers"),
             Message::GetBlocks(_) => f.write_str("GetBlocks"),
@@ -240,11 +377,30 @@ impl std::fmt::Display for Message {
             Message::FilterLoad(_) => f.write_str("FilterLoad"),
             Message::FilterAdd(_) => f.write_str("FilterAdd"),
             Message::FilterClear => f.write_str("FilterClear"),
+            Message::SendCmpct(_) => f.write_str("SendCmpct"),
+            Message::CmpctBlock(_) => f.write_str("CmpctBlock"),
+            Message::GetBlockTxn(_) => f.write_str("GetBlockTxn"),
+            Message::BlockTxn(_) => f.write_str("BlockTxn"),
             Message::Alert => f.write_str("Alert"),
         }
     }
 }
 
+/// Returns an error if a message without a payload has a non-empty body.
+fn ensure_empty_body<B: Buf>(message: &str, bytes: &B) -> io::Result<()> {
+    if bytes.has_remaining() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{message} message has no payload, but its body has {} byte(s)",
+                bytes.remaining()
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
 fn checksum(bytes: &[u8]) -> u32 {
     let sha2 = Sha256::digest(bytes);
     let sha2d = Sha256::digest(sha2);
@@ -254,3 +410,157 @@ fn checksum(bytes: &[u8]) -> u32 {
 
     u32::from_le_bytes(checksum)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::vectors::BLOCK_TESTNET_GENESIS_BYTES;
+
+    #[test]
+    #[ignore]
+    fn verack_wire_bytes() {
+        // Testnet Verack as sent by zcashd: magic, command, empty body length and the checksum of
+        // an empty body.
+        const VERACK_WIRE_BYTES: [u8; HEADER_LEN] = [
+            0xfa, 0x1a, 0xf9, 0xbf, b'v', b'e', b'r', b'a', b'c', b'k', 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0x5d, 0xf6, 0xe0, 0xe2,
+        ];
+
+        assert_eq!(Message::Verack.to_vec().unwrap(), VERACK_WIRE_BYTES);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn verack_write_to_round_trip() {
+        let mut bytes = Vec::new();
+        Message::Verack.write_to(&mut bytes).await.unwrap();
+
+        let header = MessageHeader::decode(&mut Cursor::new(&bytes[..HEADER_LEN])).unwrap();
+        assert_eq!(header.command, VERACK_COMMAND);
+        assert_eq!(header.body_length, 0);
+
+        let mut body = Cursor::new(&bytes[HEADER_LEN..]);
+        assert_eq!(
+            Message::decode(header.command, &mut body).unwrap(),
+            Message::Verack
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn pings_equal_ignoring_nonce() {
+        let ping = Message::Ping(Nonce::default());
+        let other_ping = Message::Ping(Nonce::default());
+
+        assert_ne!(ping, other_ping);
+        assert!(ping.eq_ignoring_nonce(&other_ping));
+
+        // The message types still need to match.
+        assert!(!ping.eq_ignoring_nonce(&Message::Pong(Nonce::default())));
+    }
+
+    #[test]
+    #[ignore]
+    fn block_decode_exact_length() {
+        let mut bytes = Cursor::new(&BLOCK_TESTNET_GENESIS_BYTES[..]);
+
+        assert!(Message::decode(BLOCK_COMMAND, &mut bytes).is_ok());
+    }
+
+    #[test]
+    #[ignore]
+    fn block_decode_trailing_bytes() {
+        let mut block_bytes = BLOCK_TESTNET_GENESIS_BYTES.clone();
+        block_bytes.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        let mut bytes = Cursor::new(&block_bytes[..]);
+
+        let err = Message::decode(BLOCK_COMMAND, &mut bytes).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    #[ignore]
+    fn payloadless_decode_with_body() {
+        for command in [
+            VERACK_COMMAND,
+            GETADDR_COMMAND,
+            MEMPOOL_COMMAND,
+            SENDADDRV2_COMMAND,
+        ] {
+            assert!(Message::decode(command, &mut &[0u8; 0][..]).is_ok());
+
+            let err = Message::decode(command, &mut &[0u8; 4][..]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn addrv2_message_round_trip() {
+        use crate::protocol::payload::addrv2::NetworkAddrV2;
+
+        let addrv2 = Message::AddrV2(AddrV2::new(vec![NetworkAddrV2::new(
+            "[2001:db8::1]:8233".parse().unwrap(),
+        )]));
+
+        let bytes = addrv2.to_vec().unwrap();
+        let header = MessageHeader::decode(&mut Cursor::new(&bytes[..HEADER_LEN])).unwrap();
+        assert_eq!(header.command, ADDRV2_COMMAND);
+
+        let decoded =
+            Message::decode(header.command, &mut Cursor::new(&bytes[HEADER_LEN..])).unwrap();
+        // Timestamps are truncated to seconds on the wire.
+        match (decoded, addrv2) {
+            (Message::AddrV2(decoded), Message::AddrV2(original)) => {
+                assert_eq!(decoded.addrs[0].addr, original.addrs[0].addr);
+                assert_eq!(decoded.addrs[0].port, original.addrs[0].port);
+            }
+            (decoded, _) => panic!("expected AddrV2, got {decoded:?}"),
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn filter_messages_round_trip() {
+        let mut filter_load = FilterLoad::new(2, 0.01, 0, FilterLoad::BLOOM_UPDATE_ALL);
+        filter_load.insert(b"element");
+
+        for message in [
+            Message::FilterLoad(filter_load),
+            Message::FilterAdd(FilterAdd {
+                data: b"element".to_vec(),
+            }),
+            Message::FilterClear,
+        ] {
+            let bytes = message.to_vec().unwrap();
+            let header = MessageHeader::decode(&mut Cursor::new(&bytes[..HEADER_LEN])).unwrap();
+
+            let decoded =
+                Message::decode(header.command, &mut Cursor::new(&bytes[HEADER_LEN..])).unwrap();
+            assert_eq!(decoded, message);
+        }
+
+        // FilterClear has no payload.
+        assert!(Message::decode(FILTERCLEAR_COMMAND, &mut Cursor::new(&[0u8][..])).is_err());
+    }
+
+    #[test]
+    #[ignore]
+    fn network_magic_is_encoded() {
+        for network in [Network::Mainnet, Network::Testnet, Network::Regtest] {
+            let mut bytes = BytesMut::new();
+            Message::Verack
+                .encode_for_network(network, &mut bytes)
+                .unwrap();
+
+            let header = MessageHeader::decode(&mut Cursor::new(&bytes[..HEADER_LEN])).unwrap();
+            assert_eq!(header.magic, network.magic());
+            assert_eq!(network.to_string().parse::<Network>(), Ok(network));
+        }
+
+        assert_eq!("Regtest".parse::<Network>(), Ok(Network::Regtest));
+        assert!("signet".parse::<Network>().is_err());
+    }
+}
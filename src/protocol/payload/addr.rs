@@ -133,3 +133,30 @@ impl Codec for NetworkAddr {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::message::constants::MAX_ADDRS;
+
+    #[test]
+    #[ignore]
+    fn max_len_addr_encode_decode() {
+        let addrs = (0..MAX_ADDRS)
+            .map(|i| NetworkAddr::new(SocketAddr::from(([10, 0, (i / 256) as u8, i as u8], 8233))))
+            .collect();
+        let addr = Addr::new(addrs);
+
+        let mut bytes = Vec::new();
+        addr.encode(&mut bytes).unwrap();
+        let decoded = Addr::decode(&mut io::Cursor::new(&bytes[..])).unwrap();
+
+        // Timestamps are truncated to seconds on the wire.
+        assert_eq!(decoded.addrs.len(), MAX_ADDRS);
+        assert!(decoded
+            .iter()
+            .zip(addr.iter())
+            .all(|(decoded, original)| decoded.addr == original.addr
+                && decoded.services == original.services));
+    }
+}
@@ -0,0 +1,329 @@
+//! Network address types of the `AddrV2` message ([BIP 155](https://github.com/bitcoin/bips/blob/master/bip-0155.mediawiki)).
+
+use std::{
+    convert::TryInto,
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+use bytes::{Buf, BufMut};
+use time::OffsetDateTime;
+
+use crate::protocol::payload::{codec::Codec, read_n_bytes, read_short_timestamp, VarInt};
+
+/// The maximum length of an address in an `AddrV2` entry (512 bytes).
+pub const MAX_ADDRV2_ADDR_LEN: usize = 512;
+
+/// The OnionCat prefix, under which Tor v2 addresses are mapped to IPv6 addresses.
+const ONIONCAT_PREFIX: [u8; 6] = [0xfd, 0x87, 0xd8, 0x7e, 0xeb, 0x43];
+
+/// A list of network addresses of any network, used for peering.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AddrV2 {
+    pub addrs: Vec<NetworkAddrV2>,
+}
+
+impl AddrV2 {
+    /// Returns an `AddrV2` with no addresses.
+    pub fn empty() -> Self {
+        Self { addrs: Vec::new() }
+    }
+
+    /// Returns an `AddrV2` with the given addresses.
+    pub fn new(addrs: Vec<NetworkAddrV2>) -> Self {
+        AddrV2 { addrs }
+    }
+
+    /// Returns an iterator over the list of network addresses.
+    pub fn iter(&self) -> std::slice::Iter<NetworkAddrV2> {
+        self.addrs.iter()
+    }
+}
+
+impl Codec for AddrV2 {
+    fn encode<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
+        self.addrs.encode(buffer)
+    }
+
+    fn decode<B: Buf>(bytes: &mut B) -> io::Result<Self> {
+        Ok(Self::new(Vec::decode(bytes)?))
+    }
+}
+
+/// An address of one of the networks supported by `AddrV2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddrV2Address {
+    Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+    /// A Tor v2 onion service (deprecated, but still decodable).
+    TorV2([u8; 10]),
+    /// A Tor v3 onion service public key.
+    TorV3([u8; 32]),
+    /// The SHA256 hash of an I2P destination.
+    I2p([u8; 32]),
+    Cjdns(Ipv6Addr),
+    /// An address of a network this implementation doesn't know about, which peers are
+    /// expected to relay as is.
+    Unknown {
+        network_id: u8,
+        addr: Vec<u8>,
+    },
+}
+
+impl AddrV2Address {
+    /// Returns the BIP 155 network ID of the address.
+    pub fn network_id(&self) -> u8 {
+        match self {
+            Self::Ipv4(_) => 1,
+            Self::Ipv6(_) => 2,
+            Self::TorV2(_) => 3,
+            Self::TorV3(_) => 4,
+            Self::I2p(_) => 5,
+            Self::Cjdns(_) => 6,
+            Self::Unknown { network_id, .. } => *network_id,
+        }
+    }
+
+    /// Returns the address bytes as they appear on the wire.
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Ipv4(ip) => ip.octets().to_vec(),
+            Self::Ipv6(ip) | Self::Cjdns(ip) => ip.octets().to_vec(),
+            Self::TorV2(addr) => addr.to_vec(),
+            Self::TorV3(addr) | Self::I2p(addr) => addr.to_vec(),
+            Self::Unknown { addr, .. } => addr.clone(),
+        }
+    }
+
+    /// Builds the address of the known networks, rejecting lengths not matching the network.
+    fn from_bytes(network_id: u8, addr: Vec<u8>) -> io::Result<Self> {
+        let invalid_len = |addr: &[u8]| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "AddrV2 address of network {network_id} has an invalid length of {} byte(s)",
+                    addr.len()
+                ),
+            )
+        };
+
+        let address = match network_id {
+            1 => Self::Ipv4(
+                <[u8; 4]>::try_from(&addr[..])
+                    .map_err(|_| invalid_len(&addr))?
+                    .into(),
+            ),
+            2 => Self::Ipv6(
+                <[u8; 16]>::try_from(&addr[..])
+                    .map_err(|_| invalid_len(&addr))?
+                    .into(),
+            ),
+            3 => Self::TorV2(addr[..].try_into().map_err(|_| invalid_len(&addr))?),
+            4 => Self::TorV3(addr[..].try_into().map_err(|_| invalid_len(&addr))?),
+            5 => Self::I2p(addr[..].try_into().map_err(|_| invalid_len(&addr))?),
+            6 => Self::Cjdns(
+                <[u8; 16]>::try_from(&addr[..])
+                    .map_err(|_| invalid_len(&addr))?
+                    .into(),
+            ),
+            network_id => Self::Unknown { network_id, addr },
+        };
+
+        Ok(address)
+    }
+}
+
+/// A network address of an `AddrV2` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkAddrV2 {
+    /// The last time this address was seen.
+    pub last_seen: OffsetDateTime,
+    /// The services supported by this address.
+    pub services: u64,
+    /// The network address.
+    pub addr: AddrV2Address,
+    /// The port, unused by some networks.
+    pub port: u16,
+}
+
+impl NetworkAddrV2 {
+    /// Creates a new `NetworkAddrV2` from the given socket address,
+    /// `last_seen=OffsetDateTime::now_utc()`,
+    /// and `services=1` (only `NODE_NETWORK` is enabled).
+    pub fn new(addr: SocketAddr) -> Self {
+        let address = match addr.ip() {
+            IpAddr::V4(ip) => AddrV2Address::Ipv4(ip),
+            IpAddr::V6(ip) => AddrV2Address::Ipv6(ip),
+        };
+
+        Self {
+            last_seen: OffsetDateTime::now_utc(),
+            services: 1,
+            addr: address,
+            port: addr.port(),
+        }
+    }
+
+    /// Returns the address as a socket address, if it can be represented as one.
+    ///
+    /// Tor v2 addresses are mapped to IPv6 with OnionCat, Tor v3 and I2P addresses are too long
+    /// to be represented.
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        let ip = match &self.addr {
+            AddrV2Address::Ipv4(ip) => IpAddr::V4(*ip),
+            AddrV2Address::Ipv6(ip) | AddrV2Address::Cjdns(ip) => IpAddr::V6(*ip),
+            AddrV2Address::TorV2(addr) => {
+                let mut octets = [0u8; 16];
+                octets[..ONIONCAT_PREFIX.len()].copy_from_slice(&ONIONCAT_PREFIX);
+                octets[ONIONCAT_PREFIX.len()..].copy_from_slice(addr);
+                IpAddr::V6(octets.into())
+            }
+            _ => return None,
+        };
+
+        Some(SocketAddr::new(ip, self.port))
+    }
+}
+
+impl Codec for NetworkAddrV2 {
+    fn encode<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
+        let timestamp: u32 = self.last_seen.unix_timestamp().try_into().unwrap();
+        buffer.put_u32_le(timestamp);
+
+        VarInt(self.services as usize).encode(buffer)?;
+
+        let addr = self.addr.to_bytes();
+        if addr.len() > MAX_ADDRV2_ADDR_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "AddrV2 address is {} bytes long, the maximum is {MAX_ADDRV2_ADDR_LEN}",
+                    addr.len()
+                ),
+            ));
+        }
+        buffer.put_u8(self.addr.network_id());
+        VarInt(addr.len()).encode(buffer)?;
+        buffer.put_slice(&addr);
+
+        buffer.put_u16(self.port);
+
+        Ok(())
+    }
+
+    fn decode<B: Buf>(bytes: &mut B) -> io::Result<Self> {
+        let last_seen = read_short_timestamp(bytes)?;
+        let services = *VarInt::decode(bytes)? as u64;
+        let network_id = u8::from_le_bytes(read_n_bytes(bytes)?);
+
+        let len = *VarInt::decode(bytes)?;
+        if len > MAX_ADDRV2_ADDR_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("AddrV2 address is {len} bytes long, the maximum is {MAX_ADDRV2_ADDR_LEN}"),
+            ));
+        }
+        if bytes.remaining() < len {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+        let mut addr = vec![0u8; len];
+        bytes.copy_to_slice(&mut addr);
+
+        let port = u16::from_be_bytes(read_n_bytes(bytes)?);
+
+        Ok(Self {
+            last_seen,
+            services,
+            addr: AddrV2Address::from_bytes(network_id, addr)?,
+            port,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(addr: AddrV2Address) -> NetworkAddrV2 {
+        NetworkAddrV2 {
+            last_seen: OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap(),
+            services: 1,
+            addr,
+            port: 8233,
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn addrv2_round_trip_all_networks() {
+        let addrv2 = AddrV2::new(vec![
+            entry(AddrV2Address::Ipv4(Ipv4Addr::new(1, 2, 3, 4))),
+            entry(AddrV2Address::Ipv6("2001:db8::1".parse().unwrap())),
+            entry(AddrV2Address::TorV2([0xab; 10])),
+            entry(AddrV2Address::TorV3([0xcd; 32])),
+            entry(AddrV2Address::I2p([0xef; 32])),
+            entry(AddrV2Address::Cjdns("fc00::1".parse().unwrap())),
+            entry(AddrV2Address::Unknown {
+                network_id: 42,
+                addr: vec![1, 2, 3],
+            }),
+        ]);
+
+        let mut bytes = Vec::new();
+        addrv2.encode(&mut bytes).unwrap();
+
+        assert_eq!(
+            AddrV2::decode(&mut io::Cursor::new(&bytes[..])).unwrap(),
+            addrv2
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn addrv2_ipv4_wire_bytes() {
+        let mut bytes = Vec::new();
+        entry(AddrV2Address::Ipv4(Ipv4Addr::new(1, 2, 3, 4)))
+            .encode(&mut bytes)
+            .unwrap();
+
+        assert_eq!(
+            bytes,
+            [
+                0x00, 0x10, 0x5e, 0x5f, // time
+                0x01, // services
+                0x01, // network ID
+                0x04, 1, 2, 3, 4, // address
+                0x20, 0x29, // port
+            ]
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn addrv2_invalid_address_length_is_rejected() {
+        let mut bytes = Vec::new();
+        entry(AddrV2Address::Unknown {
+            network_id: 1,
+            addr: vec![1, 2, 3],
+        })
+        .encode(&mut bytes)
+        .unwrap();
+
+        let error = NetworkAddrV2::decode(&mut io::Cursor::new(&bytes[..])).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    #[ignore]
+    fn addrv2_socket_addrs() {
+        assert_eq!(
+            entry(AddrV2Address::Ipv4(Ipv4Addr::new(1, 2, 3, 4))).socket_addr(),
+            Some("1.2.3.4:8233".parse().unwrap())
+        );
+        assert_eq!(
+            entry(AddrV2Address::TorV2([0; 10])).socket_addr(),
+            Some("[fd87:d87e:eb43::]:8233".parse().unwrap())
+        );
+        assert_eq!(entry(AddrV2Address::TorV3([0; 32])).socket_addr(), None);
+    }
+}
@@ -6,7 +6,7 @@ use bytes::{Buf, BufMut};
 use sha2::Digest;
 
 use crate::protocol::payload::{
-    codec::Codec, inv::InvHash, read_n_bytes, Hash, ProtocolVersion, Tx, VarInt,
+    codec::Codec, inv::InvHash, read_n_bytes, Hash, Inv, ProtocolVersion, Tx, VarInt,
 };
 
 /// The locator hash object, used to communicate chain state.
@@ -37,6 +37,100 @@ impl LocatorHashes {
     pub fn empty() -> Self {
         Self::new(Vec::new(), Hash::zeroed())
     }
+
+    /// Returns a `LocatorHashes` instance requesting everything after the block at `height`,
+    /// where `blocks` is the chain starting from the genesis block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `height` is out of range for `blocks`.
+    pub fn after_height(blocks: &[Block], height: usize) -> Self {
+        let hash = blocks[height].double_sha256().unwrap();
+        Self::new(vec![hash], Hash::zeroed())
+    }
+}
+
+/// Returns the [`Inv`] a correct node replies with to a `GetBlocks` whose locator resolves to
+/// `locator_tip` and which has no stop hash, where `blocks` is the node's chain starting from the
+/// genesis block.
+///
+/// The inventory lists the blocks following `locator_tip`, in chain order and capped to `cap`
+/// entries. It is empty if `locator_tip` isn't part of `blocks`.
+pub fn expected_inv_after(blocks: &[Block], locator_tip: Hash, cap: usize) -> Inv {
+    let inventory = blocks
+        .iter()
+        .position(|block| block.double_sha256().unwrap() == locator_tip)
+        .map(|tip| {
+            blocks[tip + 1..]
+                .iter()
+                .take(cap)
+                .map(|block| block.inv_hash())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Inv::new(inventory)
+}
+
+/// Returns the [`Headers`] a correct node replies with to a `GetHeaders` whose locator resolves
+/// to `locator_tip`, where `blocks` is the node's chain starting from the genesis block.
+///
+/// The headers of the blocks following `locator_tip` are listed in chain order, up to and
+/// including the block matching `hash_stop` (if any) and capped to `cap` entries. The list is
+/// empty if `locator_tip` isn't part of `blocks`.
+pub fn expected_headers_after(
+    blocks: &[Block],
+    locator_tip: Hash,
+    hash_stop: Hash,
+    cap: usize,
+) -> Headers {
+    let tip = match blocks
+        .iter()
+        .position(|block| block.double_sha256().unwrap() == locator_tip)
+    {
+        Some(tip) => tip,
+        None => return Headers::empty(),
+    };
+
+    let mut headers = Vec::new();
+    for block in blocks[tip + 1..].iter().take(cap) {
+        headers.push(block.header.clone());
+        if block.double_sha256().unwrap() == hash_stop {
+            break;
+        }
+    }
+
+    Headers::new(headers)
+}
+
+/// Returns a chain of `len` headers, starting with those of `blocks` and extended with headers
+/// building on the last one.
+///
+/// The extension copies the tip's header, linking each header to the previous one and bumping
+/// the timestamp, so the chain is well-formed but its proof of work isn't valid. It's meant for
+/// exercising code paths which don't validate headers, e.g. message size limits; a node is
+/// expected to reject the extension.
+///
+/// # Panics
+///
+/// Panics if `blocks` is empty.
+pub fn header_chain(blocks: &[Block], len: usize) -> Vec<Header> {
+    let mut headers = blocks
+        .iter()
+        .take(len)
+        .map(|block| block.header.clone())
+        .collect::<Vec<_>>();
+
+    let mut tip = blocks.last().expect("no blocks to build on").header.clone();
+    while headers.len() < len {
+        let mut header = tip.clone();
+        header.prev_block = tip.double_sha256().unwrap();
+        header.timestamp += 1;
+        headers.push(header.clone());
+        tip = header;
+    }
+
+    headers
 }
 
 impl Codec for LocatorHashes {
@@ -76,70 +170,85 @@ impl Block {
         self.header.double_sha256()
     }
 
+    /// Decodes a block from the bytes of a test vector, which must hold exactly one block.
+    pub fn from_vector(bytes: &[u8]) -> io::Result<Self> {
+        let mut cursor = std::io::Cursor::new(bytes);
+        let block = Block::decode(&mut cursor)?;
+
+        if cursor.has_remaining() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} trailing bytes after the block", cursor.remaining()),
+            ));
+        }
+
+        Ok(block)
+    }
+
     /// Creates the testnet genesis block.
     pub fn testnet_genesis() -> Self {
-        let mut cursor = std::io::Cursor::new(&crate::vectors::BLOCK_TESTNET_GENESIS_BYTES[..]);
-        Block::decode(&mut cursor).unwrap()
+        Self::from_vector(&crate::vectors::BLOCK_TESTNET_GENESIS_BYTES)
+            .expect("malformed block test vector")
     }
 
     /// Creates the testnet block at height 1.
     pub fn testnet_1() -> Self {
-        let mut cursor = std::io::Cursor::new(&crate::vectors::BLOCK_TESTNET_0_000_001_BYTES[..]);
-        Block::decode(&mut cursor).unwrap()
+        Self::from_vector(&crate::vectors::BLOCK_TESTNET_0_000_001_BYTES)
+            .expect("malformed block test vector")
     }
 
     /// Creates the testnet block at height 2.
     pub fn testnet_2() -> Self {
-        let mut cursor = std::io::Cursor::new(&crate::vectors::BLOCK_TESTNET_0_000_002_BYTES[..]);
-        Block::decode(&mut cursor).unwrap()
+        Self::from_vector(&crate::vectors::BLOCK_TESTNET_0_000_002_BYTES)
+            .expect("malformed block test vector")
     }
 
     /// Creates the testnet block at height 3.
     pub fn testnet_3() -> Self {
-        let mut cursor = std::io::Cursor::new(&crate::vectors::BLOCK_TESTNET_0_000_003_BYTES[..]);
-        Block::decode(&mut cursor).unwrap()
+        Self::from_vector(&crate::vectors::BLOCK_TESTNET_0_000_003_BYTES)
+            .expect("malformed block test vector")
     }
 
     /// Creates the testnet block at height 4.
     pub fn testnet_4() -> Self {
-        let mut cursor = std::io::Cursor::new(&crate::vectors::BLOCK_TESTNET_0_000_004_BYTES[..]);
-        Block::decode(&mut cursor).unwrap()
+        Self::from_vector(&crate::vectors::BLOCK_TESTNET_0_000_004_BYTES)
+            .expect("malformed block test vector")
     }
 
     /// Creates the testnet block at height 5.
     pub fn testnet_5() -> Self {
-        let mut cursor = std::io::Cursor::new(&crate::vectors::BLOCK_TESTNET_0_000_005_BYTES[..]);
-        Block::decode(&mut cursor).unwrap()
+        Self::from_vector(&crate::vectors::BLOCK_TESTNET_0_000_005_BYTES)
+            .expect("malformed block test vector")
     }
 
     /// Creates the testnet block at height 6.
     pub fn testnet_6() -> Self {
-        let mut cursor = std::io::Cursor::new(&crate::vectors::BLOCK_TESTNET_0_000_006_BYTES[..]);
-        Block::decode(&mut cursor).unwrap()
+        Self::from_vector(&crate::vectors::BLOCK_TESTNET_0_000_006_BYTES)
+            .expect("malformed block test vector")
     }
 
     /// Creates the testnet block at height 7.
     pub fn testnet_7() -> Self {
-        let mut cursor = std::io::Cursor::new(&crate::vectors::BLOCK_TESTNET_0_000_007_BYTES[..]);
-        Block::decode(&mut cursor).unwrap()
+        Self::from_vector(&crate::vectors::BLOCK_TESTNET_0_000_007_BYTES)
+            .expect("malformed block test vector")
     }
 
     /// Creates the testnet block at height 8.
     pub fn testnet_8() -> Self {
-        let mut cursor = std::io::Cursor::new(&crate::vectors::BLOCK_TESTNET_0_000_008_BYTES[..]);
-        Block::decode(&mut cursor).unwrap()
+        Self::from_vector(&crate::vectors::BLOCK_TESTNET_0_000_008_BYTES)
+            .expect("malformed block test vector")
     }
 
     /// Creates the testnet block at height 9.
     pub fn testnet_9() -> Self {
-        let mut cursor = std::io::Cursor::new(&crate::vectors::BLOCK_TESTNET_0_000_009_BYTES[..]);
-        Block::decode(&mut cursor).unwrap()
+        Self::from_vector(&crate::vectors::BLOCK_TESTNET_0_000_009_BYTES)
+            .expect("malformed block test vector")
     }
 
     /// Creates the testnet block at height 10.
     pub fn testnet_10() -> Self {
-        let mut cursor = std::io::Cursor::new(&crate::vectors::BLOCK_TESTNET_0_000_010_BYTES[..]);
-        Block::decode(&mut cursor).unwrap()
+        Self::from_vector(&crate::vectors::BLOCK_TESTNET_0_000_010_BYTES)
+            .expect("malformed block test vector")
     }
 
     /// Returns the first 11 testnet blocks.
@@ -196,6 +305,24 @@ impl Headers {
             headers: Vec::new(),
         }
     }
+
+    /// Encodes the headers, declaring `tx_count` transactions for each of them instead of 0.
+    ///
+    /// A non-zero count is invalid in a `Headers` message, so this is only useful for negative
+    /// testing; use [`Codec::encode`] otherwise.
+    pub fn encode_with_tx_count<B: BufMut>(
+        &self,
+        buffer: &mut B,
+        tx_count: usize,
+    ) -> io::Result<()> {
+        VarInt(self.headers.len()).encode(buffer)?;
+        for header in &self.headers {
+            header.encode_without_tx_count(buffer)?;
+            VarInt(tx_count).encode(buffer)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl Codec for Headers {
@@ -277,7 +404,7 @@ impl Header {
 
     /// Encodes [Header] without the VarInt `tx_count=0`. This is useful for [Block] encoding which requires
     /// `tx_count=N`, as well as Hash calculation as it excludes `tx_count`.
-    fn encode_without_tx_count<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
+    pub(crate) fn encode_without_tx_count<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
         self.version.encode(buffer)?;
         self.prev_block.encode(buffer)?;
         self.merkle_root.encode(buffer)?;
@@ -297,7 +424,7 @@ impl Header {
     /// requires the value to determine the number of transactions which follow in the body. [Header] on the
     /// otherhand requires that this value be 0. This gets asserted in Header::encode, making it unsuiteable
     /// for use by [Block].
-    fn decode_without_tx_count<B: Buf>(bytes: &mut B) -> io::Result<Self> {
+    pub(crate) fn decode_without_tx_count<B: Buf>(bytes: &mut B) -> io::Result<Self> {
         let version = ProtocolVersion::decode(bytes)?;
         let prev_block = Hash::decode(bytes)?;
         let merkle_root = Hash::decode(bytes)?;
@@ -332,7 +459,136 @@ mod tests {
     use hex::FromHex;
 
     use super::*;
-    use crate::vectors::*;
+    use crate::{protocol::message::constants::MAX_GETHEADERS_RESULTS, vectors::*};
+
+    #[test]
+    #[ignore]
+    fn locator_after_height() {
+        let blocks = Block::initial_testnet_blocks();
+
+        for height in 0..blocks.len() {
+            let locator = LocatorHashes::after_height(&blocks, height);
+
+            assert_eq!(
+                locator.block_locator_hashes,
+                vec![blocks[height].double_sha256().unwrap()]
+            );
+            assert_eq!(locator.hash_stop, Hash::zeroed());
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn expected_inv_after_testnet_blocks() {
+        let blocks = Block::initial_testnet_blocks();
+        let hash = |height: usize| blocks[height].double_sha256().unwrap();
+        let inv_of = |range: std::ops::Range<usize>| {
+            Inv::new(blocks[range].iter().map(|block| block.inv_hash()).collect())
+        };
+
+        assert_eq!(expected_inv_after(&blocks, hash(3), 500), inv_of(4..11));
+        assert_eq!(expected_inv_after(&blocks, hash(3), 2), inv_of(4..6));
+        assert_eq!(expected_inv_after(&blocks, hash(10), 500), Inv::empty());
+        assert_eq!(
+            expected_inv_after(&blocks, Hash::new([19; 32]), 500),
+            Inv::empty()
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn expected_headers_after_testnet_blocks() {
+        let blocks = Block::initial_testnet_blocks();
+        let hash = |height: usize| blocks[height].double_sha256().unwrap();
+        let headers_of = |range: std::ops::Range<usize>| {
+            Headers::new(
+                blocks[range]
+                    .iter()
+                    .map(|block| block.header.clone())
+                    .collect(),
+            )
+        };
+
+        let no_stop = Hash::zeroed();
+        assert_eq!(
+            expected_headers_after(&blocks, hash(3), no_stop, 160),
+            headers_of(4..11)
+        );
+        assert_eq!(
+            expected_headers_after(&blocks, hash(3), no_stop, 2),
+            headers_of(4..6)
+        );
+        // The stop hash is included.
+        assert_eq!(
+            expected_headers_after(&blocks, hash(3), hash(7), 160),
+            headers_of(4..8)
+        );
+        assert_eq!(
+            expected_headers_after(&blocks, hash(10), no_stop, 160),
+            Headers::empty()
+        );
+        assert_eq!(
+            expected_headers_after(&blocks, Hash::new([19; 32]), no_stop, 160),
+            Headers::empty()
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn header_chain_extends_the_blocks() {
+        let blocks = Block::initial_testnet_blocks();
+
+        let short = header_chain(&blocks, 5);
+        assert_eq!(short.len(), 5);
+        assert_eq!(short[4], blocks[4].header);
+
+        let chain = header_chain(&blocks, 200);
+        assert_eq!(chain.len(), 200);
+        assert_eq!(chain[10], blocks[10].header);
+        for pair in chain.windows(2) {
+            assert_eq!(pair[1].prev_block, pair[0].double_sha256().unwrap());
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn header_chain_beyond_the_batch_limit_round_trip() {
+        let len = MAX_GETHEADERS_RESULTS + 40;
+        let headers = Headers::new(header_chain(&Block::initial_testnet_blocks(), len));
+
+        let mut bytes = Vec::new();
+        headers.encode(&mut bytes).unwrap();
+        let decoded = Headers::decode(&mut Cursor::new(&bytes[..])).unwrap();
+
+        assert_eq!(decoded.headers.len(), len);
+        assert_eq!(decoded, headers);
+    }
+
+    #[test]
+    #[ignore]
+    fn headers_with_tx_count() {
+        let headers = Headers::new(
+            Block::initial_testnet_blocks()
+                .into_iter()
+                .map(|block| block.header)
+                .collect(),
+        );
+
+        // A zero count matches the regular encoding.
+        let mut regular = Vec::new();
+        headers.encode(&mut regular).unwrap();
+        let mut zero_count = Vec::new();
+        headers.encode_with_tx_count(&mut zero_count, 0).unwrap();
+        assert_eq!(regular, zero_count);
+
+        // A non-zero count is rejected on decoding.
+        let mut non_zero_count = Vec::new();
+        headers
+            .encode_with_tx_count(&mut non_zero_count, 1)
+            .unwrap();
+        let error = Headers::decode(&mut Cursor::new(&non_zero_count[..])).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
 
     #[test]
     #[ignore]
@@ -0,0 +1,325 @@
+//! Compact block relay types, see [BIP 152](https://github.com/bitcoin/bips/blob/master/bip-0152.mediawiki).
+//!
+//! Neither zcashd nor zebra implement compact block relay, these types are used to check how
+//! the nodes handle the messages.
+
+use std::io;
+
+use bytes::{Buf, BufMut};
+
+use crate::protocol::payload::{block::Header, codec::Codec, read_n_bytes, Hash, Tx, VarInt};
+
+/// The highest transaction index in compact block messages.
+pub const MAX_INDEX: u64 = u16::MAX as u64;
+
+/// A request to announce new blocks with `CmpctBlock` messages.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SendCmpct {
+    /// Whether new blocks should be announced with a `CmpctBlock` rather than an `Inv` or
+    /// `Headers` (high-bandwidth mode).
+    pub announce: bool,
+    /// The compact block protocol version.
+    pub version: u64,
+}
+
+impl Codec for SendCmpct {
+    fn encode<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
+        buffer.put_u8(self.announce.into());
+        buffer.put_u64_le(self.version);
+
+        Ok(())
+    }
+
+    fn decode<B: Buf>(bytes: &mut B) -> io::Result<Self> {
+        let announce = u8::from_le_bytes(read_n_bytes(bytes)?) != 0;
+        let version = u64::from_le_bytes(read_n_bytes(bytes)?);
+
+        Ok(Self { announce, version })
+    }
+}
+
+/// A transaction ID shortened to 6 bytes with SipHash, keyed with the block header and nonce.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ShortTxId(pub [u8; 6]);
+
+impl Codec for ShortTxId {
+    fn encode<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
+        buffer.put_slice(&self.0);
+
+        Ok(())
+    }
+
+    fn decode<B: Buf>(bytes: &mut B) -> io::Result<Self> {
+        Ok(Self(read_n_bytes(bytes)?))
+    }
+}
+
+/// A transaction sent along with a compact block, which the receiver likely doesn't have.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PrefilledTx {
+    /// The index of the transaction in the block.
+    pub index: u64,
+    pub tx: Tx,
+}
+
+/// A block relayed as its header and the short IDs of its transactions.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CmpctBlock {
+    pub header: Header,
+    /// The nonce keying the short IDs.
+    pub nonce: u64,
+    pub short_ids: Vec<ShortTxId>,
+    /// The prefilled transactions, in increasing index order.
+    pub prefilled_txs: Vec<PrefilledTx>,
+}
+
+impl Codec for CmpctBlock {
+    fn encode<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
+        self.header.encode_without_tx_count(buffer)?;
+        buffer.put_u64_le(self.nonce);
+        self.short_ids.encode(buffer)?;
+
+        let indexes = self
+            .prefilled_txs
+            .iter()
+            .map(|prefilled| prefilled.index)
+            .collect::<Vec<_>>();
+        VarInt(self.prefilled_txs.len()).encode(buffer)?;
+        for (diff, prefilled) in differential_indexes(&indexes)?
+            .into_iter()
+            .zip(&self.prefilled_txs)
+        {
+            VarInt(diff).encode(buffer)?;
+            prefilled.tx.encode(buffer)?;
+        }
+
+        Ok(())
+    }
+
+    fn decode<B: Buf>(bytes: &mut B) -> io::Result<Self> {
+        let header = Header::decode_without_tx_count(bytes)?;
+        let nonce = u64::from_le_bytes(read_n_bytes(bytes)?);
+        let short_ids = Vec::decode(bytes)?;
+
+        let len = *VarInt::decode(bytes)?;
+        let mut prefilled_txs = Vec::new();
+        let mut next_index = 0;
+        for _ in 0..len {
+            let index = absolute_index(next_index, *VarInt::decode(bytes)?)?;
+            let tx = Tx::decode(bytes)?;
+            prefilled_txs.push(PrefilledTx { index, tx });
+            next_index = index + 1;
+        }
+
+        Ok(Self {
+            header,
+            nonce,
+            short_ids,
+            prefilled_txs,
+        })
+    }
+}
+
+/// A request for some of a block's transactions, following a `CmpctBlock`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GetBlockTxn {
+    pub block_hash: Hash,
+    /// The indexes of the requested transactions in the block, in increasing order.
+    pub indexes: Vec<u64>,
+}
+
+impl Codec for GetBlockTxn {
+    fn encode<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
+        self.block_hash.encode(buffer)?;
+
+        VarInt(self.indexes.len()).encode(buffer)?;
+        for diff in differential_indexes(&self.indexes)? {
+            VarInt(diff).encode(buffer)?;
+        }
+
+        Ok(())
+    }
+
+    fn decode<B: Buf>(bytes: &mut B) -> io::Result<Self> {
+        let block_hash = Hash::decode(bytes)?;
+
+        let len = *VarInt::decode(bytes)?;
+        // Each index takes at least a byte, don't allocate for more than there could be.
+        if len > bytes.remaining() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "GetBlockTxn declares {len} indexes, but only {} byte(s) remain",
+                    bytes.remaining()
+                ),
+            ));
+        }
+
+        let mut indexes = Vec::with_capacity(len);
+        let mut next_index = 0;
+        for _ in 0..len {
+            let index = absolute_index(next_index, *VarInt::decode(bytes)?)?;
+            indexes.push(index);
+            next_index = index + 1;
+        }
+
+        Ok(Self {
+            block_hash,
+            indexes,
+        })
+    }
+}
+
+/// Some of a block's transactions, answering a `GetBlockTxn`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BlockTxn {
+    pub block_hash: Hash,
+    pub txs: Vec<Tx>,
+}
+
+impl Codec for BlockTxn {
+    fn encode<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
+        self.block_hash.encode(buffer)?;
+        self.txs.encode(buffer)
+    }
+
+    fn decode<B: Buf>(bytes: &mut B) -> io::Result<Self> {
+        let block_hash = Hash::decode(bytes)?;
+        let txs = Vec::decode(bytes)?;
+
+        Ok(Self { block_hash, txs })
+    }
+}
+
+/// Returns the indexes encoded as the difference to the previous index plus one, as they appear
+/// on the wire.
+fn differential_indexes(indexes: &[u64]) -> io::Result<Vec<usize>> {
+    let mut next_index = 0;
+    indexes
+        .iter()
+        .map(|&index| {
+            if index < next_index || index > MAX_INDEX {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("compact block indexes must be strictly increasing, up to {MAX_INDEX}"),
+                ));
+            }
+            let diff = index - next_index;
+            next_index = index + 1;
+            Ok(diff as usize)
+        })
+        .collect()
+}
+
+/// Returns the index following `next_index` by the wire difference `diff`.
+///
+/// Like in Bitcoin Core, indexes beyond [`MAX_INDEX`] are rejected.
+fn absolute_index(next_index: u64, diff: usize) -> io::Result<u64> {
+    match next_index.checked_add(diff as u64) {
+        Some(index) if index <= MAX_INDEX => Ok(index),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("compact block index exceeds {MAX_INDEX}"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::protocol::payload::block::Block;
+
+    #[test]
+    #[ignore]
+    fn sendcmpct_wire_bytes() {
+        let mut bytes = Vec::new();
+        SendCmpct {
+            announce: true,
+            version: 1,
+        }
+        .encode(&mut bytes)
+        .unwrap();
+
+        assert_eq!(bytes, [1, 1, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    #[ignore]
+    fn cmpctblock_round_trip() {
+        let block = Block::testnet_1();
+        let cmpctblock = CmpctBlock {
+            header: block.header.clone(),
+            nonce: 42,
+            short_ids: vec![ShortTxId([1, 2, 3, 4, 5, 6])],
+            prefilled_txs: vec![
+                PrefilledTx {
+                    index: 0,
+                    tx: block.txs[0].clone(),
+                },
+                PrefilledTx {
+                    index: 2,
+                    tx: block.txs[0].clone(),
+                },
+            ],
+        };
+
+        let mut bytes = Vec::new();
+        cmpctblock.encode(&mut bytes).unwrap();
+
+        assert_eq!(
+            CmpctBlock::decode(&mut Cursor::new(&bytes[..])).unwrap(),
+            cmpctblock
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn getblocktxn_indexes_are_differential() {
+        let getblocktxn = GetBlockTxn {
+            block_hash: Hash::zeroed(),
+            indexes: vec![0, 1, 5],
+        };
+
+        let mut bytes = Vec::new();
+        getblocktxn.encode(&mut bytes).unwrap();
+        assert_eq!(bytes[32..], [3, 0, 0, 3]);
+
+        assert_eq!(
+            GetBlockTxn::decode(&mut Cursor::new(&bytes[..])).unwrap(),
+            getblocktxn
+        );
+
+        // Unordered indexes can't be encoded.
+        let unordered = GetBlockTxn {
+            block_hash: Hash::zeroed(),
+            indexes: vec![5, 1],
+        };
+        let error = unordered.encode(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    #[ignore]
+    fn getblocktxn_index_beyond_max_is_rejected() {
+        let mut bytes = vec![0u8; 32];
+        VarInt(2).encode(&mut bytes).unwrap();
+        VarInt(MAX_INDEX as usize).encode(&mut bytes).unwrap();
+        VarInt(0).encode(&mut bytes).unwrap();
+
+        let error = GetBlockTxn::decode(&mut Cursor::new(&bytes[..])).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    #[ignore]
+    fn getblocktxn_oversized_count_is_rejected() {
+        let mut bytes = vec![0u8; 32];
+        VarInt(1000).encode(&mut bytes).unwrap();
+        bytes.push(0);
+
+        let error = GetBlockTxn::decode(&mut Cursor::new(&bytes[..])).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -1,10 +1,13 @@
 //! Bloom filtering types, see [BIP 37](https://github.com/bitcoin/bips/blob/master/bip-0037.mediawiki).
 
-use std::io::{self, Cursor, ErrorKind, Read};
+use std::{
+    f64::consts::LN_2,
+    io::{self, ErrorKind},
+};
 
 use bytes::{Buf, BufMut};
 
-use crate::protocol::payload::{codec::Codec, read_n_bytes};
+use crate::protocol::payload::{codec::Codec, read_n_bytes, VarInt};
 
 /// A modification to an existing filter.
 #[derive(Debug, PartialEq, Eq, Default, Clone)]
@@ -26,8 +29,105 @@ pub struct FilterLoad {
     pub flags: u8,
 }
 
+/// The maximum size of a filter, in bytes.
+const MAX_FILTER_BYTES: usize = 36_000;
+/// The maximum size of a data element added to a filter, in bytes.
+const MAX_FILTER_ADD_BYTES: usize = 520;
+/// The maximum number of hash functions of a filter.
+const MAX_HASH_FNS: u32 = 50;
+/// The multiplier of the hash function index in the murmur3 seed.
+const HASH_FN_SEED_MULTIPLIER: u32 = 0xFBA4_C795;
+
+impl FilterLoad {
+    /// Matched outpoints are never added to the filter.
+    pub const BLOOM_UPDATE_NONE: u8 = 0;
+    /// The outpoints of all matched outputs are added to the filter.
+    pub const BLOOM_UPDATE_ALL: u8 = 1;
+    /// The outpoints of matched pay-to-pubkey and multisig outputs are added to the filter.
+    pub const BLOOM_UPDATE_P2PUBKEY_ONLY: u8 = 2;
+
+    /// Returns an empty filter sized for `num_elements` entries with the given false positive
+    /// rate, following the sizing of the reference implementation.
+    pub fn new(num_elements: usize, fp_rate: f64, tweak: u32, flags: u8) -> Self {
+        let num_elements = num_elements.max(1) as f64;
+        let num_bits = (-1.0 / (LN_2 * LN_2) * num_elements * fp_rate.ln()) as usize;
+        let num_bytes = (num_bits.min(MAX_FILTER_BYTES * 8) / 8).max(1);
+        let hash_fn_count = ((num_bytes * 8) as f64 / num_elements * LN_2) as u32;
+
+        Self {
+            filter: vec![0; num_bytes],
+            hash_fn_count: hash_fn_count.min(MAX_HASH_FNS),
+            tweak,
+            flags,
+        }
+    }
+
+    /// Adds the data element to the filter.
+    pub fn insert(&mut self, data: &[u8]) {
+        for bit in self.bit_indexes(data) {
+            self.filter[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+
+    /// Returns whether the filter matches the data element, which may be a false positive.
+    pub fn contains(&self, data: &[u8]) -> bool {
+        !self.filter.is_empty()
+            && self
+                .bit_indexes(data)
+                .all(|bit| self.filter[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    fn bit_indexes<'a>(&'a self, data: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let num_bits = self.filter.len() * 8;
+
+        (0..self.hash_fn_count).map(move |i| {
+            let seed = i
+                .wrapping_mul(HASH_FN_SEED_MULTIPLIER)
+                .wrapping_add(self.tweak);
+            murmur3(seed, data) as usize % num_bits
+        })
+    }
+}
+
+/// The 32-bit x86 variant of MurmurHash3, which BIP 37 filters are based on.
+fn murmur3(seed: u32, data: &[u8]) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+
+    let mix = |k: u32| k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+
+    let mut hash = seed;
+    let blocks = data.chunks_exact(4);
+    let tail = blocks.remainder();
+    for block in blocks {
+        hash ^= mix(u32::from_le_bytes(block.try_into().unwrap()));
+        hash = hash
+            .rotate_left(13)
+            .wrapping_mul(5)
+            .wrapping_add(0xe654_6b64);
+    }
+
+    if !tail.is_empty() {
+        let k = tail
+            .iter()
+            .enumerate()
+            .fold(0u32, |k, (i, byte)| k | (*byte as u32) << (8 * i));
+        hash ^= mix(k);
+    }
+
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^= hash >> 16;
+
+    hash
+}
+
 impl Codec for FilterAdd {
     fn encode<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
+        VarInt::new(self.data.len()).encode(buffer)?;
         buffer.put_slice(&self.data);
 
         Ok(())
@@ -37,25 +137,25 @@ impl Codec for FilterAdd {
     where
         Self: Sized,
     {
-        let mut data = Vec::new();
-        bytes.reader().read_to_end(&mut data)?;
-
-        if data.len() > 520 {
+        let data_len = *VarInt::decode(bytes)?;
+        if data_len > MAX_FILTER_ADD_BYTES {
             return Err(io::Error::new(
                 ErrorKind::InvalidData,
                 format!(
-                    "Maximum FilterAdd data length is 520, but got {}",
-                    data.len()
+                    "Maximum FilterAdd data length is {MAX_FILTER_ADD_BYTES}, but got {data_len}"
                 ),
             ));
         }
 
-        Ok(Self { data })
+        Ok(Self {
+            data: read_vec(bytes, data_len)?,
+        })
     }
 }
 
 impl Codec for FilterLoad {
     fn encode<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
+        VarInt::new(self.filter.len()).encode(buffer)?;
         buffer.put_slice(&self.filter);
         buffer.put_u32_le(self.hash_fn_count);
         buffer.put_u32_le(self.tweak);
@@ -68,38 +168,18 @@ impl Codec for FilterLoad {
     where
         Self: Sized,
     {
-        // Have to read to end in order to get size of filter.
-        // (we only know the final 9 bytes are reserved for the other fields)
-        let mut buffer = Vec::new();
-        let bytes_read = bytes.reader().read_to_end(&mut buffer)?;
-
-        const NON_FILTER_BYTES: usize = 4 + 4 + 1;
-        if bytes_read < NON_FILTER_BYTES {
-            return Err(io::Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "Minimum FilterLoad bytes required is {NON_FILTER_BYTES} but only got {bytes_read}"
-                ),
-            ));
-        }
-        let filter_bytes = bytes_read - NON_FILTER_BYTES;
-        // maximum filter size is 36k bytes
-        const MAX_FILTER_BYTES: usize = 36_000;
-        if filter_bytes > MAX_FILTER_BYTES {
+        let filter_len = *VarInt::decode(bytes)?;
+        if filter_len > MAX_FILTER_BYTES {
             return Err(io::Error::new(
                 ErrorKind::InvalidData,
-                format!("Maximum filter bytes is {MAX_FILTER_BYTES} but got {filter_bytes}"),
+                format!("Maximum filter bytes is {MAX_FILTER_BYTES} but got {filter_len}"),
             ));
         }
 
-        let mut cursor = Cursor::new(&buffer[..]);
-
-        let mut filter = vec![0; filter_bytes];
-        cursor.read_exact(&mut filter)?;
-
-        let hash_fn_count = u32::from_le_bytes(read_n_bytes(&mut cursor)?);
-        let tweak = u32::from_le_bytes(read_n_bytes(&mut cursor)?);
-        let flags = u8::from_le_bytes(read_n_bytes(&mut cursor)?);
+        let filter = read_vec(bytes, filter_len)?;
+        let hash_fn_count = u32::from_le_bytes(read_n_bytes(bytes)?);
+        let tweak = u32::from_le_bytes(read_n_bytes(bytes)?);
+        let flags = u8::from_le_bytes(read_n_bytes(bytes)?);
 
         Ok(Self {
             filter,
@@ -110,14 +190,29 @@ impl Codec for FilterLoad {
     }
 }
 
+/// Reads `len` bytes from the bytes.
+fn read_vec<B: Buf>(bytes: &mut B, len: usize) -> io::Result<Vec<u8>> {
+    if bytes.remaining() < len {
+        return Err(ErrorKind::InvalidData.into());
+    }
+
+    let mut buffer = vec![0; len];
+    bytes.copy_to_slice(&mut buffer);
+
+    Ok(buffer)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io::Cursor;
+
     use super::*;
 
     #[test]
     #[ignore]
     fn filter_load_roundtrip() {
-        let original = FilterLoad::default();
+        let mut original = FilterLoad::new(10, 0.001, 7, FilterLoad::BLOOM_UPDATE_NONE);
+        original.insert(b"element");
 
         let mut buffer = Vec::new();
         original.encode(&mut buffer).unwrap();
@@ -127,10 +222,39 @@ mod tests {
         assert_eq!(decoded, original);
     }
 
+    #[test]
+    #[ignore]
+    fn filter_matches_reference_vectors() {
+        // The vectors of the reference implementation's bloom filter tests.
+        let elements = [
+            hex::decode("99108ad8ed9bb6274d3980bab5a85c048f0950c8").unwrap(),
+            hex::decode("b5a2c786d9ef4658287ced5914b37a1b4aa32eee").unwrap(),
+            hex::decode("b9300670b4c5366e95b2699e8b18bc75e5f729c5").unwrap(),
+        ];
+
+        for (tweak, expected) in [
+            (0, "03614e9b050000000000000001"),
+            (2_147_483_649, "03ce4299050000000100008001"),
+        ] {
+            let mut filter = FilterLoad::new(3, 0.01, tweak, FilterLoad::BLOOM_UPDATE_ALL);
+            assert!(!filter.contains(&elements[0]));
+            for element in &elements {
+                filter.insert(element);
+            }
+            assert!(elements.iter().all(|element| filter.contains(element)));
+
+            let mut buffer = Vec::new();
+            filter.encode(&mut buffer).unwrap();
+            assert_eq!(hex::encode(buffer), expected);
+        }
+    }
+
     #[test]
     #[ignore]
     fn filter_add_roundtrip() {
-        let original = FilterAdd::default();
+        let original = FilterAdd {
+            data: b"element".to_vec(),
+        };
 
         let mut buffer = Vec::new();
         original.encode(&mut buffer).unwrap();
@@ -1,6 +1,6 @@
 //! Inventory vector types.
 
-use std::io;
+use std::{collections::HashSet, io};
 
 use bytes::{Buf, BufMut};
 
@@ -22,6 +22,79 @@ impl Inv {
     pub fn empty() -> Self {
         Self::new(Vec::new())
     }
+
+    /// Returns the hashes of all [`InvHash::Block`] entries, in order.
+    pub fn block_hashes(&self) -> Vec<Hash> {
+        self.by_kind().blocks
+    }
+
+    /// Returns the hashes of all [`InvHash::Tx`] entries, in order.
+    pub fn tx_hashes(&self) -> Vec<Hash> {
+        self.by_kind().txs
+    }
+
+    /// Returns the inventory entries grouped by their kind, preserving their relative order.
+    pub fn by_kind(&self) -> InvByKind {
+        let mut grouped = InvByKind::default();
+
+        for inv_hash in &self.inventory {
+            match inv_hash {
+                InvHash::Error => grouped.errors += 1,
+                InvHash::Tx(hash) => grouped.txs.push(*hash),
+                InvHash::Block(hash) => grouped.blocks.push(*hash),
+                InvHash::FilteredBlock(hash) => grouped.filtered_blocks.push(*hash),
+                InvHash::MsgWtx(wtx_id) => grouped.wtxs.push(*wtx_id),
+            }
+        }
+
+        grouped
+    }
+
+    /// Returns whether the inventory vector contains the supplied entry.
+    pub fn contains(&self, inv_hash: &InvHash) -> bool {
+        self.inventory.contains(inv_hash)
+    }
+
+    /// Returns the entries which aren't present in `other`, preserving their relative order.
+    pub fn difference(&self, other: &Inv) -> Self {
+        let other = other.inventory.iter().collect::<HashSet<_>>();
+
+        Self::new(
+            self.inventory
+                .iter()
+                .filter(|inv_hash| !other.contains(inv_hash))
+                .copied()
+                .collect(),
+        )
+    }
+
+    /// Removes repeated entries, keeping the first occurrence of each.
+    pub fn dedup(&mut self) {
+        let mut seen = HashSet::with_capacity(self.inventory.len());
+        self.inventory.retain(|inv_hash| seen.insert(*inv_hash));
+    }
+
+    /// Returns a copy of the inventory vector without repeated entries, see [`Inv::dedup`].
+    pub fn deduped(&self) -> Self {
+        let mut inv = self.clone();
+        inv.dedup();
+        inv
+    }
+}
+
+/// The entries of an [`Inv`] grouped by kind.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct InvByKind {
+    /// The number of [`InvHash::Error`] entries.
+    pub errors: usize,
+    /// The hashes of [`InvHash::Tx`] entries.
+    pub txs: Vec<Hash>,
+    /// The hashes of [`InvHash::Block`] entries.
+    pub blocks: Vec<Hash>,
+    /// The hashes of [`InvHash::FilteredBlock`] entries.
+    pub filtered_blocks: Vec<Hash>,
+    /// The ids of [`InvHash::MsgWtx`] entries.
+    pub wtxs: Vec<WtxId>,
 }
 
 impl Codec for Inv {
@@ -40,7 +113,7 @@ impl Codec for Inv {
 ///
 /// Bitcoin calls this an "inventory vector" but it is just a typed hash, not a
 /// container, so we do not use that term to avoid confusion with `Vec<T>`.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum InvHash {
     /// Any data of this kind may be ignored.
     Error,
@@ -48,7 +121,10 @@ pub enum InvHash {
     Tx(Hash),
     /// The hash is that of a block.
     Block(Hash),
-    /// The hash is that of a block header.
+    /// The hash is that of a block, which is requested as a `merkleblock` (filtered by the
+    /// connection's [BIP-37] bloom filter).
+    ///
+    /// [BIP-37]: https://github.com/bitcoin/bips/blob/master/bip-0037.mediawiki
     FilteredBlock(Hash),
     /// A pair with the hash of a V5 transaction and the Authorizing Data Commitment (auth_digest).
     ///
@@ -57,6 +133,11 @@ pub enum InvHash {
 }
 
 impl InvHash {
+    /// Returns an entry requesting the block with the supplied hash as a `merkleblock`.
+    pub fn filtered_block(hash: Hash) -> Self {
+        Self::FilteredBlock(hash)
+    }
+
     /// Returns the serialized Zcash network protocol code for the current variant.
     fn code(&self) -> u32 {
         match self {
@@ -111,7 +192,7 @@ impl Codec for InvHash {
 ///
 /// [ZIP-239]: https://zips.z.cash/zip-0239
 /// [Spec: Transaction Identifiers]: https://zips.z.cash/protocol/protocol.pdf#txnidentifiers
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct WtxId {
     /// The non-malleable transaction ID for this transaction's effects.
     pub id: Hash,
@@ -135,3 +216,77 @@ impl Codec for WtxId {
         Ok(Self { id, auth_digest })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore]
+    fn mixed_inv_grouped_by_kind() {
+        let hash = |byte| Hash::new([byte; 32]);
+        let wtx_id = WtxId {
+            id: hash(5),
+            auth_digest: hash(6),
+        };
+
+        let inv = Inv::new(vec![
+            InvHash::Block(hash(1)),
+            InvHash::Tx(hash(2)),
+            InvHash::Error,
+            InvHash::Block(hash(3)),
+            InvHash::FilteredBlock(hash(4)),
+            InvHash::MsgWtx(wtx_id),
+            InvHash::Tx(hash(7)),
+        ]);
+
+        assert_eq!(inv.block_hashes(), vec![hash(1), hash(3)]);
+        assert_eq!(inv.tx_hashes(), vec![hash(2), hash(7)]);
+        assert_eq!(
+            inv.by_kind(),
+            InvByKind {
+                errors: 1,
+                txs: vec![hash(2), hash(7)],
+                blocks: vec![hash(1), hash(3)],
+                filtered_blocks: vec![hash(4)],
+                wtxs: vec![wtx_id],
+            }
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn inv_difference_and_dedup() {
+        let block = |byte| InvHash::Block(Hash::new([byte; 32]));
+        let tx = |byte| InvHash::Tx(Hash::new([byte; 32]));
+
+        let mut requested = Inv::new(vec![block(1), tx(2), block(3), block(1), tx(4), tx(2)]);
+        let known = Inv::new(vec![block(3), tx(4), block(5)]);
+
+        assert_eq!(
+            requested.difference(&known),
+            Inv::new(vec![block(1), tx(2), block(1), tx(2)])
+        );
+        // The kind is part of the entry, so a tx doesn't match a block with the same hash.
+        assert!(!requested.contains(&tx(1)));
+
+        requested.dedup();
+        assert_eq!(requested, Inv::new(vec![block(1), tx(2), block(3), tx(4)]));
+        assert_eq!(requested.deduped(), requested);
+    }
+
+    #[test]
+    #[ignore]
+    fn filtered_block_encode_decode() {
+        let inv_hash = InvHash::filtered_block(Hash::new([1; 32]));
+
+        let mut bytes = Vec::new();
+        inv_hash.encode(&mut bytes).unwrap();
+
+        // The object kind code for a filtered block is 3, followed by the hash.
+        assert_eq!(bytes[..4], 3u32.to_le_bytes());
+        assert_eq!(bytes[4..], [1; 32]);
+
+        assert_eq!(InvHash::decode(&mut &bytes[..]).unwrap(), inv_hash);
+    }
+}
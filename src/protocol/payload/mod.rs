@@ -10,8 +10,14 @@ use time::OffsetDateTime;
 pub mod addr;
 pub use addr::Addr;
 
+pub mod addrv2;
+pub use addrv2::AddrV2;
+
 pub mod block;
 
+pub mod compact_block;
+pub use compact_block::{BlockTxn, CmpctBlock, GetBlockTxn, SendCmpct};
+
 pub mod inv;
 pub use inv::Inv;
 
@@ -60,7 +66,7 @@ impl Codec for Nonce {
 }
 
 /// Specifies the protocol version.
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct ProtocolVersion(pub u32);
 
 impl ProtocolVersion {
@@ -187,7 +193,7 @@ impl VarStr {
 }
 
 /// A general purpose hash of length `32`.
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct Hash([u8; 32]);
 
 impl Hash {
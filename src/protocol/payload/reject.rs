@@ -4,7 +4,7 @@ use std::io::{self, Read};
 
 use bytes::{Buf, BufMut};
 
-use crate::protocol::payload::{codec::Codec, VarStr};
+use crate::protocol::payload::{codec::Codec, Hash, VarStr};
 
 /// A reject message payload.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -24,6 +24,21 @@ pub struct Reject {
     pub data: Vec<u8>,
 }
 
+impl Reject {
+    /// Returns the hash of the rejected object, if the data field holds one.
+    ///
+    /// The hash is only present for `tx` and `block` rejects.
+    pub fn data_hash(&self) -> Option<Hash> {
+        if !matches!(self.message.0.as_str(), "tx" | "block") {
+            return None;
+        }
+
+        let hash: [u8; 32] = self.data.as_slice().try_into().ok()?;
+
+        Some(Hash::new(hash))
+    }
+}
+
 impl Codec for Reject {
     fn encode<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
         self.message.encode(buffer)?;
@@ -118,3 +133,56 @@ impl Codec for CCode {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reject(message: &str, data: Vec<u8>) -> Reject {
+        Reject {
+            message: VarStr(message.to_string()),
+            ccode: CCode::Invalid,
+            reason: VarStr("bad-txns".to_string()),
+            data,
+        }
+    }
+
+    fn round_trip(reject: &Reject) -> Reject {
+        let mut bytes = Vec::new();
+        reject.encode(&mut bytes).unwrap();
+
+        Reject::decode(&mut &bytes[..]).unwrap()
+    }
+
+    #[test]
+    #[ignore]
+    fn decode_with_data_hash() {
+        let hash = [7u8; 32];
+        let decoded = round_trip(&reject("tx", hash.to_vec()));
+
+        assert_eq!(decoded.data, hash.to_vec());
+        assert_eq!(decoded.data_hash(), Some(Hash::new(hash)));
+    }
+
+    #[test]
+    #[ignore]
+    fn decode_without_data_hash() {
+        let decoded = round_trip(&reject("version", Vec::new()));
+
+        assert!(decoded.data.is_empty());
+        assert_eq!(decoded.data_hash(), None);
+    }
+
+    #[test]
+    #[ignore]
+    fn data_hash_only_for_tx_and_block() {
+        let hash = [7u8; 32];
+
+        assert_eq!(
+            reject("block", hash.to_vec()).data_hash(),
+            Some(Hash::new(hash))
+        );
+        assert_eq!(reject("version", hash.to_vec()).data_hash(), None);
+        assert_eq!(reject("getdata", hash.to_vec()).data_hash(), None);
+    }
+}
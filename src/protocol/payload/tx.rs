@@ -7,6 +7,21 @@ use sha2::Digest;
 
 use crate::protocol::payload::{codec::Codec, inv::InvHash, read_n_bytes, Hash, VarInt};
 
+/// The fixed part of the length of a V5 transaction's orchard proofs, in bytes.
+const ORCHARD_PROOFS_BASE_LEN: usize = 2720;
+/// The length the orchard proofs grow by with each orchard action, in bytes.
+const ORCHARD_PROOFS_LEN_PER_ACTION: usize = 2272;
+
+/// The version group ID of V4 (Sapling) transactions.
+pub const SAPLING_VERSION_GROUP_ID: u32 = 0x892f_2085;
+/// The version group ID of V5 (NU5) transactions.
+pub const NU5_VERSION_GROUP_ID: u32 = 0x26a7_270a;
+/// The consensus branch ID of NU5.
+pub const NU5_CONSENSUS_BRANCH_ID: u32 = 0xc2d6_d0b4;
+/// Lock times and expiry heights from this value on are timestamps rather than heights, the
+/// expiry height of overwintered transactions must stay below it.
+pub const LOCK_TIME_THRESHOLD: u32 = 500_000_000;
+
 /// A Zcash transaction ([spec](https://zips.z.cash/protocol/canopy.pdf#txnencodingandconsensus)).
 ///
 /// Supports V1-V4, V5 isn't yet stable.
@@ -78,12 +93,38 @@ impl Codec for Tx {
 
         let (version, overwinter) = {
             const LOW_31_BITS: u32 = !(1 << 31);
+            // Bits between the version and the overwinter flag, unused by any known format.
+            const RESERVED_BITS: u32 = 0x7fff_0000;
             let header = u32::from_le_bytes(read_n_bytes(bytes)?);
 
+            // Reject headers which might carry flags of a future format, rather than misparse them.
+            if header & RESERVED_BITS != 0 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Unexpected flag bits {:#010x} set in transaction header {header:#010x}",
+                        header & RESERVED_BITS
+                    ),
+                ));
+            }
+
             // Extract transaction version and check if overwinter flag is set.
             (header & LOW_31_BITS, header >> 31 != 0)
         };
 
+        // A segwit-style marker (an empty input list) followed by a non-zero flag byte. A V1
+        // transaction can't be valid without transparent inputs, so this can't be a legitimate one.
+        if (version, overwinter) == (1, false) {
+            if let [0x00, flag, ..] = bytes.chunk() {
+                if *flag != 0 {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Unexpected segwit-style marker and flag {flag:#04x} in transaction with version 1"),
+                    ));
+                }
+            }
+        }
+
         let tx = match (version, overwinter) {
             (1, false) => Self::V1(TxV1::decode(bytes)?),
             (2, false) => Self::V2(TxV2::decode(bytes)?),
@@ -422,6 +463,80 @@ impl Codec for TxV4 {
     }
 }
 
+/// A builder for transparent-only [`TxV4`] transactions.
+#[derive(Debug, Clone)]
+pub struct TxV4Builder {
+    group_id: u32,
+    tx_in: Vec<TxIn>,
+    tx_out: Vec<TxOut>,
+    lock_time: u32,
+    expiry_height: u32,
+}
+
+impl Default for TxV4Builder {
+    fn default() -> Self {
+        Self {
+            group_id: SAPLING_VERSION_GROUP_ID,
+            tx_in: Vec::new(),
+            tx_out: Vec::new(),
+            lock_time: 0,
+            expiry_height: 0,
+        }
+    }
+}
+
+impl TxV4Builder {
+    /// Adds a transparent input.
+    pub fn with_input(mut self, input: TxIn) -> Self {
+        self.tx_in.push(input);
+        self
+    }
+
+    /// Adds a transparent output.
+    pub fn with_output(mut self, output: TxOut) -> Self {
+        self.tx_out.push(output);
+        self
+    }
+
+    /// Sets the lock time, 0 (no lock time) by default.
+    pub fn with_lock_time(mut self, lock_time: u32) -> Self {
+        self.lock_time = lock_time;
+        self
+    }
+
+    /// Sets the expiry height, 0 (no expiry) by default.
+    pub fn with_expiry_height(mut self, expiry_height: u32) -> Self {
+        self.expiry_height = expiry_height;
+        self
+    }
+
+    /// Sets the version group ID, [`SAPLING_VERSION_GROUP_ID`] by default.
+    pub fn with_group_id(mut self, group_id: u32) -> Self {
+        self.group_id = group_id;
+        self
+    }
+
+    /// Builds the transaction, failing if the expiry height isn't below [`LOCK_TIME_THRESHOLD`].
+    pub fn build(self) -> io::Result<Tx> {
+        check_expiry_height(self.expiry_height)?;
+
+        Ok(Tx::V4(TxV4 {
+            group_id: self.group_id,
+            tx_in: self.tx_in,
+            tx_out: self.tx_out,
+            lock_time: self.lock_time,
+            expiry_height: self.expiry_height,
+            value_balance_sapling: 0,
+            spends_sapling: Vec::new(),
+            outputs_sapling: Vec::new(),
+            join_split: Vec::new(),
+            join_split_pub_key: None,
+            join_split_sig: None,
+            binding_sig_sapling: None,
+        }))
+    }
+}
+
 /// A V5 transaction.
 #[derive(Debug, PartialEq, Clone)]
 pub struct TxV5 {
@@ -596,17 +711,28 @@ impl Codec for TxV5 {
             let value_balance_orchard = bytes.get_i64_le();
             let anchor_orchard = read_n_bytes(bytes)?;
 
-            // Decode the orchard proofs.
+            // Decode the orchard proofs, their length is determined by the number of actions.
             let n_proofs_orchard = VarInt::decode(bytes)?;
 
+            let max_proofs_len =
+                ORCHARD_PROOFS_BASE_LEN + ORCHARD_PROOFS_LEN_PER_ACTION * actions_orchard.len();
+            if *n_proofs_orchard > max_proofs_len {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "orchard proofs are {} bytes long, the maximum for {} action(s) is {max_proofs_len}",
+                        *n_proofs_orchard,
+                        actions_orchard.len()
+                    ),
+                ));
+            }
+
             if bytes.remaining() < *n_proofs_orchard {
                 return Err(io::ErrorKind::InvalidData.into());
             }
 
-            let mut proofs_orchard = Vec::new();
-            for _ in 0..*n_proofs_orchard {
-                proofs_orchard.push(bytes.get_u8());
-            }
+            let mut proofs_orchard = vec![0u8; *n_proofs_orchard];
+            bytes.copy_to_slice(&mut proofs_orchard);
 
             // Decode orchard auth sigs.
             let mut auth_sigs_orchard = Vec::new();
@@ -654,8 +780,111 @@ impl Codec for TxV5 {
     }
 }
 
+/// A builder for transparent-only [`TxV5`] transactions.
+#[derive(Debug, Clone)]
+pub struct TxV5Builder {
+    group_id: u32,
+    consensus_branch: u32,
+    tx_in: Vec<TxIn>,
+    tx_out: Vec<TxOut>,
+    lock_time: u32,
+    expiry_height: u32,
+}
+
+impl Default for TxV5Builder {
+    fn default() -> Self {
+        Self {
+            group_id: NU5_VERSION_GROUP_ID,
+            consensus_branch: NU5_CONSENSUS_BRANCH_ID,
+            tx_in: Vec::new(),
+            tx_out: Vec::new(),
+            lock_time: 0,
+            expiry_height: 0,
+        }
+    }
+}
+
+impl TxV5Builder {
+    /// Adds a transparent input.
+    pub fn with_input(mut self, input: TxIn) -> Self {
+        self.tx_in.push(input);
+        self
+    }
+
+    /// Adds a transparent output.
+    pub fn with_output(mut self, output: TxOut) -> Self {
+        self.tx_out.push(output);
+        self
+    }
+
+    /// Sets the lock time, 0 (no lock time) by default.
+    pub fn with_lock_time(mut self, lock_time: u32) -> Self {
+        self.lock_time = lock_time;
+        self
+    }
+
+    /// Sets the expiry height, 0 (no expiry) by default.
+    pub fn with_expiry_height(mut self, expiry_height: u32) -> Self {
+        self.expiry_height = expiry_height;
+        self
+    }
+
+    /// Sets the version group ID, [`NU5_VERSION_GROUP_ID`] by default.
+    pub fn with_group_id(mut self, group_id: u32) -> Self {
+        self.group_id = group_id;
+        self
+    }
+
+    /// Sets the consensus branch ID, [`NU5_CONSENSUS_BRANCH_ID`] by default.
+    pub fn with_consensus_branch(mut self, consensus_branch: u32) -> Self {
+        self.consensus_branch = consensus_branch;
+        self
+    }
+
+    /// Builds the transaction, failing if the expiry height isn't below [`LOCK_TIME_THRESHOLD`].
+    pub fn build(self) -> io::Result<Tx> {
+        check_expiry_height(self.expiry_height)?;
+
+        Ok(Tx::V5(Box::new(TxV5 {
+            group_id: self.group_id,
+            consensus_branch: self.consensus_branch,
+            lock_time: self.lock_time,
+            expiry_height: self.expiry_height,
+            tx_in: self.tx_in,
+            tx_out: self.tx_out,
+            spends_sapling: Vec::new(),
+            outputs_sapling: Vec::new(),
+            value_balance_sapling: None,
+            anchor_sapling: None,
+            spend_proofs_sapling: Vec::new(),
+            spend_auth_sigs_sapling: Vec::new(),
+            output_proofs_sapling: Vec::new(),
+            binding_sig_sapling: None,
+            actions_orchard: Vec::new(),
+            flags_orchard: None,
+            value_balance_orchard: None,
+            anchor_orchard: None,
+            proofs_orchard: None,
+            auth_sigs_orchard: None,
+            binding_sig_orchard: None,
+        })))
+    }
+}
+
+fn check_expiry_height(expiry_height: u32) -> io::Result<()> {
+    if expiry_height >= LOCK_TIME_THRESHOLD {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("expiry height {expiry_height} must be below {LOCK_TIME_THRESHOLD}"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// A transparent input.
 #[derive(Debug, PartialEq, Clone)]
-struct TxIn {
+pub struct TxIn {
     // Outpoint object (previous output transaction reference).
     prev_out_hash: Hash,
     prev_out_index: u32,
@@ -667,6 +896,20 @@ struct TxIn {
     sequence: u32,
 }
 
+impl TxIn {
+    /// Creates an input spending the output `prev_out_index` of the transaction `prev_out_hash`,
+    /// unlocked by `script`.
+    pub fn new(prev_out_hash: Hash, prev_out_index: u32, script: Vec<u8>, sequence: u32) -> Self {
+        Self {
+            prev_out_hash,
+            prev_out_index,
+            script_len: VarInt(script.len()),
+            script,
+            sequence,
+        }
+    }
+}
+
 impl Codec for TxIn {
     fn encode<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
         self.prev_out_hash.encode(buffer)?;
@@ -705,13 +948,25 @@ impl Codec for TxIn {
     }
 }
 
+/// A transparent output.
 #[derive(Debug, PartialEq, Clone)]
-struct TxOut {
+pub struct TxOut {
     value: i64,
     pk_script_len: VarInt,
     pk_script: Vec<u8>,
 }
 
+impl TxOut {
+    /// Creates an output of `value` zatoshis, locked by `pk_script`.
+    pub fn new(value: i64, pk_script: Vec<u8>) -> Self {
+        Self {
+            value,
+            pk_script_len: VarInt(pk_script.len()),
+            pk_script,
+        }
+    }
+}
+
 impl Codec for TxOut {
     fn encode<B: BufMut>(&self, buffer: &mut B) -> io::Result<()> {
         buffer.put_i64_le(self.value);
@@ -1064,6 +1319,34 @@ mod tests {
         assert_eq!(tx_v1, Tx::decode(&mut Cursor::new(&bytes)).unwrap());
     }
 
+    #[test]
+    #[ignore]
+    fn unexpected_header_flag_bits_are_rejected() {
+        // A V4 header with an additional high bit set next to the overwinter flag.
+        let header = 4u32 | 1 << 31 | 1 << 30;
+        let mut bytes = header.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0u8; 32]);
+
+        let error = Tx::decode(&mut Cursor::new(&bytes)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error
+            .to_string()
+            .contains("Unexpected flag bits 0x40000000"));
+    }
+
+    #[test]
+    #[ignore]
+    fn segwit_style_marker_is_rejected() {
+        let mut bytes = 1u32.to_le_bytes().to_vec();
+        // The marker, flag and some trailing data.
+        bytes.extend_from_slice(&[0x00, 0x01]);
+        bytes.extend_from_slice(&[0u8; 32]);
+
+        let error = Tx::decode(&mut Cursor::new(&bytes)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("segwit-style marker"));
+    }
+
     #[test]
     #[ignore]
     fn empty_transaction_v2_round_trip() {
@@ -1158,4 +1441,95 @@ mod tests {
 
         assert_eq!(tx_v5, Tx::decode(&mut Cursor::new(&bytes)).unwrap());
     }
+
+    #[test]
+    #[ignore]
+    fn built_transparent_transactions_round_trip() {
+        let input = TxIn::new(Hash::new([1; 32]), 0, vec![0x51], u32::MAX);
+        let output = TxOut::new(50_000, vec![0x76, 0xa9]);
+
+        let tx_v4 = TxV4Builder::default()
+            .with_input(input.clone())
+            .with_output(output.clone())
+            .with_lock_time(10)
+            .with_expiry_height(1_000)
+            .build()
+            .unwrap();
+        let tx_v5 = TxV5Builder::default()
+            .with_input(input)
+            .with_output(output)
+            .with_lock_time(10)
+            .with_expiry_height(1_000)
+            .build()
+            .unwrap();
+
+        for (tx, group_id) in [
+            (tx_v4, SAPLING_VERSION_GROUP_ID),
+            (tx_v5, NU5_VERSION_GROUP_ID),
+        ] {
+            let mut bytes = Vec::new();
+            tx.encode(&mut bytes).unwrap();
+
+            assert_eq!(bytes[4..8], group_id.to_le_bytes());
+            assert_eq!(tx, Tx::decode(&mut Cursor::new(&bytes)).unwrap());
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn built_transaction_expiry_height_must_be_a_height() {
+        let error = TxV5Builder::default()
+            .with_expiry_height(LOCK_TIME_THRESHOLD)
+            .build()
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    #[ignore]
+    fn oversized_orchard_proofs_are_rejected() {
+        let action = ActionDescription {
+            cv: [0; 32],
+            nullifier: [0; 32],
+            rk: [0; 32],
+            cmx: [0; 32],
+            ephemeral_key: [0; 32],
+            enc_ciphertext: [0; 580],
+            out_ciphertext: [0; 80],
+        };
+        let proofs_len = ORCHARD_PROOFS_BASE_LEN + ORCHARD_PROOFS_LEN_PER_ACTION + 1;
+
+        let tx_v5 = Tx::V5(Box::new(TxV5 {
+            group_id: 0,
+            consensus_branch: 0,
+            lock_time: 500_000_000,
+            expiry_height: 500_000_000,
+            tx_in: Vec::new(),
+            tx_out: Vec::new(),
+            spends_sapling: Vec::new(),
+            outputs_sapling: Vec::new(),
+            value_balance_sapling: None,
+            anchor_sapling: None,
+            spend_proofs_sapling: Vec::new(),
+            spend_auth_sigs_sapling: Vec::new(),
+            output_proofs_sapling: Vec::new(),
+            binding_sig_sapling: None,
+            actions_orchard: vec![action],
+            flags_orchard: Some(0),
+            value_balance_orchard: Some(0),
+            anchor_orchard: Some([0; 32]),
+            proofs_orchard: Some(vec![0; proofs_len]),
+            auth_sigs_orchard: Some(vec![[0; 64]]),
+            binding_sig_orchard: Some([0; 64]),
+        }));
+
+        let mut bytes = Vec::new();
+        tx_v5.encode(&mut bytes).unwrap();
+
+        let error = Tx::decode(&mut Cursor::new(&bytes)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error
+            .to_string()
+            .contains("the maximum for 1 action(s) is 4992"));
+    }
 }
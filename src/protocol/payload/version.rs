@@ -13,6 +13,30 @@ use crate::protocol::{
     },
 };
 
+/// The services bitfield advertised by a node.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ServiceFlags(u64);
+
+impl ServiceFlags {
+    /// The node can serve the full block chain.
+    pub const NODE_NETWORK: Self = Self(1);
+
+    /// Returns flags advertising no services (`NODE_NONE`).
+    pub fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Returns flags from the raw bitfield.
+    pub fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the raw bitfield.
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+}
+
 /// A version payload.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Version {
@@ -67,6 +91,24 @@ impl Version {
         self.version = ProtocolVersion(version);
         self
     }
+
+    /// Sets the services advertised by the sender.
+    pub fn with_services(mut self, services: ServiceFlags) -> Self {
+        self.services = services.bits();
+        self
+    }
+
+    /// Sets the user agent.
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = VarStr(user_agent);
+        self
+    }
+
+    /// Sets the height of the sender's best block.
+    pub fn with_start_height(mut self, start_height: i32) -> Self {
+        self.start_height = start_height;
+        self
+    }
 }
 
 impl Codec for Version {
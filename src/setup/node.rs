@@ -36,6 +36,11 @@ pub enum Action {
     ///
     /// **Warning**: this currently only works for zcashd type nodes, for zebra the behaviour
     /// is equivalent to WaitForConnection.
+    ///
+    /// No network upgrade can be reached by seeding: the only contiguous chain available is the
+    /// one up to height 10 (pre-Overwinter). The upgrade boundary vectors, such as the first
+    /// Sapling block at height 280000, are isolated blocks the node won't accept without the
+    /// chain leading up to them.
     SeedWithTestnetBlocks(
         /// The number of initial testnet blocks to seed. Note that this is capped by the number of blocks available
         /// from [Block::initial_testnet_blocks].
@@ -135,7 +140,7 @@ impl Node {
 
                 self.config
                     .initial_peers
-                    .insert(synthetic_node.listening_addr().to_string());
+                    .insert(synthetic_node.listening_addr().unwrap().to_string());
 
                 Some(synthetic_node)
             }
@@ -0,0 +1,223 @@
+//! Contains test cases which cover ZG-CONFORMANCE-024 and ZG-CONFORMANCE-029.
+//!
+//! The node announces a block it has newly added to its chain to its peers, and requests blocks
+//! announced to it by its peers.
+//!
+//! Note: Zebra does not support seeding with chain data and as such cannot run this test.
+
+use std::{io, time::Duration};
+
+use crate::{
+    protocol::{
+        message::Message,
+        payload::{
+            block::{Block, Headers, LocatorHashes},
+            Hash, Inv,
+        },
+    },
+    setup::node::{Action, Node},
+    tools::{
+        message_filter::{Filter, MessageFilter},
+        synthetic_node::SyntheticNode,
+        LONG_TIMEOUT,
+    },
+};
+
+/// Adds `block` to the node's chain post-start, by announcing it from `feeder` and serving the
+/// node's subsequent header and block requests.
+///
+/// The `feeder` must let `GetHeaders` and `GetData` through to its inbound queue.
+async fn add_block(
+    feeder: &mut SyntheticNode,
+    node_addr: std::net::SocketAddr,
+    block: &Block,
+) -> io::Result<()> {
+    feeder.unicast(node_addr, Message::Inv(Inv::new(vec![block.inv_hash()])))?;
+
+    loop {
+        match feeder.recv_message_timeout(LONG_TIMEOUT).await? {
+            // The node syncs the headers first, as its tip isn't recent.
+            (_, Message::GetHeaders(_)) => feeder.unicast(
+                node_addr,
+                Message::Headers(Headers::new(vec![block.header.clone()])),
+            )?,
+            (_, Message::GetData(inv)) if inv.inventory.contains(&block.inv_hash()) => {
+                feeder.unicast(node_addr, Message::Block(Box::new(block.clone())))?;
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+}
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c024_BLOCK_newly_added_block_is_announced() {
+    // ZG-CONFORMANCE-024
+    //
+    // The node announces a block it added to its chain after the handshake to its other peers,
+    // with an `Inv` (or `Headers`, if the peer requested it with `SendHeaders`).
+    //
+    // zcashd: announces the new tip with an `Inv` to all its peers.
+    // zebra:  can't be seeded with blocks, so the test can't run.
+
+    let mut blocks = Block::initial_testnet_blocks();
+    let new_block = blocks.pop().unwrap();
+
+    // Spin up a node instance seeded with all but the last available block.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::SeedWithTestnetBlocks(blocks.len()))
+        .start()
+        .await
+        .unwrap();
+
+    // The observer only receives announcements, the feeder serves the new block.
+    let mut observer = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await
+        .unwrap();
+    observer.connect(node.addr()).await.unwrap();
+
+    let mut feeder = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_message_filter(
+            MessageFilter::with_all_auto_reply()
+                .with_getheaders_filter(Filter::Disabled)
+                .with_getdata_filter(Filter::Disabled),
+        )
+        .build()
+        .await
+        .unwrap();
+    feeder.connect(node.addr()).await.unwrap();
+
+    add_block(&mut feeder, node.addr(), &new_block)
+        .await
+        .unwrap();
+
+    // Expect the observer to receive the announcement of the new block.
+    let new_block_hash = new_block.double_sha256().unwrap();
+    loop {
+        match observer.recv_message_timeout(LONG_TIMEOUT).await.unwrap() {
+            (_, Message::Inv(inv)) => {
+                assert_eq!(inv.inventory, vec![new_block.inv_hash()]);
+                break;
+            }
+            (_, Message::Headers(headers)) => {
+                let hashes = headers
+                    .headers
+                    .iter()
+                    .map(|header| header.double_sha256().unwrap())
+                    .collect::<Vec<_>>();
+                assert_eq!(hashes, vec![new_block_hash]);
+                break;
+            }
+            // Skip anything else the node may send in the meantime.
+            _ => {}
+        }
+    }
+
+    // Gracefully shut down the nodes.
+    feeder.shut_down().await;
+    observer.shut_down().await;
+    node.stop().unwrap();
+}
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c029_BLOCK_advertised_block_is_served_on_getdata() {
+    // ZG-CONFORMANCE-029
+    //
+    // The node requests a block advertised by a peer with `GetData`, and accepts the block served
+    // in response.
+    //
+    // The flow is:
+    //  1. The synthetic node advertises the block with an `Inv`
+    //  2. The node syncs the headers first with `GetHeaders`, answered manually with the header
+    //  3. The node requests the block with `GetData`, served automatically by the synthetic node
+    //  4. The node adds the block to its chain, which is checked with `GetHeaders`
+    //
+    // The node must neither reject the block nor disconnect.
+    //
+    // zcashd: pass
+    // zebra:  can't be seeded with blocks, so the test can't run.
+
+    let mut blocks = Block::initial_testnet_blocks();
+    let new_block = blocks.pop().unwrap();
+    let tip_hash = blocks.last().unwrap().double_sha256().unwrap();
+    let new_block_hash = new_block.double_sha256().unwrap();
+
+    // Spin up a node instance seeded with all but the last available block.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::SeedWithTestnetBlocks(blocks.len()))
+        .start()
+        .await
+        .unwrap();
+
+    // The synthetic node serves the new block on request, but answers GetHeaders manually.
+    let mut synthetic_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_message_filter(
+            MessageFilter::with_all_auto_reply().with_getheaders_filter(Filter::Disabled),
+        )
+        .with_served_blocks(vec![new_block.clone()])
+        .build()
+        .await
+        .unwrap();
+    synthetic_node.connect(node.addr()).await.unwrap();
+
+    synthetic_node
+        .unicast(
+            node.addr(),
+            Message::Inv(Inv::new(vec![new_block.inv_hash()])),
+        )
+        .unwrap();
+
+    // Keep asking for the headers after the old tip until the new block shows up.
+    let query = Message::GetHeaders(LocatorHashes::new(vec![tip_hash], Hash::zeroed()));
+    let mut accepted = false;
+    let start = std::time::Instant::now();
+    while !accepted {
+        assert!(
+            start.elapsed() < LONG_TIMEOUT,
+            "the node didn't accept the block"
+        );
+
+        synthetic_node.unicast(node.addr(), query.clone()).unwrap();
+        match synthetic_node
+            .recv_message_timeout(LONG_TIMEOUT)
+            .await
+            .unwrap()
+        {
+            (_, Message::GetHeaders(_)) => synthetic_node
+                .unicast(
+                    node.addr(),
+                    Message::Headers(Headers::new(vec![new_block.header.clone()])),
+                )
+                .unwrap(),
+            (_, Message::Headers(headers)) => {
+                accepted = headers
+                    .headers
+                    .iter()
+                    .any(|header| header.double_sha256().unwrap() == new_block_hash);
+                if !accepted {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+            (_, Message::Reject(reject)) => panic!("the node rejected the block: {reject:?}"),
+            _ => {}
+        }
+    }
+
+    // The connection is kept alive.
+    let result = synthetic_node
+        .ping_pong_timeout(node.addr(), LONG_TIMEOUT)
+        .await;
+
+    // Gracefully shut down the nodes.
+    synthetic_node.shut_down().await;
+    node.stop().unwrap();
+
+    result.unwrap();
+}
@@ -46,7 +46,7 @@ async fn c002_handshake_when_node_initiates_connection() {
 
     // Spin up a node and set the synthetic node as an initial peer.
     let mut node = Node::new().unwrap();
-    node.initial_peers(vec![synthetic_node.listening_addr()])
+    node.initial_peers(vec![synthetic_node.listening_addr().unwrap()])
         .start()
         .await
         .unwrap();
@@ -0,0 +1,61 @@
+use crate::{
+    protocol::{
+        message::Message,
+        payload::{reject::CCode, Version},
+    },
+    setup::node::{Action, Node},
+    tools::{synthetic_node::SyntheticNode, LONG_TIMEOUT, RECV_TIMEOUT},
+};
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c020_VERSION_duplicate_after_handshake() {
+    // ZG-CONFORMANCE-020
+    //
+    // The node ignores a second Version received after the handshake has completed, optionally
+    // replying with a Reject(Duplicate), and keeps the connection alive.
+    //
+    // zcashd: pass (replies with Reject(Duplicate) and keeps the connection).
+    // zebra:  fail (drops the connection, treating it as a duplicate handshake).
+
+    // Spin up a node instance.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    // Create a synthetic node which auto-replies to keep the connection alive.
+    let mut synthetic_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await
+        .unwrap();
+
+    // Connect to the node and complete the handshake.
+    synthetic_node.connect(node.addr()).await.unwrap();
+
+    // Send a second Version.
+    let version = Version::new(node.addr(), synthetic_node.listening_addr().unwrap());
+    synthetic_node
+        .unicast(node.addr(), Message::Version(version))
+        .unwrap();
+
+    // A Reject(Duplicate) is the only acceptable reply.
+    match synthetic_node.recv_message_timeout(RECV_TIMEOUT).await {
+        Ok((_, Message::Reject(reject))) => assert_eq!(reject.ccode, CCode::Duplicate),
+        Ok((_, other)) => panic!("unexpected reply to a duplicate Version: {other:?}"),
+        Err(_timeout) => {}
+    }
+
+    // The connection must still be alive.
+    synthetic_node
+        .ping_pong_timeout(node.addr(), LONG_TIMEOUT)
+        .await
+        .unwrap();
+
+    // Gracefully shut down the nodes.
+    synthetic_node.shut_down().await;
+    node.stop().unwrap();
+}
@@ -0,0 +1,67 @@
+use crate::{
+    protocol::payload::ProtocolVersion,
+    setup::node::{Action, Node},
+    tools::{synthetic_node::SyntheticNode, LONG_TIMEOUT},
+};
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c027_VERSION_with_future_protocol_version() {
+    // ZG-CONFORMANCE-027
+    //
+    // The node handles a peer advertising a protocol version far above its own gracefully, either
+    // by accepting it (and using the lower of the two versions) or by rejecting the connection.
+    // Either way, it keeps serving other peers.
+    //
+    // zcashd: pass, accepts the connection, it only enforces a minimum version.
+    // zebra:  pass, accepts the connection and negotiates down to its own version.
+
+    // Spin up a node instance.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    // Create a synthetic node advertising the highest possible protocol version.
+    let mut synthetic_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .with_protocol_version(ProtocolVersion(u32::MAX))
+        .build()
+        .await
+        .unwrap();
+
+    // The handshake attempt is bounded by the synthetic node's handshake timeout, so the node
+    // can't hang it.
+    let accepted = synthetic_node.connect(node.addr()).await.is_ok();
+
+    // If accepted, the connection is kept alive.
+    let result = if accepted {
+        synthetic_node
+            .ping_pong_timeout(node.addr(), LONG_TIMEOUT)
+            .await
+    } else {
+        Ok(())
+    };
+    synthetic_node.shut_down().await;
+
+    // The node is still alive and accepts regular peers.
+    let mut regular_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await
+        .unwrap();
+    regular_node.connect(node.addr()).await.unwrap();
+    let regular_result = regular_node
+        .ping_pong_timeout(node.addr(), LONG_TIMEOUT)
+        .await;
+
+    // Gracefully shut down the nodes.
+    regular_node.shut_down().await;
+    node.stop().unwrap();
+
+    result.unwrap();
+    regular_result.unwrap();
+}
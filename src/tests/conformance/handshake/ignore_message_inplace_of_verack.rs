@@ -275,7 +275,7 @@ mod when_node_initiates_connection {
 
         // Spin up a node instance which will connect to our SyntheticNode.
         let mut node = Node::new()?;
-        node.initial_peers(vec![synthetic_node.listening_addr()])
+        node.initial_peers(vec![synthetic_node.listening_addr().unwrap()])
             .start()
             .await?;
 
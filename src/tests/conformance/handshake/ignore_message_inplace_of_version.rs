@@ -151,7 +151,10 @@ mod when_node_receives_connection {
         // Send Version.
         synthetic_node.unicast(
             node.addr(),
-            Message::Version(Version::new(synthetic_node.listening_addr(), node.addr())),
+            Message::Version(Version::new(
+                synthetic_node.listening_addr().unwrap(),
+                node.addr(),
+            )),
         )?;
 
         // Read Version.
@@ -316,7 +319,7 @@ mod when_node_initiates_connection {
 
         // Spin up a node instance which will connect to our SyntheticNode.
         let mut node = Node::new()?;
-        node.initial_peers(vec![dbg!(synthetic_node.listening_addr())])
+        node.initial_peers(vec![dbg!(synthetic_node.listening_addr().unwrap())])
             .start()
             .await?;
 
@@ -331,7 +334,10 @@ mod when_node_initiates_connection {
         // Send Version.
         synthetic_node.unicast(
             node_addr,
-            Message::Version(Version::new(synthetic_node.listening_addr(), node_addr)),
+            Message::Version(Version::new(
+                synthetic_node.listening_addr().unwrap(),
+                node_addr,
+            )),
         )?;
 
         // Read Version.
@@ -0,0 +1,47 @@
+use crate::{
+    protocol::{message::constants::NU5_TESTNET_PROTOCOL_VERSION, payload::ProtocolVersion},
+    setup::node::{Action, Node},
+    tools::synthetic_node::SyntheticNode,
+};
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c019_VERSION_advertises_minimum_protocol_version() {
+    // ZG-CONFORMANCE-019
+    //
+    // The node advertises a protocol version which is at least the NU5 floor for its network.
+    //
+    // Expected floor per network:
+    //  mainnet: 170_100 (NU5_MAINNET_PROTOCOL_VERSION)
+    //  testnet: 170_050 (NU5_TESTNET_PROTOCOL_VERSION)
+    //
+    // Test nodes always run on testnet, so only the testnet floor is asserted.
+    //
+    // zcashd: pass
+    // zebra:  pass
+
+    // Spin up a node instance.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    // Create a synthetic node and enable handshaking.
+    let synthetic_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .build()
+        .await
+        .unwrap();
+
+    // Connect to the node and initiate the handshake.
+    synthetic_node.connect(node.addr()).await.unwrap();
+
+    // The node's Version is recorded during the handshake.
+    let version = synthetic_node.peer_version(&node.addr()).unwrap();
+    assert!(version >= ProtocolVersion(NU5_TESTNET_PROTOCOL_VERSION));
+
+    // Gracefully shut down the nodes.
+    synthetic_node.shut_down().await;
+    node.stop().unwrap();
+}
@@ -1,4 +1,13 @@
 mod complete_handshake;
+mod duplicate_version;
+mod future_protocol_version;
 mod ignore_message_inplace_of_verack;
 mod ignore_message_inplace_of_version;
+mod min_protocol_version;
+mod no_services;
+mod post_handshake_sequence;
 mod reject_version;
+mod spoofed_addr_recv;
+mod version_latency;
+mod version_timestamp;
+mod version_wire_format;
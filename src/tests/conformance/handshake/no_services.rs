@@ -0,0 +1,49 @@
+use crate::{
+    protocol::payload::version::ServiceFlags,
+    setup::node::{Action, Node},
+    tools::{synthetic_node::SyntheticNode, LONG_TIMEOUT},
+};
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c022_VERSION_advertising_no_services() {
+    // ZG-CONFORMANCE-022
+    //
+    // The node accepts inbound peers advertising no services (NODE_NONE), e.g. light clients.
+    //
+    // Such peers can't serve blocks, so the node is not expected to download from them. Outbound
+    // connections to them would be dropped, which is not covered here.
+    //
+    // zcashd: pass
+    // zebra:  pass
+
+    // Spin up a node instance.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    // Create a synthetic node advertising no services.
+    let mut synthetic_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .with_services(ServiceFlags::empty())
+        .build()
+        .await
+        .unwrap();
+
+    // The handshake completes.
+    synthetic_node.connect(node.addr()).await.unwrap();
+
+    // The connection is kept alive.
+    let result = synthetic_node
+        .ping_pong_timeout(node.addr(), LONG_TIMEOUT)
+        .await;
+
+    // Gracefully shut down the nodes.
+    synthetic_node.shut_down().await;
+    node.stop().unwrap();
+
+    result.unwrap();
+}
@@ -0,0 +1,58 @@
+use crate::{
+    protocol::{message::Message, payload::Nonce},
+    setup::node::{Action, Node},
+    tools::{synthetic_node::SyntheticNode, LONG_TIMEOUT},
+};
+
+#[tokio::test]
+async fn c039_post_handshake_messages_answered_in_order() {
+    // ZG-CONFORMANCE-039
+    //
+    // The established connection is functional, the node answers post-handshake messages in the
+    // order they were sent.
+    //
+    // zcashd: pass
+    // zebra:  pass
+
+    // Spin up a node instance.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    // Create a synthetic node and enable handshaking, auto-reply to the node's own queries.
+    let mut synthetic_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await
+        .unwrap();
+
+    // Connect to the node and initiate the handshake.
+    synthetic_node.connect(node.addr()).await.unwrap();
+
+    let nonces = [Nonce::default(), Nonce::default()];
+    for nonce in nonces {
+        synthetic_node
+            .unicast(node.addr(), Message::Ping(nonce))
+            .unwrap();
+    }
+    let result = synthetic_node
+        .expect_sequence(
+            node.addr(),
+            &[
+                |m| matches!(m, Message::Pong(..)),
+                |m| matches!(m, Message::Pong(..)),
+            ],
+            LONG_TIMEOUT,
+        )
+        .await;
+
+    // Gracefully shut down the nodes.
+    synthetic_node.shut_down().await;
+    node.stop().unwrap();
+
+    let pongs = result.unwrap();
+    assert_eq!(pongs, nonces.map(Message::Pong));
+}
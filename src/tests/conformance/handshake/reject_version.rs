@@ -25,7 +25,7 @@ async fn c007_PING_reusing_nonce() {
 
     // Spin up a node instance with the synthetic node set as an initial peer.
     let mut node = Node::new().unwrap();
-    node.initial_peers(vec![synthetic_node.listening_addr()])
+    node.initial_peers(vec![synthetic_node.listening_addr().unwrap()])
         .start()
         .await
         .unwrap();
@@ -38,7 +38,7 @@ async fn c007_PING_reusing_nonce() {
     let nonce = assert_matches!(version, Message::Version(version) => version.nonce);
 
     // Send a Version.
-    let mut bad_version = Version::new(node.addr(), synthetic_node.listening_addr());
+    let mut bad_version = Version::new(node.addr(), synthetic_node.listening_addr().unwrap());
     bad_version.nonce = nonce;
     synthetic_node
         .unicast(source, Message::Version(bad_version))
@@ -84,7 +84,7 @@ async fn c008_VERSION_with_obsolete_number() {
             .unicast(
                 node.addr(),
                 Message::Version(
-                    Version::new(node.addr(), synthetic_node.listening_addr())
+                    Version::new(node.addr(), synthetic_node.listening_addr().unwrap())
                         .with_version(obsolete_version_number),
                 ),
             )
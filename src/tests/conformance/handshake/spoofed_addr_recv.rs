@@ -0,0 +1,53 @@
+use std::net::SocketAddr;
+
+use crate::{
+    setup::node::{Action, Node},
+    tools::{synthetic_node::SyntheticNode, LONG_TIMEOUT},
+};
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c026_VERSION_with_spoofed_addr_recv() {
+    // ZG-CONFORMANCE-026
+    //
+    // The node accepts a `Version` whose `addr_recv` doesn't match the node's actual address.
+    //
+    // The field is only informational (the node may use it to learn its external address), so
+    // it must not be used to reject the peer.
+    //
+    // zcashd: pass
+    // zebra:  pass
+
+    // A routable address, unrelated to the node's.
+    let spoofed_addr = SocketAddr::from(([93, 184, 216, 34], 8233));
+
+    // Spin up a node instance.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    // Create a synthetic node advertising a spoofed receiving address.
+    let mut synthetic_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .with_addr_recv(spoofed_addr)
+        .build()
+        .await
+        .unwrap();
+
+    // The handshake completes.
+    synthetic_node.connect(node.addr()).await.unwrap();
+
+    // The connection is kept alive.
+    let result = synthetic_node
+        .ping_pong_timeout(node.addr(), LONG_TIMEOUT)
+        .await;
+
+    // Gracefully shut down the nodes.
+    synthetic_node.shut_down().await;
+    node.stop().unwrap();
+
+    result.unwrap();
+}
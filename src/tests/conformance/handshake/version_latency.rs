@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use crate::{
+    protocol::message::Message,
+    setup::node::Node,
+    tools::{synthetic_node::SyntheticNode, LONG_TIMEOUT},
+};
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c031_VERSION_sent_promptly_when_node_initiates_connection() {
+    // ZG-CONFORMANCE-031
+    //
+    // When the node initiates a connection, it sends its `Version` as soon as the connection is
+    // established, without waiting for the peer to speak first.
+    //
+    // The synthetic node doesn't handshake, it only accepts the connection and measures the time
+    // from the connection being established to the node's `Version` arriving.
+    //
+    // zcashd: pass, sends `Version` right after the connection is established (well under 100ms).
+    // zebra:  pass, sends `Version` right after the connection is established (well under 100ms).
+
+    // The time allowed between the connection being established and the Version arriving.
+    const VERSION_BOUND: Duration = Duration::from_secs(1);
+
+    // Create a synthetic node, without handshaking so the node's Version reaches the test.
+    let mut synthetic_node = SyntheticNode::builder().build().await.unwrap();
+
+    // Spin up a node and set the synthetic node as an initial peer.
+    let mut node = Node::new().unwrap();
+    node.initial_peers(vec![synthetic_node.listening_addr().unwrap()])
+        .start()
+        .await
+        .unwrap();
+
+    let node_addr = synthetic_node.wait_for_connection().await;
+    let connected_at = synthetic_node
+        .peer_info(node_addr)
+        .expect("the connection is registered")
+        .stats()
+        .created();
+
+    let result = synthetic_node.recv_message_timeout(LONG_TIMEOUT).await;
+    let latency = connected_at.elapsed();
+
+    // Gracefully shut down the nodes.
+    synthetic_node.shut_down().await;
+    node.stop().unwrap();
+
+    let (_, message) = result.expect("the node didn't send a Version");
+    assert!(
+        matches!(message, Message::Version(..)),
+        "expected Version, got {message:?}"
+    );
+    assert!(
+        latency <= VERSION_BOUND,
+        "the node took {latency:?} to send its Version"
+    );
+}
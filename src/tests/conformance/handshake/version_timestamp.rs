@@ -0,0 +1,50 @@
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    setup::node::{Action, Node},
+    tools::{synthetic_node::SyntheticNode, LONG_TIMEOUT},
+};
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c021_VERSION_with_timestamp_far_in_the_future() {
+    // ZG-CONFORMANCE-021
+    //
+    // The node completes the handshake with a peer whose Version timestamp is far in the future.
+    //
+    // The skew used here (2 hours) exceeds the 70 minute network time adjustment inherited from
+    // Bitcoin, so the peer's clock can't be trusted, but that alone isn't a reason to drop it.
+    //
+    // zcashd: pass
+    // zebra:  pass
+
+    // Spin up a node instance.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    // Create a synthetic node advertising a skewed clock.
+    let mut synthetic_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .with_version_timestamp(OffsetDateTime::now_utc() + Duration::hours(2))
+        .build()
+        .await
+        .unwrap();
+
+    // The handshake completes.
+    synthetic_node.connect(node.addr()).await.unwrap();
+
+    // The connection is kept alive.
+    let result = synthetic_node
+        .ping_pong_timeout(node.addr(), LONG_TIMEOUT)
+        .await;
+
+    // Gracefully shut down the nodes.
+    synthetic_node.shut_down().await;
+    node.stop().unwrap();
+
+    result.unwrap();
+}
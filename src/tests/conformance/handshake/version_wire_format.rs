@@ -0,0 +1,65 @@
+use crate::{
+    protocol::{
+        message::{constants::*, Message, MessageHeader},
+        payload::codec::Codec,
+    },
+    setup::node::Node,
+    tools::{synthetic_node::SyntheticNode, LONG_TIMEOUT},
+};
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c032_VERSION_wire_format() {
+    // ZG-CONFORMANCE-032
+    //
+    // The node's `Version` is correctly encoded on the wire: the header carries the testnet
+    // magic, the null-padded `version` command, the body length and its checksum, and the body
+    // is canonically encoded (re-encoding the decoded message yields the same bytes).
+    //
+    // The synthetic node doesn't handshake, so the node's `Version` is read like any other
+    // message and its raw frame is captured before decoding.
+    //
+    // zcashd: pass
+    // zebra:  pass
+
+    // Create a synthetic node, without handshaking so the node's Version reaches the test.
+    let mut synthetic_node = SyntheticNode::builder().build().await.unwrap();
+
+    // Spin up a node and set the synthetic node as an initial peer.
+    let mut node = Node::new().unwrap();
+    node.initial_peers(vec![synthetic_node.listening_addr().unwrap()])
+        .start()
+        .await
+        .unwrap();
+
+    let result = synthetic_node.recv_message_timeout(LONG_TIMEOUT).await;
+    let captured = result
+        .as_ref()
+        .ok()
+        .and_then(|(source, _)| synthetic_node.version_bytes(*source));
+
+    // Gracefully shut down the nodes.
+    synthetic_node.shut_down().await;
+    node.stop().unwrap();
+
+    let (_, message) = result.expect("the node didn't send a Version");
+    assert!(
+        matches!(message, Message::Version(..)),
+        "expected Version, got {message:?}"
+    );
+    let bytes = captured.expect("the Version bytes weren't captured");
+
+    // The header.
+    assert_eq!(bytes[..MAGIC_LEN], MAGIC_TESTNET);
+    assert_eq!(bytes[MAGIC_LEN..][..COMMAND_LEN], VERSION_COMMAND);
+    let header = MessageHeader::decode(&mut &bytes[..HEADER_LEN]).unwrap();
+    let body = &bytes[HEADER_LEN..];
+    assert_eq!(header.body_length as usize, body.len());
+    assert_eq!(
+        header.checksum,
+        MessageHeader::new(VERSION_COMMAND, body).checksum
+    );
+
+    // The body, including the user agent and services, encodes canonically.
+    assert_eq!(message.to_vec().unwrap(), bytes);
+}
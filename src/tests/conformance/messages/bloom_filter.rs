@@ -0,0 +1,189 @@
+//! Contains test cases which cover ZG-CONFORMANCE-038.
+//!
+//! A peer which loaded a bloom filter (BIP 37) should only be announced the transactions matching
+//! the filter, until the filter is cleared:
+//!
+//!  FilterLoad              - only matching transactions are announced
+//!  FilterAdd               - transactions matching the added element are announced as well
+//!  FilterClear             - all transactions are announced again
+
+use std::{io, net::SocketAddr, time::Duration};
+
+use tokio::time::Instant;
+
+use crate::{
+    protocol::{
+        message::Message,
+        payload::{block::Block, codec::Codec, FilterAdd, FilterLoad, Hash, Tx},
+    },
+    setup::node::{Action, Node},
+    tools::synthetic_node::SyntheticNode,
+};
+
+/// The time allowed for the node to announce the transactions, which covers its trickling delay.
+const RELAY_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[tokio::test]
+#[allow(non_snake_case)]
+// This test should currently fail, since we have no way of generating a valid transaction the
+// node would accept into its mempool.
+async fn c038_t1_FILTERLOAD_only_matching_txs_are_announced() {
+    // zcashd: fail, bloom filtering is disabled unless the node advertises NODE_BLOOM, so the
+    //         filter messages are rejected as obsolete.
+    // zebra:  fail, bloom filtering isn't implemented.
+    let (matching, other) = test_txs();
+    let messages = vec![Message::FilterLoad(filter_matching(&[&matching]))];
+
+    let announced = run_test_case(messages, &[matching.clone(), other])
+        .await
+        .unwrap();
+    assert_eq!(announced, vec![txid(&matching)]);
+}
+
+#[tokio::test]
+#[allow(non_snake_case)]
+// This test should currently fail, since we have no way of generating a valid transaction the
+// node would accept into its mempool.
+async fn c038_t2_FILTERADD_extends_the_loaded_filter() {
+    // zcashd: fail, bloom filtering is disabled unless the node advertises NODE_BLOOM, so the
+    //         filter messages are rejected as obsolete.
+    // zebra:  fail, bloom filtering isn't implemented.
+    let (matching, added) = test_txs();
+    let messages = vec![
+        Message::FilterLoad(filter_matching(&[&matching])),
+        Message::FilterAdd(FilterAdd {
+            data: txid_bytes(&added),
+        }),
+    ];
+
+    let announced = run_test_case(messages, &[matching.clone(), added.clone()])
+        .await
+        .unwrap();
+    assert_eq!(announced.len(), 2);
+    assert!(announced.contains(&txid(&matching)) && announced.contains(&txid(&added)));
+}
+
+#[tokio::test]
+#[allow(non_snake_case)]
+// This test should currently fail, since we have no way of generating a valid transaction the
+// node would accept into its mempool.
+async fn c038_t3_FILTERCLEAR_announces_all_txs() {
+    // zcashd: fail, bloom filtering is disabled unless the node advertises NODE_BLOOM, so the
+    //         filter messages are rejected as obsolete.
+    // zebra:  fail, bloom filtering isn't implemented.
+    let (matching, other) = test_txs();
+    let messages = vec![
+        Message::FilterLoad(filter_matching(&[&matching])),
+        Message::FilterClear,
+    ];
+
+    let announced = run_test_case(messages, &[matching.clone(), other.clone()])
+        .await
+        .unwrap();
+    assert_eq!(announced.len(), 2);
+    assert!(announced.contains(&txid(&matching)) && announced.contains(&txid(&other)));
+}
+
+/// Returns two distinct transactions to submit to the node.
+fn test_txs() -> (Tx, Tx) {
+    (
+        Block::testnet_1().txs[0].clone(),
+        Block::testnet_2().txs[0].clone(),
+    )
+}
+
+fn txid(tx: &Tx) -> Hash {
+    tx.double_sha256().unwrap()
+}
+
+/// Returns the transaction's id as serialized, which is what the filter matches.
+fn txid_bytes(tx: &Tx) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    txid(tx).encode(&mut bytes).unwrap();
+    bytes
+}
+
+/// Returns a filter matching the ids of the supplied transactions.
+fn filter_matching(txs: &[&Tx]) -> FilterLoad {
+    let mut filter = FilterLoad::new(txs.len(), 0.0001, 0, FilterLoad::BLOOM_UPDATE_NONE);
+    for tx in txs {
+        filter.insert(&txid_bytes(tx));
+    }
+
+    filter
+}
+
+/// Sends the filter messages to the node from an observing synthetic node, submits the
+/// transactions from a second synthetic node, and returns the ids of the transactions announced
+/// to the observer within [`RELAY_TIMEOUT`].
+async fn run_test_case(filter_messages: Vec<Message>, txs: &[Tx]) -> io::Result<Vec<Hash>> {
+    let mut node = Node::new()?;
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await?;
+
+    // The observing peer, which asks for transactions to be relayed to it and filters them.
+    let mut observer = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .with_relay(true)
+        .build()
+        .await?;
+    // The submitting peer.
+    let submitter = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await?;
+    observer.connect(node.addr()).await?;
+    submitter.connect(node.addr()).await?;
+
+    for message in filter_messages {
+        observer.unicast(node.addr(), message)?;
+    }
+    let result = async {
+        // Make sure the filter is in place before the transactions are submitted.
+        observer
+            .ping_pong_timeout(node.addr(), RELAY_TIMEOUT)
+            .await
+            .map_err(io::Error::from)?;
+        for tx in txs {
+            submitter.unicast(node.addr(), Message::Tx(tx.clone()))?;
+        }
+
+        Ok(collect_announced_txs(&mut observer, node.addr()).await)
+    }
+    .await;
+
+    // Gracefully shut down the nodes.
+    submitter.shut_down().await;
+    observer.shut_down().await;
+    node.stop()?;
+
+    result
+}
+
+/// Collects the ids of the transactions announced by the node until [`RELAY_TIMEOUT`] elapses.
+async fn collect_announced_txs(observer: &mut SyntheticNode, node_addr: SocketAddr) -> Vec<Hash> {
+    let deadline = Instant::now() + RELAY_TIMEOUT;
+    let mut announced = Vec::new();
+
+    // Other messages, e.g. block announcements, are skipped.
+    while let Ok((source, message)) = observer
+        .recv_message_timeout(deadline.saturating_duration_since(Instant::now()))
+        .await
+    {
+        match message {
+            Message::Inv(inv) if source == node_addr => {
+                for hash in inv.tx_hashes() {
+                    if !announced.contains(&hash) {
+                        announced.push(hash);
+                    }
+                }
+            }
+            _ => continue,
+        }
+    }
+
+    announced
+}
@@ -0,0 +1,132 @@
+//! Contains test cases which cover ZG-CONFORMANCE-036.
+//!
+//! Neither zcashd nor zebra implement compact block relay (BIP 152). The node should ignore the
+//! compact block messages post-handshake, neither negotiating compact blocks nor terminating the
+//! connection:
+//!
+//!  SendCmpct               - not negotiated
+//!  CmpctBlock              - ignored
+//!  GetBlockTxn             - not served
+//!  BlockTxn                - ignored
+
+use std::io;
+
+use crate::{
+    protocol::{
+        message::Message,
+        payload::{
+            block::Block,
+            compact_block::{PrefilledTx, ShortTxId},
+            BlockTxn, CmpctBlock, GetBlockTxn, SendCmpct,
+        },
+    },
+    setup::node::{Action, Node},
+    tools::{
+        synthetic_node::{PingPongError, SyntheticNode},
+        RECV_TIMEOUT,
+    },
+};
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c036_t1_SENDCMPCT_is_not_negotiated() {
+    // ZG-CONFORMANCE-036
+    //
+    // zcashd: pass
+    // zebra:  pass
+    let sendcmpct = Message::SendCmpct(SendCmpct {
+        announce: true,
+        version: 1,
+    });
+
+    run_test_case(sendcmpct).await.unwrap();
+}
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c036_t2_CMPCTBLOCK_is_ignored() {
+    // ZG-CONFORMANCE-036
+    //
+    // zcashd: pass
+    // zebra:  pass
+    let block = Block::testnet_2();
+    let cmpctblock = Message::CmpctBlock(Box::new(CmpctBlock {
+        header: block.header.clone(),
+        nonce: 0,
+        short_ids: vec![ShortTxId([0; 6]); block.txs.len() - 1],
+        prefilled_txs: vec![PrefilledTx {
+            index: 0,
+            tx: block.txs[0].clone(),
+        }],
+    }));
+
+    run_test_case(cmpctblock).await.unwrap();
+}
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c036_t3_GETBLOCKTXN_is_not_served() {
+    // ZG-CONFORMANCE-036
+    //
+    // zcashd: pass
+    // zebra:  pass
+    let getblocktxn = Message::GetBlockTxn(GetBlockTxn {
+        block_hash: Block::testnet_genesis().double_sha256().unwrap(),
+        indexes: vec![0],
+    });
+
+    run_test_case(getblocktxn).await.unwrap();
+}
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c036_t4_BLOCKTXN_is_ignored() {
+    // ZG-CONFORMANCE-036
+    //
+    // zcashd: pass
+    // zebra:  pass
+    let block = Block::testnet_1();
+    let blocktxn = Message::BlockTxn(BlockTxn {
+        block_hash: block.double_sha256().unwrap(),
+        txs: block.txs.clone(),
+    });
+
+    run_test_case(blocktxn).await.unwrap();
+}
+
+async fn run_test_case(message: Message) -> io::Result<()> {
+    // Setup a fully handshaken connection between a node and synthetic node. The node is seeded
+    // with the genesis block, so a `GetBlockTxn` for it could be answered.
+    let mut node = Node::new()?;
+    node.initial_action(Action::SeedWithTestnetBlocks(1))
+        .start()
+        .await?;
+    let mut synthetic_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await?;
+    synthetic_node.connect(node.addr()).await?;
+
+    synthetic_node.unicast(node.addr(), message)?;
+
+    // Use Ping-Pong to check the node's response. We expect the message to be ignored, any
+    // reply (e.g. a `SendCmpct` or `BlockTxn`) or a terminated connection is a failure.
+    let result = match synthetic_node
+        .ping_pong_timeout(node.addr(), RECV_TIMEOUT)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(PingPongError::Unexpected(msg)) => Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("Unexpected message received: {msg:?}"),
+        )),
+        Err(err) => Err(err.into()),
+    };
+
+    // clean-up
+    synthetic_node.shut_down().await;
+    node.stop()?;
+
+    result
+}
@@ -0,0 +1,3 @@
+mod bloom_filter;
+mod compact_block;
+mod tx_relay;
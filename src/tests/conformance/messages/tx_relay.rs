@@ -0,0 +1,104 @@
+//! Contains test cases which cover ZG-CONFORMANCE-034.
+//!
+//! A transaction submitted by one peer should be announced to, and served to, the node's other
+//! peers.
+
+use std::{io, net::SocketAddr, time::Duration};
+
+use tokio::time::Instant;
+
+use crate::{
+    protocol::{
+        message::Message,
+        payload::{block::Block, Inv, Tx},
+    },
+    setup::node::{Action, Node},
+    tools::synthetic_node::SyntheticNode,
+};
+
+/// The time allowed for the node to relay the transaction, which covers its trickling delay.
+const RELAY_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[tokio::test]
+#[allow(non_snake_case)]
+// This test should currently fail, since we have no way of generating a valid transaction the
+// node would accept into its mempool.
+async fn c034_TX_relayed_to_other_peers() {
+    // ZG-CONFORMANCE-034
+    //
+    // zcashd: fail, the coinbase transaction is rejected, this is expected as we cannot generate
+    //         a valid transaction. Accepted transactions are announced to the peers which set the
+    //         `relay` flag, trickled within a few seconds.
+    // zebra:  fail, the mempool is only enabled once the node is close to the chain tip, which
+    //         a fresh testnet node isn't.
+    let tx = Block::testnet_1().txs[0].clone();
+    let relayed = run_test_case(tx.clone()).await.unwrap();
+    assert_eq!(relayed, tx);
+}
+
+/// Submits `tx` to the node from a synthetic node, and returns the transaction served to a
+/// second synthetic node after the node announced it with an [`Inv`].
+async fn run_test_case(tx: Tx) -> io::Result<Tx> {
+    let mut node = Node::new()?;
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await?;
+
+    // The submitting peer (A).
+    let submitter = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await?;
+    // The observing peer (B), which asks for transactions to be relayed to it.
+    let mut observer = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .with_relay(true)
+        .build()
+        .await?;
+    observer.connect(node.addr()).await?;
+    submitter.connect(node.addr()).await?;
+
+    submitter.unicast(node.addr(), Message::Tx(tx.clone()))?;
+    let result = expect_relay(&mut observer, node.addr(), &tx).await;
+
+    // Gracefully shut down the nodes.
+    submitter.shut_down().await;
+    observer.shut_down().await;
+    node.stop()?;
+
+    result
+}
+
+/// Waits for the node to announce `tx` to the observer, then requests it with a `GetData`.
+async fn expect_relay(
+    observer: &mut SyntheticNode,
+    node_addr: SocketAddr,
+    tx: &Tx,
+) -> io::Result<Tx> {
+    let inv_hash = tx.inv_hash();
+    let deadline = Instant::now() + RELAY_TIMEOUT;
+
+    // Other messages, e.g. block announcements, are skipped.
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match observer.recv_message_timeout(remaining).await? {
+            (source, Message::Inv(inv))
+                if source == node_addr && inv.inventory.contains(&inv_hash) =>
+            {
+                break
+            }
+            _ => continue,
+        }
+    }
+
+    observer.unicast(node_addr, Message::GetData(Inv::new(vec![inv_hash])))?;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match observer.recv_message_timeout(remaining).await? {
+            (source, Message::Tx(relayed)) if source == node_addr => return Ok(relayed),
+            _ => continue,
+        }
+    }
+}
@@ -1,5 +1,9 @@
+mod block_announcement;
 mod handshake;
 mod invalid_message;
+mod messages;
 mod peering;
 mod query;
+mod sync;
 mod unsolicited_response;
+mod wrong_pong_nonce;
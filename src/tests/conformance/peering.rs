@@ -1,10 +1,10 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
 
 use assert_matches::assert_matches;
 
 use crate::{
     protocol::{
-        message::Message,
+        message::{constants::MAX_ADDRS, Message},
         payload::{addr::NetworkAddr, Addr},
     },
     setup::node::{Action, Node},
@@ -182,3 +182,327 @@ async fn c014_correctly_lists_peers() {
 
     node.stop().unwrap();
 }
+
+#[tokio::test]
+async fn c023_unsolicited_addr_is_propagated() {
+    // ZG-CONFORMANCE-023
+    //
+    // The node propagates addresses received in an unsolicited `Addr` to its other peers, either
+    // by relaying the `Addr` or by including the addresses in its `GetAddr` responses.
+    //
+    // Test procedure:
+    //
+    //  1. Connect two synthetic nodes to the node
+    //  2. The second synthetic node requests the node's peers with `GetAddr`
+    //  3. The first synthetic node pushes an unsolicited `Addr` with a novel address
+    //  4. Expect the second synthetic node to receive the novel address within the timeout,
+    //     re-requesting the node's peers periodically
+    //
+    // zcashd: relays addresses with a recent timestamp to a couple of its peers after a short
+    //         random delay.
+    //
+    // zebra:  doesn't relay unsolicited addresses, the address only shows up in `GetAddr` responses
+    //         once the node's cached response is refreshed.
+
+    // The propagation delay is implementation dependent, so allow for more than the usual timeout.
+    const PROPAGATION_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+    const GETADDR_INTERVAL: Duration = Duration::from_secs(10);
+
+    // A routable address, which the node has no way to know about.
+    let novel_addr = SocketAddr::from(([93, 184, 216, 1], 8233));
+
+    // Spin up a node instance.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    // Create the pushing and the receiving synthetic nodes.
+    let (mut synthetic_nodes, _) = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build_n(2)
+        .await
+        .unwrap();
+    let mut receiver = synthetic_nodes.pop().unwrap();
+    let pusher = synthetic_nodes.pop().unwrap();
+
+    receiver.connect(node.addr()).await.unwrap();
+    pusher.connect(node.addr()).await.unwrap();
+
+    receiver.unicast(node.addr(), Message::GetAddr).unwrap();
+
+    // Push the novel address with a fresh timestamp, so it's eligible for relaying.
+    pusher
+        .unicast(
+            node.addr(),
+            Message::Addr(Addr::new(vec![NetworkAddr::new(novel_addr)])),
+        )
+        .unwrap();
+
+    // Wait for the address to show up in any Addr the receiver gets.
+    let start = std::time::Instant::now();
+    let mut last_getaddr = start;
+    loop {
+        assert!(
+            start.elapsed() < PROPAGATION_TIMEOUT,
+            "the novel address wasn't propagated"
+        );
+
+        if last_getaddr.elapsed() >= GETADDR_INTERVAL {
+            receiver.unicast(node.addr(), Message::GetAddr).unwrap();
+            last_getaddr = std::time::Instant::now();
+        }
+
+        if let Ok((_, Message::Addr(addr))) = receiver.recv_message_timeout(GETADDR_INTERVAL).await
+        {
+            if addr
+                .iter()
+                .any(|network_addr| network_addr.addr == novel_addr)
+            {
+                break;
+            }
+        }
+    }
+
+    // Gracefully shut down the nodes.
+    pusher.shut_down().await;
+    receiver.shut_down().await;
+    node.stop().unwrap();
+}
+
+#[tokio::test]
+async fn c025_getaddr_response_is_capped() {
+    // ZG-CONFORMANCE-025
+    //
+    // The node never sends more than 1000 addresses in a single `Addr` message.
+    //
+    // A `GetAddr` response may be larger than that in aggregate (zcashd returns up to 2500 of its
+    // known addresses), in which case it must be split over multiple `Addr` messages.
+    //
+    // Test procedure:
+    //
+    //  1. Connect a synthetic node and push more than 1000 routable addresses, spread over
+    //     multiple `Addr` messages which are each within the limit
+    //  2. Connect a second synthetic node and request the node's peers with `GetAddr`
+    //  3. Collect the `Addr` replies until none arrive within the timeout
+    //  4. Assert no single `Addr` exceeds 1000 entries
+    //
+    // zcashd: only answers a single `GetAddr` per connection, with a random subset of its
+    //         address book.
+    //
+    // zebra:  answers with a random subset of its cached peers, which may not include all of the
+    //         pushed addresses.
+
+    const SEED_ADDRS: usize = 2 * MAX_ADDRS + MAX_ADDRS / 2;
+
+    // Routable addresses the node has no way to know about.
+    let seed_addrs: Vec<NetworkAddr> = (0..SEED_ADDRS)
+        .map(|i| {
+            NetworkAddr::new(SocketAddr::from((
+                [93, 184, (i / 256) as u8, i as u8],
+                8233,
+            )))
+        })
+        .collect();
+
+    // Spin up a node instance.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    let (mut synthetic_nodes, _) = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build_n(2)
+        .await
+        .unwrap();
+    let mut receiver = synthetic_nodes.pop().unwrap();
+    let pusher = synthetic_nodes.pop().unwrap();
+
+    // Seed the node's address book.
+    pusher.connect(node.addr()).await.unwrap();
+    for chunk in seed_addrs.chunks(MAX_ADDRS) {
+        pusher
+            .unicast(node.addr(), Message::Addr(Addr::new(chunk.to_vec())))
+            .unwrap();
+    }
+
+    receiver.connect(node.addr()).await.unwrap();
+    receiver.unicast(node.addr(), Message::GetAddr).unwrap();
+
+    // Collect the replies, the first one may take a while.
+    let mut addr_lens = Vec::new();
+    let mut recv_timeout = LONG_TIMEOUT;
+    while let Ok((_, message)) = receiver.recv_message_timeout(recv_timeout).await {
+        if let Message::Addr(addr) = message {
+            addr_lens.push(addr.addrs.len());
+            recv_timeout = Duration::from_secs(5);
+        }
+    }
+
+    // Gracefully shut down the nodes.
+    pusher.shut_down().await;
+    receiver.shut_down().await;
+    node.stop().unwrap();
+
+    assert!(!addr_lens.is_empty(), "the node didn't respond to GetAddr");
+    assert!(
+        addr_lens.iter().all(|&len| len <= MAX_ADDRS),
+        "an Addr exceeded {MAX_ADDRS} entries: {addr_lens:?}"
+    );
+}
+
+#[tokio::test]
+async fn c028_getaddr_cadence() {
+    // ZG-CONFORMANCE-028
+    //
+    // The node asks its outbound peers for their addresses with `GetAddr` shortly after the
+    // handshake, as part of its address discovery.
+    //
+    // Test procedure:
+    //
+    //  1. Start the node with a synthetic node as its initial peer
+    //  2. Wait for the node to connect and complete the handshake
+    //  3. Passively wait for the node's first `GetAddr`, measuring the delay since the handshake
+    //  4. Assert it arrives within the window
+    //
+    // zcashd: sends `GetAddr` right after the handshake on outbound connections (within a second),
+    //         as long as its address book holds fewer than 1000 entries; it doesn't repeat it.
+    //
+    // zebra:  sends `GetAddr` as part of its initial crawl, a few seconds after the handshake,
+    //         then periodically as its crawler runs.
+
+    // The time allowed between the handshake and the first GetAddr.
+    const GETADDR_WINDOW: Duration = Duration::from_secs(60);
+
+    let mut synthetic_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_message_filter(
+            MessageFilter::with_all_auto_reply().with_getaddr_filter(Filter::Disabled),
+        )
+        .build()
+        .await
+        .unwrap();
+
+    // Start node with the synthetic node as its initial peer.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .initial_peers(vec![synthetic_node.listening_addr().unwrap()])
+        .start()
+        .await
+        .unwrap();
+
+    // The handshake is done once the connection is registered.
+    let node_addr = synthetic_node.wait_for_connection().await;
+    let handshake_done = std::time::Instant::now();
+
+    // Ignore any other messages, e.g. GetHeaders, until the GetAddr arrives.
+    let result = loop {
+        let remaining = GETADDR_WINDOW.saturating_sub(handshake_done.elapsed());
+        match synthetic_node.recv_message_timeout(remaining).await {
+            Ok((source, Message::GetAddr)) if source == node_addr => {
+                break Ok(handshake_done.elapsed())
+            }
+            Ok(_) => continue,
+            Err(e) => break Err(e),
+        }
+    };
+
+    // Gracefully shut down the nodes.
+    synthetic_node.shut_down().await;
+    node.stop().unwrap();
+
+    let delay = result.expect("the node didn't send GetAddr within the window");
+    assert!(delay <= GETADDR_WINDOW, "GetAddr took {delay:?}");
+}
+
+#[tokio::test]
+async fn c030_inbound_connections_are_capped_by_max_peers() {
+    // ZG-CONFORMANCE-030
+    //
+    // The node doesn't accept more inbound connections than its configured peer limit allows.
+    // Connections beyond the limit are either refused outright or evict an existing peer, in
+    // both cases the number of active connections stays at the limit.
+    //
+    // Test procedure:
+    //
+    //  1. Start a node with max_peers set to `N`
+    //  2. Sequentially connect `N + K` synthetic nodes, counting the refused connections
+    //  3. Check which of the established connections are still alive, counting the evicted ones
+    //  4. Assert exactly `N - RESERVED_SLOTS` connections remain
+    //
+    // zcashd: reserves 8 slots for its own outbound connections, only `N - 8` inbound
+    //         connections are accepted. Once full, it tries to evict an existing inbound peer
+    //         and closes the new connection if none qualify.
+    //
+    // zebra:  doesn't map max_peers to an inbound limit (it sets the outbound target size) and
+    //         accepts all the connections. This is also why the flood tests configure their
+    //         limit with an extra margin (`* 2 + 10`).
+
+    // The peer limit the node is started with.
+    const MAX_PEERS: usize = 20;
+    // The number of connections attempted beyond the limit.
+    const EXTRA_PEERS: usize = 10;
+    // The slots the node keeps for its own outbound connections.
+    const RESERVED_SLOTS: usize = 8;
+    // The time allowed for a lost connection to surface.
+    const EVICTION_TIMEOUT: Duration = Duration::from_secs(2);
+
+    // Spin up a node instance.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .max_peers(MAX_PEERS)
+        .start()
+        .await
+        .unwrap();
+
+    let (mut synthetic_nodes, _) = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build_n(MAX_PEERS + EXTRA_PEERS)
+        .await
+        .unwrap();
+
+    // Connect sequentially so the node sees the connections in a fixed order.
+    let mut established = Vec::with_capacity(synthetic_nodes.len());
+    for synthetic_node in &synthetic_nodes {
+        established.push(synthetic_node.connect(node.addr()).await.is_ok());
+    }
+    let refused = established.iter().filter(|&&ok| !ok).count();
+
+    // Connections which were established may have been evicted since.
+    let mut evicted = 0;
+    let mut accepted = 0;
+    for (synthetic_node, _) in synthetic_nodes
+        .iter_mut()
+        .zip(established)
+        .filter(|(_, ok)| *ok)
+    {
+        if !synthetic_node.is_connected(node.addr())
+            || synthetic_node
+                .wait_for_disconnect(node.addr(), EVICTION_TIMEOUT)
+                .await
+                .is_ok()
+        {
+            evicted += 1;
+        } else {
+            accepted += 1;
+        }
+    }
+
+    // Gracefully shut down the nodes.
+    for synthetic_node in synthetic_nodes {
+        synthetic_node.shut_down().await;
+    }
+    node.stop().unwrap();
+
+    assert_eq!(
+        accepted,
+        MAX_PEERS - RESERVED_SLOTS,
+        "refused: {refused}, evicted: {evicted}"
+    );
+}
@@ -19,8 +19,12 @@ use std::io;
 
 use crate::{
     protocol::{
-        message::Message,
-        payload::{block::LocatorHashes, Hash, Inv},
+        message::{constants::MAX_GETBLOCKS_INV, Message},
+        payload::{
+            block::{expected_inv_after, LocatorHashes},
+            inv::InvByKind,
+            Hash, Inv,
+        },
     },
     tests::conformance::query::{run_test_query, SEED_BLOCKS},
 };
@@ -67,11 +71,7 @@ enum Response {
 impl Response {
     /// Creates a [`Response::Reply`] containing [`Message::Inv`] whose inventory
     /// hashes comprises of all [`SEED_BLOCKS`] in the given range.
-    ///
-    /// A missing end index is interpreted as `SEED_BLOCKS.len()`.
-    fn inv_with_range(start: usize, end: Option<usize>) -> Self {
-        let end = end.unwrap_or(SEED_BLOCKS.len());
-
+    fn inv_with_range(start: usize, end: usize) -> Self {
         let inv_hashes = SEED_BLOCKS[start..end]
             .iter()
             .map(|block| block.inv_hash())
@@ -79,6 +79,15 @@ impl Response {
 
         Self::Reply(Message::Inv(Inv::new(inv_hashes)).into())
     }
+
+    /// Creates a [`Response::Reply`] containing the [`Message::Inv`] a correct node sends for a
+    /// query without range limit, whose locator resolves to the [`SEED_BLOCKS`] at `index`.
+    fn inv_after(index: usize) -> Self {
+        let locator_tip = SEED_BLOCKS[index].double_sha256().unwrap();
+        let inv = expected_inv_after(&SEED_BLOCKS, locator_tip, MAX_GETBLOCKS_INV);
+
+        Self::Reply(Message::Inv(inv).into())
+    }
 }
 
 mod stop_hash_is_zero {
@@ -93,7 +102,7 @@ mod stop_hash_is_zero {
         let response = run_test_case(GetBlocks::from_indices(index, None))
             .await
             .unwrap();
-        let expected = Response::inv_with_range(index + 1, None);
+        let expected = Response::inv_after(index);
         assert_eq!(response, expected);
     }
 
@@ -105,7 +114,7 @@ mod stop_hash_is_zero {
         let response = run_test_case(GetBlocks::from_indices(index, None))
             .await
             .unwrap();
-        let expected = Response::inv_with_range(index + 1, None);
+        let expected = Response::inv_after(index);
         assert_eq!(response, expected);
     }
 
@@ -117,7 +126,7 @@ mod stop_hash_is_zero {
         let response = run_test_case(GetBlocks::from_indices(index, None))
             .await
             .unwrap();
-        let expected = Response::inv_with_range(index + 1, None);
+        let expected = Response::inv_after(index);
         assert_eq!(response, expected);
     }
 
@@ -129,7 +138,7 @@ mod stop_hash_is_zero {
         let response = run_test_case(GetBlocks::from_indices(index, None))
             .await
             .unwrap();
-        let expected = Response::inv_with_range(index + 1, None);
+        let expected = Response::inv_after(index);
         assert_eq!(response, expected);
     }
 
@@ -168,7 +177,7 @@ mod stop_hash_is_zero {
         );
 
         let response = run_test_case(query).await.unwrap();
-        let expected = Response::inv_with_range(index + 1, None);
+        let expected = Response::inv_after(index);
         assert_eq!(response, expected);
     }
 
@@ -191,9 +200,27 @@ mod stop_hash_is_zero {
         );
 
         let response = run_test_case(query).await.unwrap();
-        let expected = Response::inv_with_range(index + 1, None);
+        let expected = Response::inv_after(index);
         assert_eq!(response, expected);
     }
+
+    #[tokio::test]
+    #[allow(non_snake_case)]
+    async fn c016_t18_GET_BLOCKS_genesis_locator_inv_contents() {
+        // The Inv returned for a genesis locator contains exactly the hashes of all the seeded
+        // blocks after genesis, in chain order, and no other kinds of entries.
+        //
+        // zcashd: pass
+        let response = run_test_case(GetBlocks::from_indices(0, None))
+            .await
+            .unwrap();
+
+        let expected_hashes = SEED_BLOCKS[1..]
+            .iter()
+            .map(|block| block.double_sha256().unwrap())
+            .collect::<Vec<_>>();
+        assert_inv_blocks(&response, &expected_hashes);
+    }
 }
 
 mod stop_hash_is_start_hash {
@@ -258,7 +285,7 @@ mod ranged {
         let response = run_test_case(GetBlocks::from_indices(range.0, Some(range.1)))
             .await
             .unwrap();
-        let expected = Response::inv_with_range(range.0 + 1, Some(range.1));
+        let expected = Response::inv_with_range(range.0 + 1, range.1);
         assert_eq!(response, expected);
     }
 
@@ -270,7 +297,7 @@ mod ranged {
         let response = run_test_case(GetBlocks::from_indices(range.0, Some(range.1)))
             .await
             .unwrap();
-        let expected = Response::inv_with_range(range.0 + 1, Some(range.1));
+        let expected = Response::inv_with_range(range.0 + 1, range.1);
         assert_eq!(response, expected);
     }
 
@@ -282,7 +309,7 @@ mod ranged {
         let response = run_test_case(GetBlocks::from_indices(range.0, Some(range.1)))
             .await
             .unwrap();
-        let expected = Response::inv_with_range(range.0 + 1, Some(range.1));
+        let expected = Response::inv_with_range(range.0 + 1, range.1);
         assert_eq!(response, expected);
     }
 
@@ -316,7 +343,7 @@ mod ranged {
             SEED_BLOCKS[range.1].double_sha256().unwrap(),
         );
         let response = run_test_case(query).await.unwrap();
-        let expected = Response::inv_with_range(range.0 + 1, Some(range.1));
+        let expected = Response::inv_with_range(range.0 + 1, range.1);
         assert_eq!(response, expected);
     }
 
@@ -337,11 +364,31 @@ mod ranged {
         );
 
         let response = run_test_case(query).await.unwrap();
-        let expected = Response::inv_with_range(index + 1, None);
+        let expected = Response::inv_after(index);
         assert_eq!(response, expected);
     }
 }
 
+/// Asserts the response is a single [`Message::Inv`] whose entries are exactly blocks with the
+/// given hashes, in order.
+fn assert_inv_blocks(response: &Response, expected_hashes: &[Hash]) {
+    let inv = match response {
+        Response::Reply(message) => match &**message {
+            Message::Inv(inv) => inv,
+            other => panic!("expected an Inv, got {other:?}"),
+        },
+        other => panic!("expected a single Inv reply, got {other:?}"),
+    };
+
+    assert_eq!(
+        inv.by_kind(),
+        InvByKind {
+            blocks: expected_hashes.to_vec(),
+            ..Default::default()
+        }
+    );
+}
+
 /// A wrapper around [`run_test_query`] which maps its output to [`Response`].
 async fn run_test_case(query: GetBlocks) -> io::Result<Response> {
     let mut reply = run_test_query(query.0).await?;
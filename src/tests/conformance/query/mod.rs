@@ -13,6 +13,7 @@ mod basic_query;
 mod get_blocks;
 mod get_data;
 mod get_headers;
+mod not_found;
 
 lazy_static::lazy_static!(
     /// The blocks that the node is seeded with for this test module.
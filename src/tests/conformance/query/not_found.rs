@@ -0,0 +1,131 @@
+//! Contains test cases which cover ZG-CONFORMANCE-037
+//!
+//! The node partitions a `GetData` request for a mix of known and unknown inventory: it serves
+//! each known entry and lists the unknown entries in a [`Message::NotFound`].
+//!
+//! Note: Zebra does not support seeding with chain data and as such cannot run any of these tests successfully.
+//!
+//! Note: Zcashd currently ignores requests for non-existent blocks, but replies with a
+//! [`Message::NotFound`] for transactions which aren't in its mempool.
+
+use crate::{
+    protocol::{
+        message::Message,
+        payload::{inv::InvHash, Hash, Inv},
+    },
+    tests::conformance::query::{run_test_query, SEED_BLOCKS},
+};
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c037_t1_GET_DATA_known_blocks_and_unknown_txs() {
+    // zcashd: pass
+    // zebra:  fail (seeding not supported for zebra)
+    let known = blocks_inv(&[2, 4, 6]);
+    let unknown = Inv::new(vec![
+        InvHash::Tx(Hash::new([17; 32])),
+        InvHash::Tx(Hash::new([211; 32])),
+    ]);
+
+    let query = interleave(&known, &unknown);
+    assert_partitioned(query, known).await;
+}
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c037_t2_GET_DATA_known_and_unknown_blocks() {
+    // zcashd: fail (ignores non-existent blocks)
+    // zebra:  fail (seeding not supported for zebra)
+    let known = blocks_inv(&[1, 3]);
+    let unknown = Inv::new(vec![
+        InvHash::Block(Hash::new([17; 32])),
+        InvHash::Block(Hash::new([74; 32])),
+    ]);
+
+    let query = interleave(&known, &unknown);
+    assert_partitioned(query, known).await;
+}
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c037_t3_GET_DATA_mined_tx_is_not_found() {
+    // Mined transactions are only served from the mempool, so a request for one of the seeded
+    // blocks' transactions is not found even though its block is served.
+    //
+    // zcashd: pass
+    // zebra:  fail (seeding not supported for zebra)
+    let known = blocks_inv(&[5]);
+    let unknown = Inv::new(vec![SEED_BLOCKS[5].txs[0].inv_hash()]);
+
+    let query = interleave(&known, &unknown);
+    assert_partitioned(query, known).await;
+}
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c037_t4_GET_DATA_repeated_unknown_entries() {
+    // The unknown entries are each requested twice, the node may list them once or twice in its
+    // NotFound, but mustn't list any known entry.
+    //
+    // zcashd: pass
+    // zebra:  fail (seeding not supported for zebra)
+    let known = blocks_inv(&[7]);
+    let unknown = Inv::new(vec![
+        InvHash::Tx(Hash::new([17; 32])),
+        InvHash::Tx(Hash::new([211; 32])),
+        InvHash::Tx(Hash::new([17; 32])),
+        InvHash::Tx(Hash::new([211; 32])),
+    ]);
+
+    let query = interleave(&known, &unknown);
+    assert_partitioned(query, known).await;
+}
+
+/// Returns the inventory of the seed blocks at the given heights.
+fn blocks_inv(heights: &[usize]) -> Inv {
+    Inv::new(
+        heights
+            .iter()
+            .map(|&height| SEED_BLOCKS[height].inv_hash())
+            .collect(),
+    )
+}
+
+/// Returns a `GetData` query for the entries of `a` and `b`, interwoven together.
+fn interleave(a: &Inv, b: &Inv) -> Message {
+    let mut inventory = Vec::with_capacity(a.inventory.len() + b.inventory.len());
+    let (mut a, mut b) = (a.inventory.iter(), b.inventory.iter());
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => break,
+            (x, y) => inventory.extend(x.into_iter().chain(y).copied()),
+        }
+    }
+
+    Message::GetData(Inv::new(inventory))
+}
+
+/// Sends the query and asserts the node serves exactly the `known` entries, in order, and lists
+/// every other requested entry in its `NotFound` replies.
+async fn assert_partitioned(query: Message, known: Inv) {
+    let requested = match &query {
+        Message::GetData(inv) => inv.clone(),
+        _ => unreachable!("the query is a GetData"),
+    };
+
+    let response = run_test_query(query).await.unwrap();
+
+    let mut served = Inv::empty();
+    let mut not_found = Inv::empty();
+    for message in response {
+        match message {
+            Message::Block(block) => served.inventory.push(block.inv_hash()),
+            Message::Tx(tx) => served.inventory.push(tx.inv_hash()),
+            Message::NotFound(inv) => not_found.inventory.extend(inv.inventory),
+            message => panic!("Unexpected message received: {message:?}"),
+        }
+    }
+
+    assert_eq!(served, known);
+    assert_eq!(not_found.deduped(), requested.difference(&known).deduped());
+}
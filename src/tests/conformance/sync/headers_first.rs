@@ -0,0 +1,220 @@
+//! Contains test cases which cover ZG-CONFORMANCE-035.
+//!
+//! The node serves a headers-first sync: a peer repeatedly sends `GetHeaders` with the last
+//! header it received as the locator, until the node replies with an empty `Headers`.
+//!
+//! Note: Zebra does not support seeding with chain data and as such cannot run any of these tests
+//! successfully.
+
+use std::{io, net::SocketAddr};
+
+use crate::{
+    protocol::{
+        message::{constants::MAX_GETHEADERS_RESULTS, Message},
+        payload::{
+            block::{expected_headers_after, Block, Headers, LocatorHashes},
+            Hash,
+        },
+    },
+    setup::node::{Action, Node},
+    tools::{synthetic_node::SyntheticNode, LONG_TIMEOUT},
+};
+
+lazy_static::lazy_static!(
+    /// The blocks that the node is seeded with for this test module.
+    static ref SEED_BLOCKS: Vec<Block> = {
+        Block::initial_testnet_blocks()
+    };
+);
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c035_t1_HEADERS_sync_from_genesis() {
+    // ZG-CONFORMANCE-035
+    //
+    // Without a stop hash, the whole chain fits in a single batch (the node is seeded with fewer
+    // than 160 blocks), followed by an empty one.
+    //
+    // zcashd: pass
+    // zebra:  fail (seeding not supported)
+    let locator = vec![hash(0)];
+    let batches = run_test_case(locator, None).await.unwrap();
+
+    assert_eq!(batches, expected_batches(0, None));
+    assert_synced(&batches, 0);
+}
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c035_t2_HEADERS_sync_in_batches_with_hash_stop() {
+    // ZG-CONFORMANCE-035
+    //
+    // Each batch is bounded by a stop hash 3 blocks ahead of the locator, which the node includes
+    // as the last header of the batch.
+    //
+    // zcashd: pass
+    // zebra:  fail (seeding not supported)
+    let locator = vec![hash(0)];
+    let batches = run_test_case(locator, Some(3)).await.unwrap();
+
+    assert_eq!(batches, expected_batches(0, Some(3)));
+    assert_eq!(
+        batches
+            .iter()
+            .map(|batch| batch.headers.len())
+            .collect::<Vec<_>>(),
+        [3, 3, 3, 1, 0]
+    );
+    assert_synced(&batches, 0);
+}
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c035_t3_HEADERS_sync_resumes_from_dense_locator() {
+    // ZG-CONFORMANCE-035
+    //
+    // A peer resuming a sync sends a dense locator, newest first, which may start with hashes the
+    // node doesn't know. The node resumes from the first known hash.
+    //
+    // zcashd: pass
+    // zebra:  fail (seeding not supported)
+    let resume_height = 6;
+    let mut locator = vec![Hash::new([19; 32])];
+    locator.extend((0..=resume_height).rev().map(hash));
+
+    let batches = run_test_case(locator, Some(2)).await.unwrap();
+
+    assert_eq!(batches, expected_batches(resume_height, Some(2)));
+    assert_synced(&batches, resume_height);
+}
+
+/// Returns the hash of the seeded block at `height`.
+fn hash(height: usize) -> Hash {
+    SEED_BLOCKS[height].double_sha256().unwrap()
+}
+
+/// Returns the hash to stop the batch following the block at `tip` at, `batch_len` blocks ahead.
+///
+/// The stop hash is zeroed if there is no batch length, and is that of the last block if the
+/// batch would go beyond it.
+fn hash_stop(tip: usize, batch_len: Option<usize>) -> Hash {
+    batch_len.map_or_else(Hash::zeroed, |len| {
+        hash((tip + len).min(SEED_BLOCKS.len() - 1))
+    })
+}
+
+/// Returns the batches a correct node replies with during a sync starting after the block at
+/// `start`, ending with an empty batch.
+fn expected_batches(start: usize, batch_len: Option<usize>) -> Vec<Headers> {
+    let mut batches = Vec::new();
+    let mut tip = start;
+    loop {
+        let batch = expected_headers_after(
+            &SEED_BLOCKS,
+            hash(tip),
+            hash_stop(tip, batch_len),
+            MAX_GETHEADERS_RESULTS,
+        );
+        let len = batch.headers.len();
+        batches.push(batch);
+
+        if len == 0 {
+            return batches;
+        }
+        tip += len;
+    }
+}
+
+/// Asserts the batches respect the size limit and, put together, form the seeded chain following
+/// the block at `start`, in order.
+fn assert_synced(batches: &[Headers], start: usize) {
+    assert!(batches
+        .iter()
+        .all(|batch| batch.headers.len() <= MAX_GETHEADERS_RESULTS));
+
+    let headers = batches
+        .iter()
+        .flat_map(|batch| batch.headers.iter())
+        .collect::<Vec<_>>();
+    assert_eq!(headers.len(), SEED_BLOCKS.len() - start - 1);
+
+    let mut prev_hash = hash(start);
+    for header in headers {
+        assert_eq!(header.prev_block, prev_hash, "headers out of order");
+        prev_hash = header.double_sha256().unwrap();
+    }
+}
+
+/// Syncs the headers from a node seeded with the [`SEED_BLOCKS`], starting with `locator` and
+/// then continuing from the last received header, and returns the received batches.
+///
+/// With a `batch_len`, each request sets a stop hash `batch_len` blocks ahead of its locator.
+/// The sync ends with the first empty batch.
+async fn run_test_case(locator: Vec<Hash>, batch_len: Option<usize>) -> io::Result<Vec<Headers>> {
+    // Spin up a node instance with knowledge of the initial testnet-chain.
+    let mut node = Node::new()?;
+    node.initial_action(Action::SeedWithTestnetBlocks(SEED_BLOCKS.len()))
+        .start()
+        .await?;
+
+    let mut synthetic_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await?;
+    synthetic_node.connect(node.addr()).await?;
+
+    let result = sync_headers(&mut synthetic_node, node.addr(), locator, batch_len).await;
+
+    // Gracefully shut down the nodes.
+    synthetic_node.shut_down().await;
+    node.stop()?;
+
+    result
+}
+
+async fn sync_headers(
+    synthetic_node: &mut SyntheticNode,
+    node_addr: SocketAddr,
+    mut locator: Vec<Hash>,
+    batch_len: Option<usize>,
+) -> io::Result<Vec<Headers>> {
+    let mut batches = Vec::new();
+
+    // A correct node never needs more requests than there are blocks, this bounds the sync if it
+    // keeps replying with the same headers.
+    for _ in 0..=SEED_BLOCKS.len() {
+        // The stop hash is computed from the locator's first known hash, as the node would.
+        let tip = locator
+            .iter()
+            .find_map(|hash| {
+                SEED_BLOCKS
+                    .iter()
+                    .position(|block| block.double_sha256().unwrap() == *hash)
+            })
+            .unwrap_or(0);
+
+        let request = LocatorHashes::new(locator.clone(), hash_stop(tip, batch_len));
+        synthetic_node.unicast(node_addr, Message::GetHeaders(request))?;
+
+        let batch = loop {
+            match synthetic_node.recv_message_timeout(LONG_TIMEOUT).await? {
+                (_, Message::Headers(headers)) => break headers,
+                // Other messages, e.g. the node's own GetHeaders, are ignored.
+                _ => continue,
+            }
+        };
+
+        let last_hash = batch.headers.last().map(|header| header.double_sha256());
+        batches.push(batch);
+        match last_hash {
+            Some(hash) => locator = vec![hash?],
+            None => return Ok(batches),
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "the node kept serving headers past the end of its chain",
+    ))
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use tokio::time::{sleep, timeout};
+
+use crate::{
+    protocol::message::Message,
+    setup::node::{Action, Node},
+    tools::{synthetic_node::SyntheticNode, LONG_TIMEOUT, RECV_TIMEOUT},
+};
+
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn c033_PONG_with_wrong_nonce_is_ignored() {
+    // ZG-CONFORMANCE-033
+    //
+    // The node ignores a `Pong` whose nonce doesn't match the one of its `Ping`, and keeps the
+    // connection open.
+    //
+    // The synthetic node auto-replies to the node's `Ping` with a random nonce, then checks the
+    // node still answers its own `Ping`.
+    //
+    // zcashd: pass, pings right after the handshake and only logs the nonce mismatch.
+    // zebra:  pass, the mismatched `Pong` is dropped; the connection is only closed once the
+    //         heartbeat's own request times out, well after this test completes.
+
+    // The time allowed for the node's reaction to the mismatched Pong.
+    const REACTION_TIMEOUT: Duration = Duration::from_secs(2);
+
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    let mut synthetic_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .with_wrong_pong_nonce()
+        .with_message_history(usize::MAX)
+        .build()
+        .await
+        .unwrap();
+    synthetic_node.connect(node.addr()).await.unwrap();
+
+    // Wait for the node's Ping, which is auto-replied to with a mismatched Pong.
+    let pinged = timeout(LONG_TIMEOUT, async {
+        while !synthetic_node
+            .recent_messages(node.addr(), usize::MAX)
+            .iter()
+            .any(|message| matches!(message, Message::Ping(..)))
+        {
+            sleep(RECV_TIMEOUT).await;
+        }
+    })
+    .await;
+
+    // Give the node some time to react, then check the connection is still usable.
+    sleep(REACTION_TIMEOUT).await;
+    let result = synthetic_node
+        .ping_pong_timeout(node.addr(), RECV_TIMEOUT)
+        .await;
+
+    // Gracefully shut down the nodes.
+    synthetic_node.shut_down().await;
+    node.stop().unwrap();
+
+    pinged.expect("the node didn't send a Ping");
+    result.unwrap();
+}
@@ -160,7 +160,7 @@ async fn node_run_forever(cfg: DevTestCfg) {
                 .build()
                 .await
                 .unwrap();
-            initial_peers.push(synthetic_node.listening_addr());
+            initial_peers.push(synthetic_node.listening_addr().unwrap());
             Some(synthetic_node)
         }
         _ => None,
@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 
 use tokio::time::Duration;
 use ziggurat_core_metrics::{
@@ -10,9 +10,13 @@ use ziggurat_core_metrics::{
 use crate::{
     protocol::{
         message::Message,
-        payload::{block::Block, Inv},
+        payload::{block::Block, Inv, Nonce},
     },
     setup::node::{Action, Node},
+    tests::performance::{
+        record_request_latency, register_request_latencies, LATENCY_GETDATA, LATENCY_PING,
+        REQUEST_LATENCY_METRICS,
+    },
     tools::synthetic_node::SyntheticNode,
 };
 
@@ -175,3 +179,143 @@ async fn p001_t2_GET_DATA_BLOCKS_throughput() {
     // Display various percentiles
     println!("\r\n{table}");
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+#[allow(non_snake_case)]
+async fn p001_t4_GET_DATA_BLOCKS_and_PING_latency_per_message_type() {
+    // ZG-PERFORMANCE-001, GetData-Block and Ping latencies per message type
+    //
+    // The node behaves as expected under load from other peers.
+    //
+    // Each peer alternates block requests and Pings, as their cost profiles differ the latencies
+    // are recorded per request type and displayed in separate tables.
+    //
+    // Note: This test only asserts that the latencies of both request types are kept apart, the
+    //       results tables require manual inspection as they rely on the machine running the
+    //       test.
+    //
+    // ZCashd: pass
+    //
+    // Zebra: Does not support block seeding and therefore cannot run this test.
+
+    // number of block requests (each followed by a Ping) to send per peer
+    const REQUESTS: usize = 100;
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+    // number of concurrent peers to test
+    let synth_counts = vec![1, 10, 50, 100];
+
+    // One table per request type.
+    let mut tables: BTreeMap<&str, LatencyRequestsTable> = BTreeMap::new();
+
+    // Start node seeded with initial testnet blocks,
+    // with max peers set so that our peers should never be rejected.
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::SeedWithTestnetBlocks(11))
+        .max_peers(synth_counts.iter().max().unwrap() * 2 + 10)
+        .start()
+        .await
+        .unwrap();
+    let node_addr = node.addr();
+
+    for synth_count in synth_counts {
+        // setup metrics recorder
+        let test_metrics = TestMetrics::default();
+        // register metrics
+        register_request_latencies();
+
+        let mut synth_handles = Vec::with_capacity(synth_count);
+
+        let test_start = tokio::time::Instant::now();
+
+        for _ in 0..synth_count {
+            let requests = Block::initial_testnet_blocks()
+                .into_iter()
+                .map(|block| {
+                    (
+                        Message::GetData(Inv::new(vec![block.inv_hash()])),
+                        Box::new(block),
+                    )
+                })
+                .collect::<VecDeque<_>>();
+
+            synth_handles.push(tokio::spawn(async move {
+                let mut synth_node = SyntheticNode::builder()
+                    .with_full_handshake()
+                    .with_all_auto_reply()
+                    .build()
+                    .await
+                    .unwrap();
+
+                synth_node.connect(node_addr).await.unwrap();
+
+                for i in 0..REQUESTS {
+                    let (request, expected) = &requests[i % requests.len()];
+                    synth_node.unicast(node_addr, request.clone()).unwrap();
+                    let now = tokio::time::Instant::now();
+                    match synth_node.recv_message_timeout(REQUEST_TIMEOUT).await {
+                        Err(_timeout) => break,
+                        Ok((_, Message::Block(block))) if &block == expected => {
+                            record_request_latency(request, now.elapsed());
+                        }
+                        Ok((_, bad_reply)) => {
+                            panic!("Failed to receive Block, got {bad_reply:?}");
+                        }
+                    }
+
+                    let nonce = Nonce::default();
+                    let ping = Message::Ping(nonce);
+                    synth_node.unicast(node_addr, ping.clone()).unwrap();
+                    let now = tokio::time::Instant::now();
+                    match synth_node.recv_message_timeout(REQUEST_TIMEOUT).await {
+                        Err(_timeout) => break,
+                        Ok((_, Message::Pong(pong_nonce))) if pong_nonce == nonce => {
+                            record_request_latency(&ping, now.elapsed());
+                        }
+                        Ok((_, bad_reply)) => {
+                            panic!("Failed to receive Pong, got {bad_reply:?}");
+                        }
+                    }
+                }
+                synth_node.shut_down().await;
+            }));
+        }
+
+        // wait for peers to complete
+        for handle in synth_handles {
+            handle.await.unwrap();
+        }
+
+        let time_taken_secs = test_start.elapsed().as_secs_f64();
+
+        let snapshot = test_metrics.take_snapshot();
+        for metric in REQUEST_LATENCY_METRICS {
+            if let Some(latencies) = snapshot.construct_histogram(metric) {
+                if latencies.entries() >= 1 {
+                    // add stats to the table display of this request type
+                    tables
+                        .entry(metric)
+                        .or_default()
+                        .add_row(LatencyRequestStats::new(
+                            synth_count as u16,
+                            REQUESTS as u16,
+                            latencies,
+                            time_taken_secs,
+                        ));
+                }
+            }
+        }
+    }
+
+    node.stop().unwrap();
+
+    // Display various percentiles, per request type
+    for (metric, table) in &tables {
+        println!("\r\n{metric}\r\n{table}");
+    }
+
+    // GetData and Ping latencies are kept apart.
+    assert_eq!(
+        tables.keys().copied().collect::<Vec<_>>(),
+        vec![LATENCY_GETDATA, LATENCY_PING]
+    );
+}
@@ -1,3 +1,50 @@
 mod connections;
 mod getdata_blocks;
 mod ping_pong;
+
+use std::time::Duration;
+
+use ziggurat_core_metrics::tables::duration_as_ms;
+
+use crate::protocol::message::Message;
+
+const LATENCY_PING: &str = "perf_latency_ping";
+const LATENCY_GETADDR: &str = "perf_latency_getaddr";
+const LATENCY_GETHEADERS: &str = "perf_latency_getheaders";
+const LATENCY_GETBLOCKS: &str = "perf_latency_getblocks";
+const LATENCY_GETDATA: &str = "perf_latency_getdata";
+const LATENCY_OTHER: &str = "perf_latency_other";
+
+/// The latency metrics recorded by [`record_request_latency`], one per request message type.
+const REQUEST_LATENCY_METRICS: [&str; 6] = [
+    LATENCY_PING,
+    LATENCY_GETADDR,
+    LATENCY_GETHEADERS,
+    LATENCY_GETBLOCKS,
+    LATENCY_GETDATA,
+    LATENCY_OTHER,
+];
+
+/// Returns the latency metric for requests of the same message type as `request`.
+fn request_latency_metric(request: &Message) -> &'static str {
+    match request {
+        Message::Ping(_) => LATENCY_PING,
+        Message::GetAddr => LATENCY_GETADDR,
+        Message::GetHeaders(_) => LATENCY_GETHEADERS,
+        Message::GetBlocks(_) => LATENCY_GETBLOCKS,
+        Message::GetData(_) => LATENCY_GETDATA,
+        _ => LATENCY_OTHER,
+    }
+}
+
+/// Registers the latency histograms of all the request message types.
+fn register_request_latencies() {
+    for metric in REQUEST_LATENCY_METRICS {
+        metrics::register_histogram!(metric);
+    }
+}
+
+/// Records the latency of a reply to `request`, keyed by its message type.
+fn record_request_latency(request: &Message, latency: Duration) {
+    metrics::histogram!(request_latency_metric(request), duration_as_ms(latency));
+}
@@ -7,9 +7,8 @@ use ziggurat_core_metrics::{
 };
 
 use crate::{
-    protocol::{message::Message, payload::Nonce},
     setup::node::{Action, Node},
-    tools::synthetic_node::SyntheticNode,
+    tools::{synthetic_node::SyntheticNode, LONG_TIMEOUT},
 };
 
 const PINGS: u16 = 1000;
@@ -205,6 +204,41 @@ async fn p001_t1_PING_PONG_throughput() {
     println!("\r\n{table}");
 }
 
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn p001_t3_PING_PONG_latency_on_localhost() {
+    // ZG-PERFORMANCE-001, Ping-Pong latency
+    //
+    // A single peer on localhost measures the node's Ping-Pong round trip. As network time is
+    // negligible, this is effectively the node's processing time and should be well below a second.
+    //
+    // zcashd: pass
+    // zebra:  pass
+
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    let mut synth_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await
+        .unwrap();
+    synth_node.connect(node.addr()).await.unwrap();
+
+    let latency = synth_node
+        .ping_pong_measured(node.addr(), LONG_TIMEOUT)
+        .await
+        .unwrap();
+    assert!(latency < Duration::from_secs(1), "latency was {latency:?}");
+
+    synth_node.shut_down().await;
+    node.stop().unwrap();
+}
+
 async fn simulate_peer(node_addr: SocketAddr) {
     // Create a synthetic node, enable handshaking and auto-reply
     let mut synth_node = SyntheticNode::builder()
@@ -217,22 +251,14 @@ async fn simulate_peer(node_addr: SocketAddr) {
     synth_node.connect(node_addr).await.unwrap();
 
     for _ in 0..PINGS {
-        let nonce = Nonce::default();
-        let expected = Message::Pong(nonce);
-
-        // send Ping(nonce)
-        synth_node.unicast(node_addr, Message::Ping(nonce)).unwrap();
-
-        let now = tokio::time::Instant::now();
         match synth_node
-            .recv_message_timeout(Duration::from_secs(5))
+            .ping_pong_measured(node_addr, Duration::from_secs(5))
             .await
         {
-            Ok((_, reply)) => {
-                assert_eq!(reply, expected);
-                metrics::histogram!(METRIC_LATENCY, duration_as_ms(now.elapsed()));
+            Ok(latency) => {
+                metrics::histogram!(METRIC_LATENCY, duration_as_ms(latency));
             }
-            Err(_timeout) => break,
+            Err(_err) => break,
         }
     }
 }
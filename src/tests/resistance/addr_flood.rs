@@ -0,0 +1,156 @@
+//! Contains tests flooding the node with maximum-size `Addr` messages from several peers.
+
+use std::{io, net::SocketAddr};
+
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    protocol::{
+        message::{constants::MAX_ADDRS, Message},
+        payload::{Addr, Nonce},
+    },
+    setup::node::{Action, Node},
+    tools::{
+        fuzzing::{addr_from_pool, random_addr, seeded_rng},
+        synthetic_node::SyntheticNode,
+        LONG_TIMEOUT,
+    },
+};
+
+/// The number of peers flooding the node concurrently.
+const PEERS: usize = 5;
+/// The number of rounds in which each peer sends [`MESSAGES_PER_ROUND`] `Addr` messages.
+const ROUNDS: usize = 10;
+const MESSAGES_PER_ROUND: usize = 10;
+/// The number of distinct addresses the duplicate entries are drawn from.
+const DUPLICATE_POOL_LEN: usize = 10;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn r013_t1_addr_flood_unique_entries() {
+    // ZG-RESISTANCE-013 (part 1)
+    //
+    // Each peer floods the node with maximum-size Addr messages made of new addresses.
+    //
+    // zcashd: pass (rate-limits the processing of the gossiped addresses).
+    // zebra:  pass
+
+    run_flood(|rng, _| random_addr(rng, MAX_ADDRS)).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn r013_t2_addr_flood_duplicate_entries() {
+    // ZG-RESISTANCE-013 (part 2)
+    //
+    // Each peer floods the node with maximum-size Addr messages repeating a handful of addresses.
+    //
+    // zcashd: pass
+    // zebra:  pass
+
+    let mut rng = seeded_rng();
+    let pool = random_addr(&mut rng, DUPLICATE_POOL_LEN)
+        .iter()
+        .map(|entry| entry.addr)
+        .collect::<Vec<_>>();
+
+    run_flood(move |rng, _| addr_from_pool(rng, MAX_ADDRS, &pool)).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn r013_t3_addr_flood_self_entries() {
+    // ZG-RESISTANCE-013 (part 3)
+    //
+    // Each peer floods the node with maximum-size Addr messages advertising the node's own
+    // address and the peers' addresses.
+    //
+    // zcashd: pass
+    // zebra:  pass
+
+    run_flood(|rng, self_addrs| addr_from_pool(rng, MAX_ADDRS, self_addrs)).await;
+}
+
+/// Connects [`PEERS`] synthetic nodes to the node, then floods it with the `Addr` messages
+/// returned by `gen_addr` over [`ROUNDS`] rounds.
+///
+/// `gen_addr` is supplied with the node's address and those of the peers. After each round, every
+/// peer checks the node is still responsive with a ping-pong, so a node crashing or stalling
+/// under the load of the addresses it stores fails the test. Finally, the node must still accept
+/// new connections.
+async fn run_flood<F>(mut gen_addr: F)
+where
+    F: FnMut(&mut ChaCha8Rng, &[SocketAddr]) -> Addr,
+{
+    let mut rng = seeded_rng();
+
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    let mut synth_nodes = Vec::with_capacity(PEERS);
+    for _ in 0..PEERS {
+        let synth_node = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_all_auto_reply()
+            .build()
+            .await
+            .unwrap();
+        synth_node.connect(node.addr()).await.unwrap();
+        synth_nodes.push(synth_node);
+    }
+
+    let mut self_addrs = vec![node.addr()];
+    self_addrs.extend(
+        synth_nodes
+            .iter()
+            .map(|synth_node| synth_node.listening_addr().unwrap()),
+    );
+
+    for round in 0..ROUNDS {
+        for synth_node in &synth_nodes {
+            for _ in 0..MESSAGES_PER_ROUND {
+                let addr = gen_addr(&mut rng, &self_addrs);
+                synth_node
+                    .unicast(node.addr(), Message::Addr(addr))
+                    .unwrap();
+            }
+        }
+
+        for synth_node in &mut synth_nodes {
+            if let Err(e) = ping_pong_skipping_relays(synth_node, node.addr()).await {
+                panic!("the node became unresponsive in round {round}: {e}");
+            }
+        }
+    }
+
+    // The node still accepts new peers.
+    let late_peer = SyntheticNode::builder()
+        .with_full_handshake()
+        .build()
+        .await
+        .unwrap();
+    late_peer.connect(node.addr()).await.unwrap();
+
+    late_peer.shut_down().await;
+    for synth_node in synth_nodes {
+        synth_node.shut_down().await;
+    }
+    node.stop().unwrap();
+}
+
+/// Sends a `Ping` and waits for the matching `Pong`, skipping any other message, as the node may
+/// relay some of the flooded addresses to the peers.
+async fn ping_pong_skipping_relays(
+    synth_node: &mut SyntheticNode,
+    node_addr: SocketAddr,
+) -> io::Result<()> {
+    let nonce = Nonce::default();
+    synth_node.unicast(node_addr, Message::Ping(nonce))?;
+
+    loop {
+        match synth_node.recv_message_timeout(LONG_TIMEOUT).await? {
+            (_, Message::Pong(rx_nonce)) if rx_nonce == nonce => return Ok(()),
+            _ => continue,
+        }
+    }
+}
@@ -7,8 +7,8 @@ use crate::{
     tests::resistance::{DISCONNECT_TIMEOUT, ITERATIONS},
     tools::{
         fuzzing::{
-            default_fuzz_messages, encode_message_with_corrupt_checksum,
-            encode_messages_with_corrupt_checksum, seeded_rng,
+            corpus::CorpusRecorder, default_fuzz_messages, encode_message_with_corrupt_checksum,
+            encode_messages_with_corrupt_checksum, seeded_rng_with_seed,
         },
         synthetic_node::SyntheticNode,
     },
@@ -21,7 +21,8 @@ async fn r001_t5_instead_of_version_when_node_receives_connection() {
     // zebra: sends a version before disconnecting.
     // zcashd: ignores the messages but doesn't disconnect (logs show a `CHECKSUM ERROR`).
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
+    let mut corpus = CorpusRecorder::new("r001_t5", seed);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -34,6 +35,7 @@ async fn r001_t5_instead_of_version_when_node_receives_connection() {
     for _ in 0..ITERATIONS {
         let message = test_messages.choose(&mut rng).unwrap();
         let payload = encode_message_with_corrupt_checksum(&mut rng, message);
+        corpus.record(std::slice::from_ref(&payload));
 
         let mut synth_node = SyntheticNode::builder()
             .with_all_auto_reply()
@@ -62,7 +64,8 @@ async fn r002_t5_instead_of_verack_when_node_receives_connection() {
     // zebra: sends a verack before disconnecting.
     // zcashd: logs indicate message was ignored, doesn't disconnect.
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
+    let mut corpus = CorpusRecorder::new("r002_t5", seed);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -75,6 +78,7 @@ async fn r002_t5_instead_of_verack_when_node_receives_connection() {
     for _ in 0..ITERATIONS {
         let message = test_messages.choose(&mut rng).unwrap();
         let payload = encode_message_with_corrupt_checksum(&mut rng, message);
+        corpus.record(std::slice::from_ref(&payload));
 
         let mut synth_node = SyntheticNode::builder()
             .with_all_auto_reply()
@@ -104,11 +108,12 @@ async fn r003_t5_instead_of_version_when_node_initiates_connection() {
     // zebra: disconnects immediately.
     // zcashd: Messages appear to get ignored
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
 
     let test_messages = default_fuzz_messages();
 
     let mut payloads = encode_messages_with_corrupt_checksum(&mut rng, ITERATIONS, &test_messages);
+    let _corpus = CorpusRecorder::new("r003_t5", seed).with_payloads(&payloads);
 
     // create peers (we need their ports to give to the node)
     let (synth_nodes, synth_addrs) = SyntheticNode::builder()
@@ -163,11 +168,12 @@ async fn r004_t5_instead_of_verack_when_node_initiates_connection() {
     // zcashd: Messages get ignored (some get logged as bad checksum),
     //         node sends GetAddr, Ping and GetHeaders.
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
 
     let test_messages = default_fuzz_messages();
 
     let mut payloads = encode_messages_with_corrupt_checksum(&mut rng, ITERATIONS, &test_messages);
+    let _corpus = CorpusRecorder::new("r004_t5", seed).with_payloads(&payloads);
 
     // create peers (we need their ports to give to the node)
     let (synth_nodes, synth_addrs) = SyntheticNode::builder()
@@ -222,7 +228,8 @@ async fn r005_t5_post_handshake() {
     // zebra: disconnects.
     // zcashd: logs indicate message was ignored, doesn't disconnect.
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
+    let mut corpus = CorpusRecorder::new("r005_t5", seed);
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
         .start()
@@ -234,6 +241,7 @@ async fn r005_t5_post_handshake() {
     for _ in 0..ITERATIONS {
         let message = test_messages.choose(&mut rng).unwrap();
         let payload = encode_message_with_corrupt_checksum(&mut rng, message);
+        corpus.record(std::slice::from_ref(&payload));
 
         let mut synth_node = SyntheticNode::builder()
             .with_full_handshake()
@@ -7,8 +7,8 @@ use crate::{
     tests::resistance::{DISCONNECT_TIMEOUT, ITERATIONS},
     tools::{
         fuzzing::{
-            default_fuzz_messages, encode_message_with_corrupt_body_length,
-            encode_messages_with_corrupt_body_length, seeded_rng,
+            corpus::CorpusRecorder, default_fuzz_messages, encode_message_with_corrupt_body_length,
+            encode_messages_with_corrupt_body_length, seeded_rng_with_seed,
         },
         synthetic_node::SyntheticNode,
     },
@@ -21,7 +21,8 @@ async fn r001_t6_tinstead_of_version_when_node_receives_connection() {
     // zebra: sends version before disconnecting.
     // zcashd: disconnects.
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
+    let mut corpus = CorpusRecorder::new("r001_t6", seed);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -41,6 +42,7 @@ async fn r001_t6_tinstead_of_version_when_node_receives_connection() {
 
         let message = test_messages.choose(&mut rng).unwrap();
         let payload = encode_message_with_corrupt_body_length(&mut rng, message);
+        corpus.record(std::slice::from_ref(&payload));
 
         synth_node.send_direct_bytes(node.addr(), payload).unwrap();
 
@@ -60,7 +62,8 @@ async fn r002_t6_instead_of_verack_when_node_receives_connection() {
     // zebra: disconnects.
     // zcashd: disconnects (after sending verack).
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
+    let mut corpus = CorpusRecorder::new("r002_t6", seed);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -81,6 +84,7 @@ async fn r002_t6_instead_of_verack_when_node_receives_connection() {
 
         let message = test_messages.choose(&mut rng).unwrap();
         let payload = encode_message_with_corrupt_body_length(&mut rng, message);
+        corpus.record(std::slice::from_ref(&payload));
 
         synth_node.send_direct_bytes(node.addr(), payload).unwrap();
 
@@ -100,12 +104,13 @@ async fn r003_t6_instead_of_version_when_node_initiates_connection() {
     // zebra: disconnects
     // zcashd: disconnects, very slow running
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
 
     let test_messages = default_fuzz_messages();
 
     let mut payloads =
         encode_messages_with_corrupt_body_length(&mut rng, ITERATIONS, &test_messages);
+    let _corpus = CorpusRecorder::new("r003_t6", seed).with_payloads(&payloads);
 
     // create peers (we need their ports to give to the node)
     let (synth_nodes, synth_addrs) = SyntheticNode::builder()
@@ -163,12 +168,13 @@ async fn r004_t6_instead_of_verack_when_node_initiates_connection() {
     //
     // zcashd: sends GetAddr, Ping, GetHeaders then disconnects
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
 
     let test_messages = default_fuzz_messages();
 
     let mut payloads =
         encode_messages_with_corrupt_body_length(&mut rng, ITERATIONS, &test_messages);
+    let _corpus = CorpusRecorder::new("r004_t6", seed).with_payloads(&payloads);
 
     // create peers (we need their ports to give to the node)
     let (synth_nodes, synth_addrs) = SyntheticNode::builder()
@@ -225,7 +231,8 @@ async fn r005_t6_post_handshake() {
     // zebra: disconnects.
     // zcashd: disconnects (sometimes sends ping and getheaders)
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
+    let mut corpus = CorpusRecorder::new("r005_t6", seed);
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
         .start()
@@ -245,6 +252,7 @@ async fn r005_t6_post_handshake() {
 
         let message = test_messages.choose(&mut rng).unwrap();
         let payload = encode_message_with_corrupt_body_length(&mut rng, message);
+        corpus.record(std::slice::from_ref(&payload));
 
         synth_node.send_direct_bytes(node.addr(), payload).unwrap();
 
@@ -9,7 +9,10 @@ use crate::{
     setup::node::{Action, Node},
     tests::resistance::{DISCONNECT_TIMEOUT, ITERATIONS},
     tools::{
-        fuzzing::{default_fuzz_messages, encode_slightly_corrupted_messages, seeded_rng},
+        fuzzing::{
+            corpus::CorpusRecorder, default_fuzz_messages, encode_slightly_corrupted_messages,
+            seeded_rng_with_seed,
+        },
         synthetic_node::SyntheticNode,
     },
 };
@@ -23,8 +26,9 @@ async fn r001_t4_instead_of_version_when_node_receives_connection() {
 
     let test_messages = default_fuzz_messages();
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let payloads = encode_slightly_corrupted_messages(&mut rng, ITERATIONS, &test_messages);
+    let _corpus = CorpusRecorder::new("r001_t4", seed).with_payloads(&payloads);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -58,8 +62,9 @@ async fn r002_t4_instead_of_verack_when_node_receives_connection() {
 
     let test_messages = default_fuzz_messages();
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let payloads = encode_slightly_corrupted_messages(&mut rng, ITERATIONS, &test_messages);
+    let _corpus = CorpusRecorder::new("r002_t4", seed).with_payloads(&payloads);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -98,8 +103,9 @@ async fn r003_t4_instead_of_version_when_node_initiates_connection() {
 
     let test_messages = default_fuzz_messages();
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let mut payloads = encode_slightly_corrupted_messages(&mut rng, ITERATIONS, &test_messages);
+    let _corpus = CorpusRecorder::new("r003_t4", seed).with_payloads(&payloads);
 
     // create peers (we need their ports to give to the node)
     let (synth_nodes, synth_addrs) = SyntheticNode::builder()
@@ -159,8 +165,9 @@ async fn r004_t4_instead_of_verack_when_node_initiates_connection() {
 
     let test_messages = default_fuzz_messages();
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let mut payloads = encode_slightly_corrupted_messages(&mut rng, ITERATIONS, &test_messages);
+    let _corpus = CorpusRecorder::new("r004_t4", seed).with_payloads(&payloads);
 
     // create peers (we need their ports to give to the node)
     let (synth_nodes, synth_addrs) = SyntheticNode::builder()
@@ -219,8 +226,9 @@ async fn r005_t4_post_handshake() {
 
     let test_messages = default_fuzz_messages();
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let payloads = encode_slightly_corrupted_messages(&mut rng, ITERATIONS, &test_messages);
+    let _corpus = CorpusRecorder::new("r005_t4", seed).with_payloads(&payloads);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -5,7 +5,10 @@ use crate::{
     setup::node::{Action, Node},
     tests::resistance::{DISCONNECT_TIMEOUT, ITERATIONS},
     tools::{
-        fuzzing::{metadata_compliant_random_bytes, seeded_rng, COMMANDS_WITH_PAYLOADS},
+        fuzzing::{
+            corpus::CorpusRecorder, metadata_compliant_random_bytes, seeded_rng_with_seed,
+            COMMANDS_WITH_PAYLOADS,
+        },
         synthetic_node::SyntheticNode,
     },
 };
@@ -19,8 +22,9 @@ async fn r001_t3_instead_of_version_when_node_receives_connection() {
     // zcashd: just ignores the message and doesn't disconnect.
 
     // Payloadless messages are omitted.
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let payloads = metadata_compliant_random_bytes(&mut rng, ITERATIONS, &COMMANDS_WITH_PAYLOADS);
+    let _corpus = CorpusRecorder::new("r001_t3", seed).with_payloads(&payloads);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -55,8 +59,9 @@ async fn r002_t3_instead_of_verack_when_node_receives_connection() {
     // zcashd: responds with reject, ccode malformed and doesn't disconnect.
 
     // Payloadless messages are omitted.
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let payloads = metadata_compliant_random_bytes(&mut rng, ITERATIONS, &COMMANDS_WITH_PAYLOADS);
+    let _corpus = CorpusRecorder::new("r002_t3", seed).with_payloads(&payloads);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -94,9 +99,10 @@ async fn r003_t3_instead_of_version_when_node_initiates_connection() {
     // Note: zcashd is two orders of magnitude slower (~52 vs ~0.5 seconds)
 
     // Payloadless messages are omitted.
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let mut payloads =
         metadata_compliant_random_bytes(&mut rng, ITERATIONS, &COMMANDS_WITH_PAYLOADS);
+    let _corpus = CorpusRecorder::new("r003_t3", seed).with_payloads(&payloads);
 
     // create peers (we need their ports to give to the node)
     let (synth_nodes, synth_addrs) = SyntheticNode::builder()
@@ -155,9 +161,10 @@ async fn r004_t3_instead_of_verack_when_node_initiates_connection() {
     // Caution: zcashd takes extremely long in this test
 
     // Payloadless messages are omitted.
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let mut payloads =
         metadata_compliant_random_bytes(&mut rng, ITERATIONS, &COMMANDS_WITH_PAYLOADS);
+    let _corpus = CorpusRecorder::new("r004_t3", seed).with_payloads(&payloads);
 
     // create peers (we need their ports to give to the node)
     let (synth_nodes, synth_addrs) = SyntheticNode::builder()
@@ -214,8 +221,9 @@ async fn r005_t3_post_handshake() {
     // for instance.
 
     // Payloadless messages are omitted.
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let payloads = metadata_compliant_random_bytes(&mut rng, ITERATIONS, &COMMANDS_WITH_PAYLOADS);
+    let _corpus = CorpusRecorder::new("r005_t3", seed).with_payloads(&payloads);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -0,0 +1,68 @@
+//! Contains tests sending `Headers` messages in which the headers declare a non-zero tx count.
+
+use crate::{
+    protocol::{
+        message::{constants::HEADERS_COMMAND, MessageHeader},
+        payload::{
+            block::{Block, Headers},
+            codec::Codec,
+        },
+    },
+    setup::node::{Action, Node},
+    tests::resistance::DISCONNECT_TIMEOUT,
+    tools::synthetic_node::SyntheticNode,
+};
+
+#[tokio::test]
+async fn r011_t1_headers_with_non_zero_tx_count() {
+    // ZG-RESISTANCE-011 (part 1)
+    //
+    // The headers in a `Headers` message are followed by a tx count which must be 0, as the
+    // transactions themselves aren't included. The node should treat a non-zero count as a
+    // malformed message and disconnect.
+    //
+    // zcashd: fail (ignores the tx count, assuming it's 0, and keeps the connection alive).
+    // zebra:  pass (rejects the message as a parse error and disconnects).
+
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    let mut synth_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await
+        .unwrap();
+    synth_node.connect(node.addr()).await.unwrap();
+
+    // Headers which extend the node's chain, so only the tx count is at fault.
+    let headers = Headers::new(
+        Block::initial_testnet_blocks()
+            .into_iter()
+            .skip(1)
+            .map(|block| block.header)
+            .collect(),
+    );
+
+    let mut body = Vec::new();
+    headers.encode_with_tx_count(&mut body, 1).unwrap();
+    let mut message = Vec::new();
+    MessageHeader::new(HEADERS_COMMAND, &body)
+        .encode(&mut message)
+        .unwrap();
+    message.extend_from_slice(&body);
+
+    synth_node.send_direct_bytes(node.addr(), message).unwrap();
+
+    let result = synth_node
+        .wait_for_disconnect(node.addr(), DISCONNECT_TIMEOUT)
+        .await;
+
+    synth_node.shut_down().await;
+    node.stop().unwrap();
+
+    assert!(result.is_ok());
+}
@@ -0,0 +1,90 @@
+//! Contains tests sending messages whose entry count declares more entries than are present.
+
+use std::net::SocketAddr;
+
+use crate::{
+    protocol::{
+        message::Message,
+        payload::{
+            addr::NetworkAddr,
+            block::{Block, Headers},
+            Addr, Inv,
+        },
+    },
+    setup::node::{Action, Node},
+    tests::resistance::DISCONNECT_TIMEOUT,
+    tools::{fuzzing::encode_with_lying_len, synthetic_node::SyntheticNode, RECV_TIMEOUT},
+};
+
+#[tokio::test]
+async fn r012_t1_entry_count_exceeds_entries() {
+    // ZG-RESISTANCE-012 (part 1)
+    //
+    // The entry count of an `Addr`, `Headers`, `Inv`, `GetData` or `NotFound` message declares
+    // one more entry than the body contains. The message header is valid for the body, so the
+    // node has received the whole message and should treat it as malformed, by either rejecting
+    // it or disconnecting, rather than waiting for the missing entry.
+    //
+    // This differs from the hung connections observed in the stress test (ZG-RESISTANCE-006),
+    // which were all caused by a corrupt body length in the header: there the node is right to
+    // wait for more data, as the message isn't complete yet.
+    //
+    // zcashd: pass (rejects the message as malformed, as it's shorter than its stated length).
+    // zebra:  pass (fails to deserialize the message and drops the connection).
+
+    let block = Block::testnet_genesis();
+    let messages = vec![
+        Message::Addr(Addr::new(vec![
+            NetworkAddr::new(SocketAddr::from((
+                [93, 184, 0, 1],
+                8233
+            )));
+            2
+        ])),
+        Message::Headers(Headers::new(vec![block.header.clone(); 2])),
+        Message::Inv(Inv::new(vec![block.inv_hash(); 2])),
+        Message::GetData(Inv::new(vec![block.inv_hash(); 2])),
+        Message::NotFound(Inv::new(vec![block.inv_hash(); 2])),
+    ];
+
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    for message in messages {
+        let payload = encode_with_lying_len(&message, 3).unwrap();
+
+        let mut synth_node = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_all_auto_reply()
+            .build()
+            .await
+            .unwrap();
+        synth_node.connect(node.addr()).await.unwrap();
+
+        synth_node.send_direct_bytes(node.addr(), payload).unwrap();
+
+        // Poll so a disconnect is noticed in-between waiting on reads.
+        let now = std::time::Instant::now();
+        let rejected = loop {
+            match synth_node.recv_message_timeout(RECV_TIMEOUT).await {
+                Ok((_, Message::Reject(..))) => break true,
+                _ if !synth_node.is_connected(node.addr()) => break false,
+                _ if now.elapsed() > DISCONNECT_TIMEOUT => break false,
+                _ => continue,
+            }
+        };
+        let disconnected = !synth_node.is_connected(node.addr());
+
+        synth_node.shut_down().await;
+
+        assert!(
+            rejected || disconnected,
+            "the node neither rejected {message:?} nor disconnected"
+        );
+    }
+
+    node.stop().unwrap();
+}
@@ -1,6 +1,13 @@
+mod addr_flood;
 mod corrupt_message;
+mod headers_tx_count;
+mod lying_length;
+mod oversized_message;
+mod payloadless_with_body;
 mod random_bytes;
+mod slow_handshake;
 mod stress_test;
+mod unknown_command;
 mod zeroes;
 
 use std::time::Duration;
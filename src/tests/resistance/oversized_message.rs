@@ -0,0 +1,49 @@
+//! Contains tests sending valid messages which are close to the maximum message length.
+
+use crate::{
+    protocol::message::constants::MAX_MESSAGE_LEN,
+    setup::node::{Action, Node},
+    tools::{fuzzing::addr_with_body_len_up_to, synthetic_node::SyntheticNode, LONG_TIMEOUT},
+};
+
+#[tokio::test]
+async fn r007_t1_addr_with_body_just_under_max_message_len() {
+    // ZG-RESISTANCE-007 (part 1)
+    //
+    // The node tolerates a valid Addr whose body is just under the maximum message length, the
+    // message framing is within bounds so the connection is kept alive.
+    //
+    // Both implementations limit Addr messages to 1000 entries, the ~70k entries sent here are well
+    // above that threshold.
+    //
+    // zcashd: pass (the message is dropped and the peer's misbehaviour score increased by 20).
+    // zebra:  fail (fails to deserialize the oversized address list and drops the connection).
+
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    let mut synthetic_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await
+        .unwrap();
+    synthetic_node.connect(node.addr()).await.unwrap();
+
+    let message = addr_with_body_len_up_to(MAX_MESSAGE_LEN);
+    synthetic_node.unicast(node.addr(), message).unwrap();
+
+    // A response to ping would indicate the connection survived.
+    let result = synthetic_node
+        .ping_pong_timeout(node.addr(), LONG_TIMEOUT)
+        .await;
+
+    // Gracefully shut down the nodes.
+    synthetic_node.shut_down().await;
+    node.stop().unwrap();
+
+    result.unwrap();
+}
@@ -0,0 +1,72 @@
+//! Contains tests sending payloadless messages whose header declares a non-empty body.
+
+use crate::{
+    protocol::{
+        message::{constants::VERACK_COMMAND, MessageHeader},
+        payload::codec::Codec,
+    },
+    setup::node::{Action, Node},
+    tools::{synthetic_node::SyntheticNode, RECV_TIMEOUT},
+};
+
+/// Returns a `Verack` whose header declares (and is followed by) a body of `len` zeroes, with a
+/// valid checksum.
+fn verack_with_body(len: usize) -> Vec<u8> {
+    let body = vec![0u8; len];
+
+    let mut bytes = Vec::new();
+    MessageHeader::new(VERACK_COMMAND, &body)
+        .encode(&mut bytes)
+        .unwrap();
+    bytes.extend_from_slice(&body);
+
+    bytes
+}
+
+#[tokio::test]
+async fn r008_t1_verack_with_nonzero_body_length() {
+    // ZG-RESISTANCE-008 (part 1)
+    //
+    // The node handles a Verack declaring a non-empty body gracefully, it either completes the
+    // handshake ignoring the body or drops the connection, but keeps accepting new peers.
+    //
+    // zcashd: pass (ignores the body and completes the handshake).
+    // zebra:  pass
+
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    for len in [1, 4, 32, 1024] {
+        let synth_node = SyntheticNode::builder()
+            .with_version_exchange_handshake()
+            .with_all_auto_reply()
+            .build()
+            .await
+            .unwrap();
+        synth_node.connect(node.addr()).await.unwrap();
+
+        // Send the malformed Verack in place of the regular one.
+        synth_node
+            .send_direct_bytes(node.addr(), verack_with_body(len))
+            .unwrap();
+
+        // Give the node time to process the message.
+        tokio::time::sleep(RECV_TIMEOUT).await;
+        synth_node.shut_down().await;
+    }
+
+    // The node still accepts and handshakes new peers.
+    let synth_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await
+        .unwrap();
+    synth_node.connect(node.addr()).await.unwrap();
+
+    synth_node.shut_down().await;
+    node.stop().unwrap();
+}
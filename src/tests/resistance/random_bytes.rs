@@ -5,7 +5,7 @@ use crate::{
     setup::node::{Action, Node},
     tests::resistance::{DISCONNECT_TIMEOUT, ITERATIONS},
     tools::{
-        fuzzing::{random_bytes, seeded_rng},
+        fuzzing::{corpus::CorpusRecorder, random_bytes, seeded_rng_with_seed},
         synthetic_node::SyntheticNode,
     },
 };
@@ -17,8 +17,9 @@ async fn r001_t2_instead_of_version_when_node_receives_connection() {
     // zebra: sends a version before disconnecting.
     // zcashd: ignores the bytes and disconnects.
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let payloads = random_bytes(&mut rng, ITERATIONS);
+    let _corpus = CorpusRecorder::new("r001_t2", seed).with_payloads(&payloads);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -51,8 +52,9 @@ async fn r002_t2_instead_of_verack_when_node_receives_connection() {
     // zebra: responds with verack before disconnecting.
     // zcashd: responds with verack, pong and getheaders before disconnecting.
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let payloads = random_bytes(&mut rng, ITERATIONS);
+    let _corpus = CorpusRecorder::new("r002_t2", seed).with_payloads(&payloads);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -90,8 +92,9 @@ async fn r003_t2_instead_of_version_when_node_initiates_connection() {
     //
     // Note: zcashd is two orders of magnitude slower (~52 vs ~0.5 seconds)
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let mut payloads = random_bytes(&mut rng, ITERATIONS);
+    let _corpus = CorpusRecorder::new("r003_t2", seed).with_payloads(&payloads);
 
     // create peers (we need their ports to give to the node)
     let (synth_nodes, synth_addrs) = SyntheticNode::builder()
@@ -147,8 +150,9 @@ async fn r004_t2_instead_of_verack_when_node_initiates_connection() {
     //
     // Note: zcashd is two orders of magnitude slower (~52 vs ~0.5 seconds)
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let mut payloads = random_bytes(&mut rng, ITERATIONS);
+    let _corpus = CorpusRecorder::new("r004_t2", seed).with_payloads(&payloads);
 
     // create peers (we need their ports to give to the node)
     let (synth_nodes, synth_addrs) = SyntheticNode::builder()
@@ -203,8 +207,9 @@ async fn r005_t2_post_handshake() {
     // zebra: disconnects.
     // zcashd: sends ping, getheaders and disconnects.
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let payloads = random_bytes(&mut rng, ITERATIONS);
+    let _corpus = CorpusRecorder::new("r005_t2", seed).with_payloads(&payloads);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -0,0 +1,53 @@
+//! Contains tests with a peer stalling the handshake.
+
+use std::time::Duration;
+
+use crate::{
+    setup::node::{Action, Node},
+    tools::synthetic_node::SyntheticNode,
+};
+
+/// The delay before each of the synthetic node's handshake messages, longer than the expected
+/// handshake and inactivity timeouts of the nodes.
+const HANDSHAKE_STEP_DELAY: Duration = Duration::from_secs(70);
+
+#[tokio::test]
+async fn r009_t1_handshake_with_large_step_delay() {
+    // ZG-RESISTANCE-009 (part 1)
+    //
+    // The node times out a handshake stalled by the peer, and keeps accepting new peers.
+    //
+    // zcashd: pass (disconnects peers which haven't sent any data after 60s).
+    // zebra:  pass (disconnects after its handshake timeout).
+
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    // Delay sending the Version (and Verack) well past the node's timeouts.
+    let synth_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_handshake_step_delay(HANDSHAKE_STEP_DELAY)
+        .build()
+        .await
+        .unwrap();
+
+    // The node drops the connection before the handshake completes.
+    assert!(synth_node.connect(node.addr()).await.is_err());
+    assert_eq!(synth_node.num_connected(), 0);
+    synth_node.shut_down().await;
+
+    // The node still accepts and handshakes new peers.
+    let synth_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await
+        .unwrap();
+    synth_node.connect(node.addr()).await.unwrap();
+
+    synth_node.shut_down().await;
+    node.stop().unwrap();
+}
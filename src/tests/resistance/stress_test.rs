@@ -21,10 +21,10 @@ use crate::{
     setup::node::{Action, Node},
     tools::{
         fuzzing::{
-            default_fuzz_messages, encode_messages_with_corrupt_body_length,
-            encode_messages_with_corrupt_checksum, encode_slightly_corrupted_messages,
-            metadata_compliant_random_bytes, random_bytes, seeded_rng, zeroes,
-            COMMANDS_WITH_PAYLOADS,
+            corpus::CorpusRecorder, default_fuzz_messages,
+            encode_messages_with_corrupt_body_length, encode_messages_with_corrupt_checksum,
+            encode_slightly_corrupted_messages, metadata_compliant_random_bytes, random_bytes,
+            seeded_rng_with_seed, zeroes, COMMANDS_WITH_PAYLOADS,
         },
         synthetic_node::SyntheticNode,
     },
@@ -213,7 +213,8 @@ async fn r006_stress_test_throughput() {
         1, 10, 20, 30, 40, 50, 60, 70, 80, 90, 100, 200, 300, 500, 750, 800,
     ];
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
+    let mut corpus = CorpusRecorder::new("r006", seed);
     let valid_pool = valid_queries_responses();
 
     // Start node with arbitrarily higher max peer count than what we
@@ -250,6 +251,7 @@ async fn r006_stress_test_throughput() {
 
         // Generate the broken fuzz messages for this peer set (one per peer, since this should break the connection)
         let mut corrupt_messages = generate_corrupt_messages(&mut rng, peers);
+        corpus.record(&corrupt_messages);
 
         let (tx, mut rx) = tokio::sync::mpsc::channel::<()>(peers);
 
@@ -0,0 +1,76 @@
+//! Contains tests sending well-formed messages with an unknown command after the handshake.
+
+use rand::{distributions::Alphanumeric, Rng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    protocol::{
+        message::{constants::COMMAND_LEN, MessageHeader},
+        payload::codec::Codec,
+    },
+    setup::node::{Action, Node},
+    tests::resistance::ITERATIONS,
+    tools::{
+        fuzzing::{corpus::CorpusRecorder, seeded_rng_with_seed},
+        synthetic_node::SyntheticNode,
+        LONG_TIMEOUT,
+    },
+};
+
+/// Returns a validly framed message with an empty body and a random alphanumeric command.
+///
+/// Known commands are padded with NUL bytes, so the command can never match one of them.
+fn unknown_command_message(rng: &mut ChaCha8Rng) -> Vec<u8> {
+    let mut command = [0u8; COMMAND_LEN];
+    command.fill_with(|| rng.sample(Alphanumeric));
+
+    let mut bytes = Vec::new();
+    MessageHeader::new(command, &[]).encode(&mut bytes).unwrap();
+
+    bytes
+}
+
+#[tokio::test]
+async fn r010_t1_unknown_command_post_handshake() {
+    // ZG-RESISTANCE-010 (part 1)
+    //
+    // The node ignores well-formed messages with an unknown command, for extensibility, and keeps
+    // the connection alive.
+    //
+    // zcashd: pass (logs the unknown command and ignores it).
+    // zebra:  fail (treats the unknown command as a parse error and drops the connection).
+
+    let (mut rng, seed) = seeded_rng_with_seed();
+    let payloads = (0..ITERATIONS)
+        .map(|_| unknown_command_message(&mut rng))
+        .collect::<Vec<_>>();
+    let _corpus = CorpusRecorder::new("r010_t1", seed).with_payloads(&payloads);
+
+    let mut node = Node::new().unwrap();
+    node.initial_action(Action::WaitForConnection)
+        .start()
+        .await
+        .unwrap();
+
+    let mut synth_node = SyntheticNode::builder()
+        .with_full_handshake()
+        .with_all_auto_reply()
+        .build()
+        .await
+        .unwrap();
+    synth_node.connect(node.addr()).await.unwrap();
+
+    for payload in payloads {
+        synth_node.send_direct_bytes(node.addr(), payload).unwrap();
+    }
+
+    // The node neither replies (e.g. with a Reject) nor disconnects: the next message it sends is
+    // the Pong.
+    synth_node
+        .ping_pong_timeout(node.addr(), LONG_TIMEOUT)
+        .await
+        .unwrap();
+
+    synth_node.shut_down().await;
+    node.stop().unwrap();
+}
@@ -7,7 +7,7 @@ use crate::{
     setup::node::{Action, Node},
     tests::resistance::{DISCONNECT_TIMEOUT, ITERATIONS},
     tools::{
-        fuzzing::{seeded_rng, zeroes},
+        fuzzing::{corpus::CorpusRecorder, seeded_rng_with_seed, zeroes},
         synthetic_node::SyntheticNode,
     },
 };
@@ -19,8 +19,9 @@ async fn r001_t1_instead_of_version_when_node_receives_connection() {
     // zebra: sends a version before disconnecting.
     // zcashd: disconnects immediately (log: `INFO main: PROCESSMESSAGE: INVALID MESSAGESTART peer=1`).
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let payloads = zeroes(&mut rng, ITERATIONS);
+    let _corpus = CorpusRecorder::new("r001_t1", seed).with_payloads(&payloads);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -54,8 +55,9 @@ async fn r002_t1_instead_of_verack_when_node_receives_connection() {
     // zebra: responds with verack before disconnecting.
     // zcashd: disconnects immediately.
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let payloads = zeroes(&mut rng, ITERATIONS);
+    let _corpus = CorpusRecorder::new("r002_t1", seed).with_payloads(&payloads);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -93,8 +95,9 @@ async fn r003_t1_instead_of_version_when_node_initiates_connection() {
     //
     // Note: zcashd is two orders of magnitude slower (~52 vs ~0.5 seconds)
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let mut payloads = zeroes(&mut rng, ITERATIONS);
+    let _corpus = CorpusRecorder::new("r003_t1", seed).with_payloads(&payloads);
 
     // create peers (we need their ports to give to the node)
     let (synth_nodes, synth_addrs) = SyntheticNode::builder()
@@ -150,8 +153,9 @@ async fn r004_t1_instead_of_verack_when_node_initiates_connection() {
     //
     // Note: zcashd is two orders of magnitude slower (~52 vs ~0.5 seconds)
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let mut payloads = zeroes(&mut rng, ITERATIONS);
+    let _corpus = CorpusRecorder::new("r004_t1", seed).with_payloads(&payloads);
 
     // create peers (we need their ports to give to the node)
     let (synth_nodes, synth_addrs) = SyntheticNode::builder()
@@ -208,8 +212,9 @@ async fn r005_t1_post_handshake() {
     // zebra: disconnects.
     // zcashd: responds with ping and getheaders before disconnecting.
 
-    let mut rng = seeded_rng();
+    let (mut rng, seed) = seeded_rng_with_seed();
     let payloads = zeroes(&mut rng, ITERATIONS);
+    let _corpus = CorpusRecorder::new("r005_t1", seed).with_payloads(&payloads);
 
     let mut node = Node::new().unwrap();
     node.initial_action(Action::WaitForConnection)
@@ -1,49 +1,77 @@
 use std::{
+    fmt::{self, Write as _},
+    fs, io,
     net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
     sync::Arc,
     thread,
     time::{Duration, Instant},
 };
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use dns_lookup::lookup_host;
 use parking_lot::Mutex;
 use pea2pea::{
-    protocols::{Handshake, Reading, Writing},
+    protocols::{Disconnect, Handshake, Reading, Writing},
     Pea2Pea,
 };
 use rand::prelude::IteratorRandom;
-use tokio::{signal, time::sleep};
-use tracing::{debug, error, info, warn};
+use tokio::{
+    signal,
+    task::JoinHandle,
+    time::{interval, sleep},
+};
+use tracing::{debug, error, info, warn, Span};
 use tracing_subscriber::filter::{EnvFilter, LevelFilter};
 use ziggurat_core_crawler::summary::NetworkSummary;
-use ziggurat_zcash::wait_until;
+use ziggurat_zcash::{
+    protocol::message::constants::{Network, MAX_ADDRS},
+    wait_until,
+};
 
 use crate::{
-    metrics::{NetworkMetrics, ZCASH_P2P_DEFAULT_MAINNET_PORT},
-    network::{ConnectionState, KnownNode},
+    metrics::{
+        DegreeStats, HandshakeLatencyStats, HeightStats, NetworkMetrics, ProtocolVersionCounts,
+    },
+    metrics_exporter::{start_metrics_exporter, LoopDurations, MetricsExporter},
+    network::{AddrClass, ConnectionState, KnownNetwork, NUM_FLAKIEST_NODES},
     protocol::{
-        Crawler, MAIN_LOOP_INTERVAL_SECS, MAX_WAIT_FOR_ADDR_SECS, NUM_CONN_ATTEMPTS_PERIODIC,
-        RECONNECT_INTERVAL_SECS,
+        Crawler, CrawlerConfig, MAIN_LOOP_INTERVAL_SECS, MAX_WAIT_FOR_ADDR_SECS,
+        NUM_CONN_ATTEMPTS_PERIODIC, RECONNECT_INTERVAL_SECS,
     },
     rpc::{initialize_rpc_server, RpcContext},
+    state::{load_peer_list, save_peer_list, NetworkState},
 };
 
 mod metrics;
+mod metrics_exporter;
 mod network;
 mod protocol;
 mod rpc;
+mod state;
 
 const SEED_WAIT_LOOP_INTERVAL_MS: u64 = 500;
 const SEED_RESPONSE_TIMEOUT_MS: u64 = 120_000;
 const SUMMARY_LOOP_INTERVAL: u64 = 60;
-const LOG_PATH: &str = "crawler-log.txt";
+const DEFAULT_TEXT_SUMMARY_PATH: &str = "crawler-log.txt";
+const DEFAULT_ADDR_DEDUP_WINDOW_SECS: u64 = 60;
+const DEFAULT_CHECKPOINT_INTERVAL_SECS: u64 = 5 * 60;
+
+/// The format of the crawler's logs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Pretty,
+    /// One JSON record per line, with the crawl events' fields at the top level.
+    Json,
+}
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
     /// A list of initial standalone IP addresses and/or DNS servers to connect to
-    #[clap(short, long, value_parser, num_args(1..), required = true)]
+    #[clap(short, long, value_parser, num_args(1..), required_unless_present = "passive")]
     seed_addrs: Vec<String>,
 
     /// The main crawling loop interval in seconds
@@ -54,15 +82,76 @@ struct Args {
     #[clap(short, long, value_parser)]
     rpc_addr: Option<SocketAddr>,
 
-    /// Default port used for connecting to the nodes
-    #[clap(short, long, value_parser, default_value_t = ZCASH_P2P_DEFAULT_MAINNET_PORT)]
-    node_listening_port: u16,
-    // TODO
-    // #[clap(short, long, value_parser, default_value = "testnet")]
-    // network: String,
+    /// If present, serve Prometheus metrics at the specified address, under /metrics
+    #[clap(long, value_parser)]
+    metrics_addr: Option<SocketAddr>,
+
+    /// The network to crawl: mainnet, testnet or regtest
+    #[clap(long, value_parser, default_value_t = Network::Mainnet)]
+    network: Network,
+
+    /// Default port used for connecting to the nodes, the network's default port if absent
+    #[clap(short, long, value_parser)]
+    node_listening_port: Option<u16>,
+
+    /// If present, only listen for inbound connections at the specified address instead of crawling
+    #[clap(long, value_parser)]
+    passive: Option<SocketAddr>,
+
+    /// If present, answer block queries with data from the embedded testnet blocks
+    #[clap(long, value_parser)]
+    cooperative: bool,
+
+    /// If present, disconnect peers sending more than the specified number of messages per second
+    #[clap(long, value_parser)]
+    max_msg_rate: Option<u32>,
+
+    /// Addresses already gossiped by any peer within this many seconds aren't processed again, 0 disables this
+    #[clap(long, value_parser, default_value_t = DEFAULT_ADDR_DEDUP_WINDOW_SECS)]
+    addr_dedup_window_secs: u64,
+
+    /// If present, answer GetAddr with up to the specified number (at most 1000) of the nodes the crawler has connected to
+    #[clap(long, value_parser = parse_served_addr_limit)]
+    served_addr_limit: Option<usize>,
+
+    /// If present, answer GetAddr with up to 1000 of the addresses listed (one per line) in the specified file
+    #[clap(long, value_parser)]
+    serve_peers_file: Option<PathBuf>,
+
+    /// If present, write the addresses of the nodes the crawler has contacted to the specified file on exit, one per line
+    #[clap(long, value_parser)]
+    export_peers_file: Option<PathBuf>,
+
+    /// The maximum number of nodes the crawling loop attempts to (re)connect to in each iteration
+    #[clap(long, value_parser, default_value_t = NUM_CONN_ATTEMPTS_PERIODIC)]
+    conn_attempts_per_iteration: usize,
+
+    /// The file the text summary is written to
+    #[clap(long, value_parser, default_value = DEFAULT_TEXT_SUMMARY_PATH)]
+    text_summary: PathBuf,
+
+    /// If present, periodically export the current summary to the log file at the specified interval in seconds (at least 1)
+    #[clap(short, long, value_parser = clap::value_parser!(u64).range(1..))]
+    export_interval_secs: Option<u64>,
+
+    /// If present, load the known nodes from the specified file at startup and periodically save them to it
+    #[clap(long, value_parser)]
+    state_file: Option<PathBuf>,
+
+    /// The interval in seconds at which the known nodes are saved to the state file (at least 1)
+    #[clap(
+        long,
+        value_parser = clap::value_parser!(u64).range(1..),
+        default_value_t = DEFAULT_CHECKPOINT_INTERVAL_SECS
+    )]
+    checkpoint_interval_secs: u64,
+
+    /// The format of the logs: pretty or json
+    #[clap(long, value_enum, default_value_t = LogFormat::Pretty)]
+    log_format: LogFormat,
 }
 
-fn start_logger(default_level: LevelFilter) {
+fn start_logger(default_level: LevelFilter, format: LogFormat) {
     let filter = match EnvFilter::try_from_default_env() {
         Ok(filter) => filter
             .add_directive("tokio_util=off".parse().unwrap())
@@ -73,10 +162,14 @@ fn start_logger(default_level: LevelFilter) {
             .add_directive("mio=off".parse().unwrap()),
     };
 
-    tracing_subscriber::fmt()
+    let subscriber = tracing_subscriber::fmt()
         .with_env_filter(filter)
-        .with_target(false)
-        .init();
+        .with_target(false);
+
+    match format {
+        LogFormat::Pretty => subscriber.init(),
+        LogFormat::Json => subscriber.json().flatten_event(true).init(),
+    }
 }
 
 /// Parses and converts `String` values found in a `Vec` to valid `SocketAddr`.
@@ -136,125 +229,394 @@ fn parse_addrs(seed_addrs: Vec<String>, node_listening_port: u16) -> Vec<SocketA
     return parsed_addrs;
 }
 
+/// Parses the `--served-addr-limit` value, which can't exceed the number of addresses allowed in
+/// a single `Addr` message.
+fn parse_served_addr_limit(limit: &str) -> Result<usize, String> {
+    let limit = limit.parse::<usize>().map_err(|e| e.to_string())?;
+    if limit > MAX_ADDRS {
+        return Err(format!("must be at most {MAX_ADDRS}"));
+    }
+
+    Ok(limit)
+}
+
+/// An error encountered while writing the text summary.
+#[derive(Debug)]
+enum SummaryLogError {
+    /// The summary couldn't be formatted.
+    Format(fmt::Error),
+    /// The formatted summary couldn't be written to the file.
+    Io(io::Error),
+}
+
+impl fmt::Display for SummaryLogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Format(e) => write!(f, "couldn't format the summary: {e}"),
+            Self::Io(e) => write!(f, "couldn't write the summary to file: {e}"),
+        }
+    }
+}
+
+/// Writes the text representation of the summary to the file at `path`, replacing its contents.
+fn log_summary_to_file(summary: &NetworkSummary, path: &Path) -> Result<(), SummaryLogError> {
+    let mut text = String::new();
+    write!(text, "{summary}").map_err(SummaryLogError::Format)?;
+
+    fs::write(path, text).map_err(SummaryLogError::Io)
+}
+
+/// Returns an export routine writing the summary to the file at `path`, logging any failure
+/// within `span`.
+fn summary_file_exporter(
+    span: Span,
+    path: PathBuf,
+) -> impl FnMut(&NetworkSummary) + Send + 'static {
+    move |summary| {
+        if let Err(e) = log_summary_to_file(summary, &path) {
+            error!(parent: &span, "{}", e);
+        }
+    }
+}
+
+/// Spawns a task calling `export` with the current summary snapshot every `period`.
+///
+/// The first export happens one full `period` after the task is spawned.
+fn spawn_summary_exporter<F>(
+    summary: Arc<Mutex<NetworkSummary>>,
+    period: Duration,
+    mut export: F,
+) -> JoinHandle<()>
+where
+    F: FnMut(&NetworkSummary) + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut interval = interval(period);
+        // The first tick completes immediately, skip it.
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            export(&summary.lock());
+        }
+    })
+}
+
+/// Spawns a task calling `export` with the current summary snapshot whenever the process receives
+/// `SIGUSR1`, without otherwise affecting the crawler.
+#[cfg(unix)]
+fn spawn_signal_exporter<F>(
+    summary: Arc<Mutex<NetworkSummary>>,
+    mut export: F,
+) -> io::Result<JoinHandle<()>>
+where
+    F: FnMut(&NetworkSummary) + Send + 'static,
+{
+    let mut sigusr1 = signal::unix::signal(signal::unix::SignalKind::user_defined1())?;
+
+    Ok(tokio::spawn(async move {
+        while sigusr1.recv().await.is_some() {
+            export(&summary.lock());
+        }
+    }))
+}
+
+/// Saves the known network's state to the file at `path`, logging any failure within `span`.
+fn save_state(known_network: &KnownNetwork, path: &Path, span: &Span) {
+    match known_network.state().save(path) {
+        Ok(()) => debug!(parent: span, "saved the crawler state to {}", path.display()),
+        Err(e) => error!(parent: span, "couldn't save the crawler state: {}", e),
+    }
+}
+
+/// Spawns a task saving the known network's state to the file at `path` every `period`.
+///
+/// The first checkpoint happens one full `period` after the task is spawned.
+fn spawn_state_checkpointer(crawler: Crawler, path: PathBuf, period: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = interval(period);
+        // The first tick completes immediately, skip it.
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            save_state(&crawler.known_network, &path, crawler.node().span());
+        }
+    })
+}
+
+/// Randomly picks up to `max` known nodes which are due a (re)connection attempt.
+///
+/// Nodes with overlay network addresses (see [`AddrClass`]) are never picked, as they can't be
+/// dialed directly.
+///
+/// The attempts are further subject to the
+/// [`MAX_CONCURRENT_CONNECTIONS`](protocol::MAX_CONCURRENT_CONNECTIONS) limit enforced by
+/// [`Crawler::should_connect`], so a lower `max` only reduces how many connections are in flight at
+/// once when it's below that limit.
+fn reconnection_candidates(known_network: &KnownNetwork, max: usize) -> Vec<SocketAddr> {
+    known_network
+        .nodes()
+        .into_iter()
+        .filter(|(addr, _)| !AddrClass::of(addr).is_overlay())
+        .filter(|(_, node)| {
+            if let Some(i) = node.last_connected {
+                i.elapsed().as_secs() >= RECONNECT_INTERVAL_SECS
+            } else {
+                true
+            }
+        })
+        .map(|(addr, _)| addr)
+        .choose_multiple(&mut rand::thread_rng(), max)
+}
+
 #[tokio::main]
 async fn main() {
-    start_logger(LevelFilter::INFO);
     let args = Args::parse();
-    let seed_addrs = parse_addrs(args.seed_addrs, args.node_listening_port);
+    start_logger(LevelFilter::INFO, args.log_format);
+    let node_listening_port = args
+        .node_listening_port
+        .unwrap_or_else(|| args.network.default_port());
+    let seed_addrs = parse_addrs(args.seed_addrs, node_listening_port);
+
+    let served_peers = match &args.serve_peers_file {
+        Some(path) => match load_peer_list(path) {
+            Ok(addrs) => {
+                info!("serving {} peer(s) from {}", addrs.len(), path.display());
+                Some(addrs.into())
+            }
+            Err(e) => {
+                error!(
+                    "couldn't load the peers to serve from {}: {}",
+                    path.display(),
+                    e
+                );
+                return;
+            }
+        },
+        None => None,
+    };
 
     // Create the crawler with the given listener address.
-    let crawler = Crawler::new().await;
+    let crawler = Crawler::new(CrawlerConfig {
+        cooperative: args.cooperative,
+        listening_addr: args.passive,
+        max_msg_rate: args.max_msg_rate,
+        addr_dedup_window: Duration::from_secs(args.addr_dedup_window_secs),
+        served_addr_limit: args.served_addr_limit,
+        served_peers,
+        network: args.network,
+    })
+    .await;
+
+    // Resume from the state of the previous run if there is one.
+    if let Some(path) = &args.state_file {
+        match NetworkState::load(path) {
+            Ok(state) => {
+                info!(parent: crawler.node().span(), "resuming session {} with {} known node(s) from {}", state.session + 1, state.nodes.len(), path.display());
+                crawler.known_network.restore(state);
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                info!(parent: crawler.node().span(), "no state found at {}, starting from the seed nodes", path.display());
+            }
+            Err(e) => {
+                // Don't start over, as the checkpoints would overwrite the unreadable state.
+                error!(parent: crawler.node().span(), "couldn't load the crawler state from {}: {}", path.display(), e);
+                return;
+            }
+        }
+    }
 
     let mut network_metrics = NetworkMetrics::default();
     let summary_snapshot = Arc::new(Mutex::new(NetworkSummary::default()));
+    let degree_stats_snapshot = Arc::new(Mutex::new(DegreeStats::default()));
+    let height_stats_snapshot = Arc::new(Mutex::new(HeightStats::default()));
+    let protocol_version_counts_snapshot = Arc::new(Mutex::new(ProtocolVersionCounts::default()));
+    let handshake_latency_stats_snapshot = Arc::new(Mutex::new(HandshakeLatencyStats::default()));
+    let loop_durations = Arc::new(Mutex::new(LoopDurations::default()));
 
     // Initialize the RPC server if address is specified.
     let _rpc_handle = if let Some(addr) = args.rpc_addr {
-        let rpc_context = RpcContext::new(Arc::clone(&summary_snapshot));
+        let rpc_context = RpcContext::new(
+            Arc::clone(&summary_snapshot),
+            crawler.clone(),
+            seed_addrs.clone(),
+            Arc::clone(&degree_stats_snapshot),
+            Arc::clone(&height_stats_snapshot),
+            Arc::clone(&protocol_version_counts_snapshot),
+            Arc::clone(&handshake_latency_stats_snapshot),
+        );
         let rpc_handle = initialize_rpc_server(addr, rpc_context).await;
         Some(rpc_handle)
     } else {
         None
     };
 
+    // Start the Prometheus metrics exporter if address is specified.
+    let metrics_exporter_task = if let Some(addr) = args.metrics_addr {
+        let exporter = MetricsExporter::new(
+            crawler.clone(),
+            Arc::clone(&summary_snapshot),
+            Arc::clone(&loop_durations),
+        );
+        match start_metrics_exporter(addr, exporter).await {
+            Ok((addr, task)) => {
+                info!(parent: crawler.node().span(), "serving Prometheus metrics at http://{}/metrics", addr);
+                Some(task)
+            }
+            Err(e) => {
+                error!(parent: crawler.node().span(), "couldn't start the metrics exporter: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
     crawler.enable_handshake().await;
     crawler.enable_reading().await;
     crawler.enable_writing().await;
+    crawler.enable_disconnect().await;
 
-    for addr in &seed_addrs {
-        let crawler_clone = crawler.clone();
-        let addr = *addr;
-
-        tokio::spawn(async move {
-            crawler_clone
-                .known_network
-                .nodes
-                .write()
-                .insert(addr, KnownNode::default());
-
-            // Once the Version message is received in the process_message function,
-            // GetAddr will be requested from the peer
-            let _ = crawler_clone.connect(addr).await;
-        });
-    }
+    let crawling_loop_task = if let Some(addr) = args.passive {
+        // Only listen for inbound connections, recording the peers in the known network.
+        crawler
+            .node()
+            .start_listening()
+            .await
+            .expect("couldn't start listening");
+        info!(parent: crawler.node().span(), "passively listening at {}", addr);
 
-    // Wait for a single successful connection before proceeding.
-    wait_until!(Duration::from_secs(3), crawler.node().num_connected() >= 1);
+        None
+    } else {
+        for addr in &seed_addrs {
+            let crawler_clone = crawler.clone();
+            let addr = *addr;
 
-    // Wait for one of the seed nodes to respond with a list of addrs.
-    wait_until!(
-        Duration::from_millis(SEED_RESPONSE_TIMEOUT_MS),
-        crawler.known_network.nodes().len() > seed_addrs.len(),
-        Duration::from_millis(SEED_WAIT_LOOP_INTERVAL_MS)
-    );
+            tokio::spawn(async move {
+                // Seed nodes restored from the state file keep their statistics.
+                crawler_clone
+                    .known_network
+                    .nodes
+                    .write()
+                    .entry(addr)
+                    .or_default();
+
+                // Once the Version message is received in the process_message function,
+                // GetAddr will be requested from the peer
+                let _ = crawler_clone.connect(addr).await;
+            });
+        }
 
-    let crawler_clone = crawler.clone();
-    let crawling_loop_task = tokio::spawn(async move {
-        let crawler = crawler_clone;
-        loop {
-            info!(parent: crawler.node().span(), "asking peers for their peers (connected to {})", crawler.node().num_connected());
-            info!(parent: crawler.node().span(), "known addrs: {}", crawler.known_network.num_nodes());
-
-            // Filter nodes that stuck in connected state for longer than 3 minutes
-            for (addr, _) in crawler
-                .known_network
-                .nodes()
-                .into_iter()
-                .filter(|(_, node)| {
-                    if node.state == ConnectionState::Connected {
-                        if let Some(i) = node.last_connected {
-                            i.elapsed().as_secs() >= MAX_WAIT_FOR_ADDR_SECS
+        // Wait for a single successful connection before proceeding.
+        wait_until!(Duration::from_secs(3), crawler.node().num_connected() >= 1);
+
+        // Wait for one of the seed nodes to respond with a list of addrs.
+        wait_until!(
+            Duration::from_millis(SEED_RESPONSE_TIMEOUT_MS),
+            crawler.known_network.nodes().len() > seed_addrs.len(),
+            Duration::from_millis(SEED_WAIT_LOOP_INTERVAL_MS)
+        );
+
+        // Copy the arguments used by the loop, so the task doesn't capture `args`.
+        let crawl_interval = args.crawl_interval;
+        let conn_attempts_per_iteration = args.conn_attempts_per_iteration;
+        let crawler_clone = crawler.clone();
+        let loop_durations = Arc::clone(&loop_durations);
+        Some(tokio::spawn(async move {
+            let crawler = crawler_clone;
+            loop {
+                let start_time = Instant::now();
+                info!(parent: crawler.node().span(), "asking peers for their peers (connected to {})", crawler.node().num_connected());
+                info!(parent: crawler.node().span(), "known addrs: {}", crawler.known_network.num_nodes());
+
+                // Filter nodes that stuck in connected state for longer than 3 minutes
+                for (addr, _) in crawler
+                    .known_network
+                    .nodes()
+                    .into_iter()
+                    .filter(|(_, node)| {
+                        if node.state == ConnectionState::Connected {
+                            if let Some(i) = node.last_connected {
+                                i.elapsed().as_secs() >= MAX_WAIT_FOR_ADDR_SECS
+                            } else {
+                                true
+                            }
                         } else {
-                            true
+                            false
                         }
-                    } else {
-                        false
-                    }
-                })
-            {
-                warn!(parent: crawler.node().span(), "disconnecting from node {} because it didn't send us proper addr message", addr);
-                crawler.node().disconnect(addr).await;
-                crawler
-                    .known_network
-                    .set_node_state(addr, ConnectionState::Disconnected);
-            }
+                    })
+                {
+                    warn!(parent: crawler.node().span(), "disconnecting from node {} because it didn't send us proper addr message", addr);
+                    crawler.node().disconnect(addr).await;
+                    crawler
+                        .known_network
+                        .set_node_state(addr, ConnectionState::Disconnected);
+                }
 
-            for (addr, _) in crawler
-                .known_network
-                .nodes()
-                .into_iter()
-                .filter(|(_, node)| {
-                    if let Some(i) = node.last_connected {
-                        i.elapsed().as_secs() >= RECONNECT_INTERVAL_SECS
-                    } else {
-                        true
+                for addr in
+                    reconnection_candidates(&crawler.known_network, conn_attempts_per_iteration)
+                {
+                    if crawler.should_connect(addr) {
+                        let crawler_clone = crawler.clone();
+                        tokio::spawn(async move {
+                            // Once the Version message is received in the process_message function,
+                            // GetAddr will be requested from the peer
+                            let _ = crawler_clone.connect(addr).await;
+                        });
                     }
-                })
-                .choose_multiple(&mut rand::thread_rng(), NUM_CONN_ATTEMPTS_PERIODIC)
-            {
-                if crawler.should_connect(addr) {
-                    let crawler_clone = crawler.clone();
-                    tokio::spawn(async move {
-                        // Once the Version message is received in the process_message function,
-                        // GetAddr will be requested from the peer
-                        let _ = crawler_clone.connect(addr).await;
-                    });
                 }
+
+                loop_durations.lock().crawl.record(start_time.elapsed());
+                sleep(Duration::from_secs(crawl_interval)).await;
             }
+        }))
+    };
 
-            sleep(Duration::from_secs(args.crawl_interval)).await;
-        }
+    // Periodically export the summary snapshot if requested, independently of its computation.
+    let export_task = args.export_interval_secs.map(|secs| {
+        spawn_summary_exporter(
+            Arc::clone(&summary_snapshot),
+            Duration::from_secs(secs),
+            summary_file_exporter(crawler.node().span().clone(), args.text_summary.clone()),
+        )
     });
 
+    // Periodically save the known nodes if a state file is used.
+    let checkpoint_task = args.state_file.clone().map(|path| {
+        spawn_state_checkpointer(
+            crawler.clone(),
+            path,
+            Duration::from_secs(args.checkpoint_interval_secs),
+        )
+    });
+
+    // Export the summary snapshot on demand, whenever SIGUSR1 is received.
+    #[cfg(unix)]
+    let signal_export_task = match spawn_signal_exporter(
+        Arc::clone(&summary_snapshot),
+        summary_file_exporter(crawler.node().span().clone(), args.text_summary.clone()),
+    ) {
+        Ok(task) => Some(task),
+        Err(e) => {
+            error!(parent: crawler.node().span(), "couldn't install the SIGUSR1 handler: {}", e);
+            None
+        }
+    };
+
     // Clone crawler and summary before we move them into a new thread.
     let crawler_clone = crawler.clone();
     let summary = Arc::clone(&summary_snapshot);
+    let passive = args.passive.is_some();
+    let summary_loop_durations = Arc::clone(&loop_durations);
 
     thread::spawn(move || {
         loop {
             let start_time = Instant::now();
 
-            if crawler.known_network.num_connections() > 0 {
+            if crawler.known_network.num_connections() > 0
+                || (passive && crawler.known_network.num_nodes() > 0)
+            {
                 crawler.known_network.remove_old_connections();
 
                 // Update graph, then create a summary and log it to a file.
@@ -263,6 +625,22 @@ async fn main() {
 
                 // Aquire lock and replace old summary snapshot with the newly generated one.
                 *summary_snapshot.lock() = new_summary;
+
+                let degree_stats = network_metrics.degree_stats();
+                info!(parent: crawler.node().span(), "average node degree: {:.2}, max node degree: {}", degree_stats.avg_degree, degree_stats.max_degree);
+                *degree_stats_snapshot.lock() = degree_stats;
+
+                let height_stats = HeightStats::from_nodes(crawler.known_network.nodes().values());
+                info!(parent: crawler.node().span(), "advertised block heights: min {}, median {}, max {}", height_stats.min_height, height_stats.median_height, height_stats.max_height);
+                *height_stats_snapshot.lock() = height_stats;
+
+                let protocol_version_counts = network_metrics.protocol_version_counts().clone();
+                info!(parent: crawler.node().span(), "advertised protocol versions: {:?}", protocol_version_counts.protocol_version_counts);
+                *protocol_version_counts_snapshot.lock() = protocol_version_counts;
+
+                let handshake_latency_stats = network_metrics.handshake_latency_stats();
+                info!(parent: crawler.node().span(), "handshake latencies over {} node(s): p50 {}ms, p95 {}ms, p99 {}ms", handshake_latency_stats.num_samples, handshake_latency_stats.p50_ms, handshake_latency_stats.p95_ms, handshake_latency_stats.p99_ms);
+                *handshake_latency_stats_snapshot.lock() = handshake_latency_stats;
             }
 
             let delta_time =
@@ -272,6 +650,10 @@ async fn main() {
                 warn!(parent: crawler.node().span(), "summary calculation took more time than the loop interval");
             }
             info!(parent: crawler.node().span(), "summary calculation took: {:?}", start_time.elapsed());
+            summary_loop_durations
+                .lock()
+                .summary
+                .record(start_time.elapsed());
 
             thread::sleep(delta_time);
         }
@@ -281,23 +663,140 @@ async fn main() {
     let _ = signal::ctrl_c().await;
     debug!(parent: crawler_clone.node().span(), "interrupt received, exiting process");
 
-    crawling_loop_task.abort();
-    let _ = crawling_loop_task.await;
+    if let Some(crawling_loop_task) = crawling_loop_task {
+        crawling_loop_task.abort();
+        let _ = crawling_loop_task.await;
+    }
+    if let Some(metrics_exporter_task) = metrics_exporter_task {
+        metrics_exporter_task.abort();
+        let _ = metrics_exporter_task.await;
+    }
+    if let Some(checkpoint_task) = checkpoint_task {
+        checkpoint_task.abort();
+        let _ = checkpoint_task.await;
+    }
+    if let Some(export_task) = export_task {
+        export_task.abort();
+        let _ = export_task.await;
+    }
+    #[cfg(unix)]
+    if let Some(signal_export_task) = signal_export_task {
+        signal_export_task.abort();
+        let _ = signal_export_task.await;
+    }
     crawler_clone.node().shut_down().await;
 
+    if let Some(path) = &args.state_file {
+        save_state(
+            &crawler_clone.known_network,
+            path,
+            crawler_clone.node().span(),
+        );
+    }
+
+    if let Some(path) = &args.export_peers_file {
+        let good_nodes = crawler_clone.known_network.good_nodes();
+        match save_peer_list(path, &good_nodes) {
+            Ok(()) => {
+                info!(parent: crawler_clone.node().span(), "exported {} peer(s) to {}", good_nodes.len(), path.display())
+            }
+            Err(e) => {
+                error!(parent: crawler_clone.node().span(), "couldn't export the peers: {}", e)
+            }
+        }
+    }
+
     // Print out summary of network metrics.
     let summary = summary.lock();
     info!(parent: crawler_clone.node().span(), "{}", summary);
-    if let Err(e) = summary.log_to_file(LOG_PATH) {
-        error!(parent: crawler_clone.node().span(), "couldn't write summary to file: {}", e);
+    if let Err(e) = log_summary_to_file(&summary, &args.text_summary) {
+        error!(parent: crawler_clone.node().span(), "{}", e);
+    }
+
+    let throttle_stats = crawler_clone.known_network.throttle_stats();
+    info!(parent: crawler_clone.node().span(), "peers disconnected for exceeding the message rate: {} time(s), {} node(s)", throttle_stats.num_throttle_events, throttle_stats.num_throttled_nodes);
+
+    let failures = crawler_clone.known_network.connection_failure_counts();
+    info!(parent: crawler_clone.node().span(), "nodes failing to connect: {} refused, {} timed out, {} reset, {} failed the handshake, {} rejected our version, {} banned us", failures.refused, failures.timeout, failures.reset, failures.handshake_failed, failures.version_rejected, failures.banned);
+
+    let never_contacted = crawler_clone.known_network.never_contacted_stats();
+    info!(parent: crawler_clone.node().span(), "nodes never contacted: {} ({:.2}%)", never_contacted.num_never_contacted, never_contacted.never_contacted_pct);
+
+    let addr_classes = crawler_clone.known_network.addr_class_counts();
+    info!(parent: crawler_clone.node().span(), "known nodes by address class: {} IPv4, {} IPv6, {} Tor, {} I2P, {} CJDNS", addr_classes.ipv4, addr_classes.ipv6, addr_classes.tor, addr_classes.i2p, addr_classes.cjdns);
+
+    let stability = crawler_clone.known_network.stability_stats();
+    info!(parent: crawler_clone.node().span(), "node stability over {} session(s): {} node(s) seen in every session, average stability {:.2}", stability.num_sessions, stability.num_stable_nodes, stability.avg_stability);
+
+    // Print out the nodes which were the least reliable to connect to.
+    for node in crawler_clone
+        .known_network
+        .flakiest_nodes(NUM_FLAKIEST_NODES)
+    {
+        info!(parent: crawler_clone.node().span(), "flaky node {}: {} connection attempt(s), {} successful, {} disconnection(s)", node.addr, node.connection_attempts, node.successful_connections, node.disconnections);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::{
+        net::{Ipv4Addr, Ipv6Addr},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
 
     use super::*;
+    use crate::metrics::ZCASH_P2P_DEFAULT_MAINNET_PORT;
+
+    #[test]
+    fn log_format_flag() {
+        let parse = |args: &[&str]| {
+            Args::try_parse_from(
+                ["crawler", "--passive", "127.0.0.1:0"]
+                    .iter()
+                    .chain(args)
+                    .copied(),
+            )
+        };
+
+        assert_eq!(parse(&[]).unwrap().log_format, LogFormat::Pretty);
+        assert_eq!(
+            parse(&["--log-format", "json"]).unwrap().log_format,
+            LogFormat::Json
+        );
+        assert!(parse(&["--log-format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn zero_export_interval_is_rejected() {
+        let parse = |interval: &str| {
+            Args::try_parse_from([
+                "crawler",
+                "--passive",
+                "127.0.0.1:0",
+                "--export-interval-secs",
+                interval,
+            ])
+        };
+
+        assert_eq!(parse("1").unwrap().export_interval_secs, Some(1));
+        assert!(parse("0").is_err());
+    }
+
+    #[test]
+    fn zero_checkpoint_interval_is_rejected() {
+        let parse = |interval: &str| {
+            Args::try_parse_from([
+                "crawler",
+                "--passive",
+                "127.0.0.1:0",
+                "--checkpoint-interval-secs",
+                interval,
+            ])
+        };
+
+        assert_eq!(parse("1").unwrap().checkpoint_interval_secs, 1);
+        assert!(parse("0").is_err());
+    }
 
     #[test]
     fn parse_addrs_test() {
@@ -307,7 +806,7 @@ mod tests {
             String::from("127.0.0.1"),
             String::from("192.0.2.235:54321"),
         ];
-        let parsed_addrs = parse_addrs(addrs);
+        let parsed_addrs = parse_addrs(addrs, ZCASH_P2P_DEFAULT_MAINNET_PORT);
 
         let correct_addrs = vec![
             SocketAddr::new(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)), 12345),
@@ -326,4 +825,89 @@ mod tests {
 
         assert_eq!(parsed_addrs, correct_addrs)
     }
+
+    #[test]
+    fn parse_served_addr_limit_test() {
+        assert_eq!(parse_served_addr_limit("0"), Ok(0));
+        assert_eq!(
+            parse_served_addr_limit(&MAX_ADDRS.to_string()),
+            Ok(MAX_ADDRS)
+        );
+        assert!(parse_served_addr_limit(&(MAX_ADDRS + 1).to_string()).is_err());
+        assert!(parse_served_addr_limit("many").is_err());
+        assert!(parse_served_addr_limit("-1").is_err());
+    }
+
+    #[test]
+    fn reconnection_candidates_are_capped() {
+        let source: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let addrs = (2..=11)
+            .map(|i| SocketAddr::from(([127, 0, 0, i], 8233)))
+            .collect::<Vec<_>>();
+
+        let network = KnownNetwork::default();
+        network.add_addrs(source, &addrs);
+        let num_nodes = network.nodes().len();
+        assert!(num_nodes >= addrs.len());
+
+        assert_eq!(reconnection_candidates(&network, 3).len(), 3);
+        assert_eq!(reconnection_candidates(&network, 0).len(), 0);
+        assert_eq!(reconnection_candidates(&network, 100).len(), num_nodes);
+    }
+
+    #[test]
+    fn overlay_addrs_are_not_reconnection_candidates() {
+        let source: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let onion: SocketAddr = "[fd87:d87e:eb43::1]:8233".parse().unwrap();
+        let garlic: SocketAddr = "[fd60:db4d:ddb5::1]:8233".parse().unwrap();
+
+        let network = KnownNetwork::default();
+        network.add_addrs(source, &[onion, garlic]);
+
+        assert_eq!(reconnection_candidates(&network, 100), vec![source]);
+    }
+
+    #[test]
+    fn unwritable_text_summary_path_is_an_io_error() {
+        let path = Path::new("/nonexistent-directory/crawler-log.txt");
+
+        let result = log_summary_to_file(&NetworkSummary::default(), path);
+        assert!(matches!(result, Err(SummaryLogError::Io(_))));
+    }
+
+    #[test]
+    fn summary_file_exporter_writes_the_summary() {
+        let path = std::env::temp_dir().join(format!("crawler-log-{}.txt", std::process::id()));
+        let summary = NetworkSummary::default();
+
+        let mut export = summary_file_exporter(Span::none(), path.clone());
+        export(&summary);
+
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(text, summary.to_string());
+    }
+
+    #[tokio::test]
+    async fn summary_exporter_cadence() {
+        const PERIOD: Duration = Duration::from_millis(100);
+
+        let summary = Arc::new(Mutex::new(NetworkSummary::default()));
+        let exports = Arc::new(AtomicUsize::new(0));
+
+        let exports_clone = Arc::clone(&exports);
+        let handle = spawn_summary_exporter(summary, PERIOD, move |_| {
+            exports_clone.fetch_add(1, Ordering::Relaxed);
+        });
+
+        // Nothing is exported before the first full period elapses.
+        sleep(PERIOD / 2).await;
+        assert_eq!(exports.load(Ordering::Relaxed), 0);
+
+        // Exports happen at 100, 200, 300, 400 and 500ms; allow some scheduling slack.
+        sleep(PERIOD * 5).await;
+        handle.abort();
+        let num_exports = exports.load(Ordering::Relaxed);
+        assert!((4..=6).contains(&num_exports), "{num_exports} exports");
+    }
 }
@@ -1,6 +1,11 @@
-use std::{collections::HashMap, net::SocketAddr};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    net::SocketAddr,
+    time::Duration,
+};
 
 use regex::Regex;
+use serde::Serialize;
 use spectre::{edge::Edge, graph::Graph};
 use ziggurat_core_crawler::summary::{NetworkSummary, NetworkType};
 
@@ -12,27 +17,205 @@ use crate::{
 const MIN_BLOCK_HEIGHT: i32 = 2_000_000;
 pub const ZCASH_P2P_DEFAULT_MAINNET_PORT: u16 = 8233;
 pub const ZCASH_P2P_DEFAULT_TESTNET_PORT: u16 = 18233;
+/// The number of most recent handshake latencies the percentiles are computed over.
+const HANDSHAKE_LATENCY_WINDOW: usize = 1000;
 
 #[derive(Default)]
 pub struct NetworkMetrics {
     graph: Graph<SocketAddr>,
+    degree_stats: DegreeStats,
+    protocol_version_counts: ProtocolVersionCounts,
+    handshake_latencies: HandshakeLatencies,
+}
+
+/// Node degree statistics of the network graph.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct DegreeStats {
+    /// The average number of connections per node.
+    pub avg_degree: f64,
+    /// The highest number of connections of a single node.
+    pub max_degree: usize,
+}
+
+impl DegreeStats {
+    /// Computes the degree statistics from the undirected edges of a graph.
+    ///
+    /// Duplicate edges (in either direction) and self-loops are ignored.
+    pub fn from_edges<I: IntoIterator<Item = (SocketAddr, SocketAddr)>>(edges: I) -> Self {
+        let edges = edges
+            .into_iter()
+            .filter(|(a, b)| a != b)
+            .map(|(a, b)| if a < b { (a, b) } else { (b, a) })
+            .collect::<HashSet<_>>();
+
+        let mut degrees: HashMap<SocketAddr, usize> = HashMap::new();
+        for (a, b) in &edges {
+            *degrees.entry(*a).or_default() += 1;
+            *degrees.entry(*b).or_default() += 1;
+        }
+
+        if degrees.is_empty() {
+            return Self::default();
+        }
+
+        Self {
+            avg_degree: 2.0 * edges.len() as f64 / degrees.len() as f64,
+            max_degree: degrees.values().copied().max().unwrap_or(0),
+        }
+    }
+}
+
+/// Block height statistics of the nodes, based on the `start_height` advertised in their
+/// `Version` messages.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct HeightStats {
+    /// The lowest advertised height.
+    pub min_height: i32,
+    /// The median advertised height (the upper one for an even number of heights).
+    pub median_height: i32,
+    /// The highest advertised height.
+    pub max_height: i32,
+}
+
+impl HeightStats {
+    /// Computes the height statistics from the nodes' advertised heights.
+    pub fn from_heights<I: IntoIterator<Item = i32>>(heights: I) -> Self {
+        let mut heights = heights.into_iter().collect::<Vec<_>>();
+        if heights.is_empty() {
+            return Self::default();
+        }
+        heights.sort_unstable();
+
+        Self {
+            min_height: heights[0],
+            median_height: heights[heights.len() / 2],
+            max_height: heights[heights.len() - 1],
+        }
+    }
+
+    /// Computes the height statistics of the known nodes which advertised their height.
+    pub fn from_nodes<'a, I: IntoIterator<Item = &'a KnownNode>>(nodes: I) -> Self {
+        Self::from_heights(nodes.into_iter().filter_map(|node| node.start_height))
+    }
+}
+
+/// The number of known nodes per protocol version advertised in their `Version` messages.
+///
+/// This only looks at the advertised version number, it's independent of the node
+/// implementation and network classification, which also rely on the user agent and port.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ProtocolVersionCounts {
+    pub protocol_version_counts: HashMap<u32, usize>,
+}
+
+impl ProtocolVersionCounts {
+    /// Counts the protocol versions of the known nodes which advertised one.
+    pub fn from_nodes<'a, I: IntoIterator<Item = &'a KnownNode>>(nodes: I) -> Self {
+        let mut protocol_version_counts = HashMap::new();
+        for version in nodes.into_iter().filter_map(|node| node.protocol_version) {
+            *protocol_version_counts.entry(version.0).or_default() += 1;
+        }
+
+        Self {
+            protocol_version_counts,
+        }
+    }
+}
+
+/// Handshake latency percentiles, from the TCP connection to the peer's `Verack`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct HandshakeLatencyStats {
+    /// The number of measured handshakes.
+    pub num_samples: usize,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+}
+
+impl HandshakeLatencyStats {
+    /// Computes the latency percentiles (nearest-rank) from the measured handshake times.
+    pub fn from_latencies<I: IntoIterator<Item = Duration>>(latencies: I) -> Self {
+        let mut latencies = latencies.into_iter().collect::<Vec<_>>();
+        if latencies.is_empty() {
+            return Self::default();
+        }
+        latencies.sort_unstable();
+
+        let percentile = |p: usize| {
+            let rank = (p * latencies.len() + 99) / 100;
+            latencies[rank.max(1) - 1].as_millis() as u64
+        };
+
+        Self {
+            num_samples: latencies.len(),
+            p50_ms: percentile(50),
+            p95_ms: percentile(95),
+            p99_ms: percentile(99),
+        }
+    }
+}
+
+/// A rolling window of the most recent handshake latencies.
+#[derive(Debug, Default)]
+pub struct HandshakeLatencies {
+    latencies: VecDeque<Duration>,
+}
+
+impl HandshakeLatencies {
+    /// Records a handshake latency, evicting the oldest one once the window is full.
+    pub fn record(&mut self, latency: Duration) {
+        if self.latencies.len() == HANDSHAKE_LATENCY_WINDOW {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+    }
+
+    /// Computes the latency percentiles of the recorded handshakes.
+    pub fn stats(&self) -> HandshakeLatencyStats {
+        HandshakeLatencyStats::from_latencies(self.latencies.iter().copied())
+    }
 }
 
 impl NetworkMetrics {
     /// Updates the network graph with new connections.
     pub fn update_graph(&mut self, crawler: &Crawler) {
+        let mut active_edges = Vec::new();
         for conn in crawler.known_network.connections() {
             let edge = Edge::new(conn.a, conn.b);
             if conn.last_seen.elapsed().as_secs() > LAST_SEEN_CUTOFF {
                 self.graph.remove(&edge);
             } else {
                 self.graph.insert(edge);
+                active_edges.push((conn.a, conn.b));
             }
         }
+
+        self.degree_stats = DegreeStats::from_edges(active_edges);
+    }
+
+    /// Returns the node degree statistics of the graph as of the last update.
+    pub fn degree_stats(&self) -> DegreeStats {
+        self.degree_stats
+    }
+
+    /// Returns the protocol version counts as of the last summary.
+    pub fn protocol_version_counts(&self) -> &ProtocolVersionCounts {
+        &self.protocol_version_counts
+    }
+
+    /// Returns the handshake latency percentiles as of the last summary.
+    pub fn handshake_latency_stats(&self) -> HandshakeLatencyStats {
+        self.handshake_latencies.stats()
     }
 
     /// Requests a summary of the network metrics.
     pub fn request_summary(&mut self, crawler: &Crawler) -> NetworkSummary {
+        let nodes = crawler.known_network.nodes();
+        self.protocol_version_counts = ProtocolVersionCounts::from_nodes(nodes.values());
+        for latency in crawler.take_handshake_latencies() {
+            self.handshake_latencies.record(latency);
+        }
+
         new_network_summary(crawler, &self.graph)
     }
 }
@@ -154,3 +337,130 @@ pub fn new_network_summary(crawler: &Crawler, graph: &Graph<SocketAddr>) -> Netw
         nodes_indices,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ziggurat_zcash::protocol::payload::ProtocolVersion;
+
+    use super::*;
+
+    #[test]
+    fn degree_stats_of_small_graph() {
+        let addr = |i: u8| SocketAddr::from(([127, 0, 0, i], 8233));
+
+        // A star centered on node 1, plus an edge between nodes 2 and 3, duplicated in the
+        // opposite direction, and a self-loop.
+        let edges = vec![
+            (addr(1), addr(2)),
+            (addr(1), addr(3)),
+            (addr(1), addr(4)),
+            (addr(2), addr(3)),
+            (addr(3), addr(2)),
+            (addr(4), addr(4)),
+        ];
+
+        // Degrees: 1 => 3, 2 => 2, 3 => 2, 4 => 1.
+        let stats = DegreeStats::from_edges(edges);
+        assert_eq!(stats.avg_degree, 2.0);
+        assert_eq!(stats.max_degree, 3);
+
+        assert_eq!(DegreeStats::from_edges(Vec::new()), DegreeStats::default());
+    }
+
+    #[test]
+    fn height_stats_of_sample_heights() {
+        let node = |start_height| KnownNode {
+            start_height,
+            ..Default::default()
+        };
+        // Nodes which didn't advertise a height are skipped.
+        let nodes = vec![
+            node(Some(2_100_000)),
+            node(None),
+            node(Some(1_900_000)),
+            node(Some(2_100_005)),
+            node(Some(2_099_990)),
+            node(Some(2_100_003)),
+        ];
+
+        assert_eq!(
+            HeightStats::from_nodes(&nodes),
+            HeightStats {
+                min_height: 1_900_000,
+                median_height: 2_100_000,
+                max_height: 2_100_005,
+            }
+        );
+
+        // The upper median is used for an even number of heights.
+        assert_eq!(HeightStats::from_heights([1, 2, 3, 4]).median_height, 3);
+        assert_eq!(HeightStats::from_heights([]), HeightStats::default());
+    }
+
+    #[test]
+    fn handshake_latency_percentiles() {
+        // 1..=100ms, in reverse order.
+        let mut latencies = HandshakeLatencies::default();
+        for ms in (1..=100).rev() {
+            latencies.record(Duration::from_millis(ms));
+        }
+
+        assert_eq!(
+            latencies.stats(),
+            HandshakeLatencyStats {
+                num_samples: 100,
+                p50_ms: 50,
+                p95_ms: 95,
+                p99_ms: 99,
+            }
+        );
+
+        let single = HandshakeLatencyStats::from_latencies([Duration::from_millis(7)]);
+        assert_eq!((single.p50_ms, single.p99_ms), (7, 7));
+        assert_eq!(
+            HandshakeLatencyStats::from_latencies([]),
+            HandshakeLatencyStats::default()
+        );
+    }
+
+    #[test]
+    fn handshake_latencies_roll_over() {
+        let mut latencies = HandshakeLatencies::default();
+        for _ in 0..HANDSHAKE_LATENCY_WINDOW {
+            latencies.record(Duration::from_millis(500));
+        }
+        assert_eq!(latencies.stats().p50_ms, 500);
+
+        // Newer handshakes evict the oldest ones.
+        for _ in 0..HANDSHAKE_LATENCY_WINDOW / 2 + 1 {
+            latencies.record(Duration::from_millis(10));
+        }
+        let stats = latencies.stats();
+        assert_eq!(stats.num_samples, HANDSHAKE_LATENCY_WINDOW);
+        assert_eq!((stats.p50_ms, stats.p99_ms), (10, 500));
+    }
+
+    #[test]
+    fn protocol_version_counts_of_sample_versions() {
+        let node = |version: Option<u32>| KnownNode {
+            protocol_version: version.map(ProtocolVersion),
+            ..Default::default()
+        };
+        // Nodes which didn't advertise a version are skipped.
+        let nodes = vec![
+            node(Some(170_100)),
+            node(Some(170_018)),
+            node(None),
+            node(Some(170_100)),
+            node(Some(170_100)),
+        ];
+
+        assert_eq!(
+            ProtocolVersionCounts::from_nodes(&nodes).protocol_version_counts,
+            HashMap::from([(170_100, 3), (170_018, 1)])
+        );
+        assert!(ProtocolVersionCounts::from_nodes(&[])
+            .protocol_version_counts
+            .is_empty());
+    }
+}
@@ -0,0 +1,417 @@
+//! A minimal HTTP server exposing the crawler's metrics in the Prometheus text format.
+
+use std::{
+    fmt::{self, Write as _},
+    io,
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+use pea2pea::Pea2Pea;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+    time::{sleep, timeout},
+};
+use tracing::{debug, warn};
+use ziggurat_core_crawler::summary::NetworkSummary;
+
+use crate::protocol::Crawler;
+
+/// The path the metrics are served at.
+const METRICS_PATH: &str = "/metrics";
+/// The maximum size of a request's head, larger requests are rejected.
+const MAX_REQUEST_HEAD_LEN: usize = 8 * 1024;
+/// The content type of the Prometheus text format.
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+/// The time a client has to send its request's head before the connection is dropped.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+/// The pause after failing to accept a connection, e.g. when running out of file descriptors.
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// The number of iterations of a loop and how long they took.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LoopStats {
+    pub iterations: u64,
+    pub last_duration: Duration,
+    pub total_duration: Duration,
+}
+
+impl LoopStats {
+    /// Records an iteration which took `duration`.
+    pub fn record(&mut self, duration: Duration) {
+        self.iterations += 1;
+        self.last_duration = duration;
+        self.total_duration += duration;
+    }
+}
+
+/// The durations of the crawler's loops.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LoopDurations {
+    /// The crawling loop, (re)connecting to the known nodes.
+    pub crawl: LoopStats,
+    /// The summary loop, computing the network summary and statistics.
+    pub summary: LoopStats,
+}
+
+/// The state the metrics are rendered from.
+#[derive(Clone)]
+pub struct MetricsExporter {
+    crawler: Crawler,
+    summary: Arc<Mutex<NetworkSummary>>,
+    loop_durations: Arc<Mutex<LoopDurations>>,
+}
+
+impl MetricsExporter {
+    /// Creates a new MetricsExporter.
+    pub fn new(
+        crawler: Crawler,
+        summary: Arc<Mutex<NetworkSummary>>,
+        loop_durations: Arc<Mutex<LoopDurations>>,
+    ) -> Self {
+        Self {
+            crawler,
+            summary,
+            loop_durations,
+        }
+    }
+
+    /// Renders the current metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        render_metrics(
+            self.crawler.known_network.num_nodes(),
+            self.crawler.node().num_connected(),
+            &self.summary.lock(),
+            &self.loop_durations.lock(),
+        )
+    }
+}
+
+/// Writes the `HELP` and `TYPE` lines of a metric.
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) -> fmt::Result {
+    writeln!(out, "# HELP {name} {help}")?;
+    writeln!(out, "# TYPE {name} {kind}")
+}
+
+/// Escapes a label value, as required by the text format.
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+/// Renders the metrics in the Prometheus text format.
+///
+/// The known and connected node counts are live, the distributions are those of the last
+/// summary.
+pub fn render_metrics(
+    num_known_nodes: usize,
+    num_connected_nodes: usize,
+    summary: &NetworkSummary,
+    loop_durations: &LoopDurations,
+) -> String {
+    let mut out = String::new();
+    // Writing to a String can't fail.
+    let _ = write_metrics(
+        &mut out,
+        num_known_nodes,
+        num_connected_nodes,
+        summary,
+        loop_durations,
+    );
+    out
+}
+
+fn write_metrics(
+    out: &mut String,
+    num_known_nodes: usize,
+    num_connected_nodes: usize,
+    summary: &NetworkSummary,
+    loop_durations: &LoopDurations,
+) -> fmt::Result {
+    write_header(
+        out,
+        "zcash_crawler_known_nodes",
+        "gauge",
+        "The number of nodes known to the crawler.",
+    )?;
+    writeln!(out, "zcash_crawler_known_nodes {num_known_nodes}")?;
+
+    write_header(
+        out,
+        "zcash_crawler_connected_nodes",
+        "gauge",
+        "The number of nodes the crawler is currently connected to.",
+    )?;
+    writeln!(out, "zcash_crawler_connected_nodes {num_connected_nodes}")?;
+
+    write_header(
+        out,
+        "zcash_crawler_good_nodes",
+        "gauge",
+        "The number of nodes the crawler has connected to, as of the last summary.",
+    )?;
+    writeln!(out, "zcash_crawler_good_nodes {}", summary.num_good_nodes)?;
+
+    write_header(
+        out,
+        "zcash_crawler_protocol_version_nodes",
+        "gauge",
+        "The number of nodes per advertised protocol version, as of the last summary.",
+    )?;
+    let mut protocol_versions = summary.protocol_versions.iter().collect::<Vec<_>>();
+    protocol_versions.sort();
+    for (version, count) in protocol_versions {
+        writeln!(
+            out,
+            "zcash_crawler_protocol_version_nodes{{version=\"{version}\"}} {count}"
+        )?;
+    }
+
+    write_header(
+        out,
+        "zcash_crawler_user_agent_nodes",
+        "gauge",
+        "The number of nodes per advertised user agent, as of the last summary.",
+    )?;
+    let mut user_agents = summary.user_agents.iter().collect::<Vec<_>>();
+    user_agents.sort();
+    for (user_agent, count) in user_agents {
+        writeln!(
+            out,
+            "zcash_crawler_user_agent_nodes{{user_agent=\"{}\"}} {count}",
+            escape_label_value(user_agent)
+        )?;
+    }
+
+    let loops = [
+        ("crawl", &loop_durations.crawl),
+        ("summary", &loop_durations.summary),
+    ];
+
+    write_header(
+        out,
+        "zcash_crawler_loop_iterations_total",
+        "counter",
+        "The number of iterations of the crawler's loops.",
+    )?;
+    for (name, stats) in loops {
+        writeln!(
+            out,
+            "zcash_crawler_loop_iterations_total{{loop=\"{name}\"}} {}",
+            stats.iterations
+        )?;
+    }
+
+    write_header(
+        out,
+        "zcash_crawler_loop_duration_seconds_total",
+        "counter",
+        "The total time spent in the iterations of the crawler's loops.",
+    )?;
+    for (name, stats) in loops {
+        writeln!(
+            out,
+            "zcash_crawler_loop_duration_seconds_total{{loop=\"{name}\"}} {}",
+            stats.total_duration.as_secs_f64()
+        )?;
+    }
+
+    write_header(
+        out,
+        "zcash_crawler_loop_last_duration_seconds",
+        "gauge",
+        "The duration of the last iteration of the crawler's loops.",
+    )?;
+    for (name, stats) in loops {
+        writeln!(
+            out,
+            "zcash_crawler_loop_last_duration_seconds{{loop=\"{name}\"}} {}",
+            stats.last_duration.as_secs_f64()
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Starts serving the metrics at `addr`, and returns the address actually bound along with the
+/// server's task.
+pub async fn start_metrics_exporter(
+    addr: SocketAddr,
+    exporter: MetricsExporter,
+) -> io::Result<(SocketAddr, JoinHandle<()>)> {
+    let listener = TcpListener::bind(addr).await?;
+    let local_addr = listener.local_addr()?;
+    debug!("Starting the metrics exporter at {}", local_addr);
+
+    let task = tokio::spawn(async move {
+        loop {
+            let (stream, peer_addr) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    warn!("couldn't accept a metrics connection: {}", e);
+                    sleep(ACCEPT_ERROR_BACKOFF).await;
+                    continue;
+                }
+            };
+
+            let exporter = exporter.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_request(stream, &exporter).await {
+                    debug!("couldn't serve the metrics to {}: {}", peer_addr, e);
+                }
+            });
+        }
+    });
+
+    Ok((local_addr, task))
+}
+
+/// Answers a single request, `GET /metrics` with the metrics and anything else with an error.
+async fn serve_request(mut stream: TcpStream, exporter: &MetricsExporter) -> io::Result<()> {
+    let head = match timeout(REQUEST_TIMEOUT, read_request_head(&mut stream)).await {
+        Ok(head) => head?,
+        Err(_) => return Err(io::ErrorKind::TimedOut.into()),
+    };
+    let Some(head) = head else {
+        return write_response(&mut stream, "431 Request Header Fields Too Large", "").await;
+    };
+
+    let request_line = String::from_utf8_lossy(&head);
+    let mut parts = request_line
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(METRICS_PATH)) => {
+            write_response(&mut stream, "200 OK", &exporter.render()).await
+        }
+        (Some("GET"), _) => write_response(&mut stream, "404 Not Found", "").await,
+        _ => write_response(&mut stream, "405 Method Not Allowed", "").await,
+    }
+}
+
+/// Reads the request's head, returning `None` if it exceeds [`MAX_REQUEST_HEAD_LEN`].
+async fn read_request_head(stream: &mut TcpStream) -> io::Result<Option<Vec<u8>>> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        if head.len() > MAX_REQUEST_HEAD_LEN {
+            return Ok(None);
+        }
+
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+
+    Ok(Some(head))
+}
+
+async fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {CONTENT_TYPE}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::protocol::CrawlerConfig;
+
+    #[test]
+    fn metrics_are_rendered_in_text_format() {
+        let summary = NetworkSummary {
+            num_good_nodes: 2,
+            protocol_versions: HashMap::from([(170_100, 2)]),
+            user_agents: HashMap::from([
+                ("/Zebra:1.0.0/".to_owned(), 1),
+                ("/\"x\"/".to_owned(), 1),
+            ]),
+            ..Default::default()
+        };
+        let mut loop_durations = LoopDurations::default();
+        loop_durations.crawl.record(Duration::from_millis(500));
+        loop_durations.crawl.record(Duration::from_millis(250));
+
+        let metrics = render_metrics(10, 3, &summary, &loop_durations);
+
+        for line in [
+            "# TYPE zcash_crawler_known_nodes gauge",
+            "zcash_crawler_known_nodes 10",
+            "zcash_crawler_connected_nodes 3",
+            "zcash_crawler_good_nodes 2",
+            "zcash_crawler_protocol_version_nodes{version=\"170100\"} 2",
+            "zcash_crawler_user_agent_nodes{user_agent=\"/Zebra:1.0.0/\"} 1",
+            r#"zcash_crawler_user_agent_nodes{user_agent="/\"x\"/"} 1"#,
+            "zcash_crawler_loop_iterations_total{loop=\"crawl\"} 2",
+            "zcash_crawler_loop_iterations_total{loop=\"summary\"} 0",
+            "zcash_crawler_loop_duration_seconds_total{loop=\"crawl\"} 0.75",
+            "zcash_crawler_loop_last_duration_seconds{loop=\"crawl\"} 0.25",
+        ] {
+            assert!(metrics.lines().any(|l| l == line), "missing {line:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn metrics_are_served_over_http() {
+        let crawler = Crawler::new(CrawlerConfig::default()).await;
+        let exporter = MetricsExporter::new(crawler, Default::default(), Default::default());
+        let (addr, task) = start_metrics_exporter(([127, 0, 0, 1], 0).into(), exporter)
+            .await
+            .unwrap();
+
+        let request = |path: &'static str| async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").as_bytes())
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = request(METRICS_PATH).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("\nzcash_crawler_known_nodes 0\n"));
+
+        assert!(request("/").await.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn idle_clients_are_dropped() {
+        let crawler = Crawler::new(CrawlerConfig::default()).await;
+        let exporter = MetricsExporter::new(crawler, Default::default(), Default::default());
+        let (addr, task) = start_metrics_exporter(([127, 0, 0, 1], 0).into(), exporter)
+            .await
+            .unwrap();
+
+        // A client which never sends its request is disconnected without a response.
+        let mut idle = TcpStream::connect(addr).await.unwrap();
+        let mut response = Vec::new();
+        timeout(2 * REQUEST_TIMEOUT, idle.read_to_end(&mut response))
+            .await
+            .expect("the idle client wasn't dropped")
+            .unwrap();
+        assert!(response.is_empty());
+
+        task.abort();
+    }
+}
@@ -1,15 +1,31 @@
 use std::{
+    cmp::Reverse,
     collections::{HashMap, HashSet},
-    net::SocketAddr,
+    io,
+    net::{IpAddr, SocketAddr},
+    sync::atomic::{AtomicU32, Ordering},
     time::{Duration, Instant},
 };
 
 use parking_lot::RwLock;
+use rand::prelude::IteratorRandom;
+use serde::Serialize;
+use time::OffsetDateTime;
 use ziggurat_core_crawler::connection::KnownConnection;
-use ziggurat_zcash::protocol::payload::{ProtocolVersion, VarStr};
+use ziggurat_zcash::protocol::{
+    message::constants::MAX_ADDRS,
+    payload::{addr::NetworkAddr, version::ServiceFlags, Addr, ProtocolVersion, VarStr},
+};
+
+use crate::state::{NetworkState, PersistedNode};
 
 /// The elapsed time before a connection should be regarded as inactive.
 pub const LAST_SEEN_CUTOFF: u64 = 10 * 60;
+/// The number of nodes listed in the flakiest nodes view.
+pub const NUM_FLAKIEST_NODES: usize = 20;
+/// The time after connecting within which a node closing the connection before sending its
+/// version is regarded as banning the crawler.
+pub const BAN_DISCONNECT_WINDOW: Duration = Duration::from_secs(5);
 
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub enum ConnectionState {
@@ -20,13 +36,189 @@ pub enum ConnectionState {
     Connected,
 }
 
+/// Returns an `Addr` made of a random sample of up to `limit` of the supplied addresses, advertised
+/// as seen now. The number of addresses never exceeds [`MAX_ADDRS`].
+pub fn addr_from_list(addrs: &[SocketAddr], limit: usize) -> Addr {
+    let now = OffsetDateTime::now_utc();
+    let sample = addrs
+        .iter()
+        .choose_multiple(&mut rand::thread_rng(), limit.min(MAX_ADDRS));
+
+    addr_with_last_seen(sample.into_iter().map(|addr| (*addr, now)), limit)
+}
+
+/// Returns an `Addr` listing the first `limit` of the supplied addresses, in order, with their
+/// last-seen timestamps and the default services. The number of addresses never exceeds
+/// [`MAX_ADDRS`].
+fn addr_with_last_seen(
+    addrs: impl IntoIterator<Item = (SocketAddr, OffsetDateTime)>,
+    limit: usize,
+) -> Addr {
+    let network_addrs = addrs
+        .into_iter()
+        .take(limit.min(MAX_ADDRS))
+        .map(|(addr, last_seen)| NetworkAddr {
+            last_seen: Some(last_seen),
+            services: ServiceFlags::NODE_NETWORK.bits(),
+            addr,
+        })
+        .collect();
+
+    Addr::new(network_addrs)
+}
+
+/// The reason a connection attempt failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionFailure {
+    /// The node actively refused the connection.
+    Refused,
+    /// The connection or the handshake timed out.
+    Timeout,
+    /// The connection was reset or aborted by the node.
+    Reset,
+    /// Any other error raised while establishing the connection or during the handshake.
+    HandshakeFailed,
+    /// The node rejected the crawler's version, e.g. because its protocol version is obsolete.
+    VersionRejected,
+    /// The node closed the connection right away, before sending its version, which is how nodes
+    /// treat the peers they banned or discouraged.
+    Banned,
+}
+
+impl From<&io::Error> for ConnectionFailure {
+    fn from(error: &io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::ConnectionRefused => Self::Refused,
+            io::ErrorKind::TimedOut => Self::Timeout,
+            io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe => Self::Reset,
+            _ => Self::HandshakeFailed,
+        }
+    }
+}
+
+/// The number of nodes whose last connection attempt failed, by failure reason.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ConnectionFailureCounts {
+    pub refused: usize,
+    pub timeout: usize,
+    pub reset: usize,
+    pub handshake_failed: usize,
+    pub version_rejected: usize,
+    pub banned: usize,
+}
+
+/// The class of a node's address, telling clearnet addresses apart from overlay network ones.
+///
+/// `Addr` messages can only carry IPv6 addresses, so overlay addresses are advertised as IPv6
+/// addresses in a reserved range, e.g. OnionCat for Tor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddrClass {
+    /// A clearnet IPv4 address, including IPv4-mapped IPv6 addresses.
+    Ipv4,
+    /// A clearnet IPv6 address.
+    Ipv6,
+    /// A Tor onion address encoded with OnionCat (`fd87:d87e:eb43::/48`).
+    Tor,
+    /// An I2P address encoded with GarliCat (`fd60:db4d:ddb5::/48`).
+    I2p,
+    /// A CJDNS address (`fc00::/8`).
+    Cjdns,
+}
+
+impl AddrClass {
+    /// Classifies the address.
+    pub fn of(addr: &SocketAddr) -> Self {
+        match normalize_addr(*addr).ip() {
+            IpAddr::V4(_) => Self::Ipv4,
+            IpAddr::V6(ip) => match ip.octets() {
+                [0xfd, 0x87, 0xd8, 0x7e, 0xeb, 0x43, ..] => Self::Tor,
+                [0xfd, 0x60, 0xdb, 0x4d, 0xdd, 0xb5, ..] => Self::I2p,
+                [0xfc, ..] => Self::Cjdns,
+                _ => Self::Ipv6,
+            },
+        }
+    }
+
+    /// Returns `true` for overlay network addresses, which the crawler can't dial.
+    pub fn is_overlay(&self) -> bool {
+        matches!(self, Self::Tor | Self::I2p | Self::Cjdns)
+    }
+}
+
+/// Converts IPv4-mapped IPv6 addresses to plain IPv4 ones, so the same node isn't known under two
+/// addresses.
+pub fn normalize_addr(addr: SocketAddr) -> SocketAddr {
+    match addr.ip() {
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ipv4) => SocketAddr::new(IpAddr::V4(ipv4), addr.port()),
+            None => addr,
+        },
+        IpAddr::V4(_) => addr,
+    }
+}
+
+/// The number of known nodes per address class.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct AddrClassCounts {
+    pub ipv4: usize,
+    pub ipv6: usize,
+    pub tor: usize,
+    pub i2p: usize,
+    pub cjdns: usize,
+}
+
+/// The share of known nodes which were never successfully contacted, e.g. speculative addresses
+/// learned from `Addr` messages.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct NeverContactedStats {
+    pub num_never_contacted: usize,
+    /// The percentage of the known nodes, 0 if there are none.
+    pub never_contacted_pct: f64,
+}
+
+/// The number of times the known nodes were disconnected for exceeding the message rate.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ThrottleStats {
+    pub num_throttle_events: u32,
+    /// The number of nodes which were disconnected at least once.
+    pub num_throttled_nodes: usize,
+}
+
+/// The details of a single known node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NodeDetails {
+    pub addr: SocketAddr,
+    pub connected: bool,
+    pub addr_from: Option<SocketAddr>,
+    pub protocol_version: Option<u32>,
+    pub user_agent: Option<String>,
+    pub start_height: Option<i32>,
+    pub services: Option<u64>,
+    pub handshake_time_ms: Option<u128>,
+    /// The last time the node was successfully contacted, as a Unix timestamp.
+    pub last_seen: Option<i64>,
+    pub sessions_seen: u32,
+    pub connection_attempts: u32,
+    pub successful_connections: u32,
+    pub disconnections: u32,
+    /// The number of subsequent connection errors.
+    pub connection_failures: u8,
+    pub last_failure: Option<ConnectionFailure>,
+}
+
 /// A node encountered in the network or obtained from one of the peers.
 #[derive(Debug, Default, Clone)]
 pub struct KnownNode {
     // The address is omitted, as it's a key in the owning HashMap.
+    /// The listening address advertised by the node in its version message.
+    pub addr_from: Option<SocketAddr>,
     /// The last time the node was successfully connected to.
     pub last_connected: Option<Instant>,
-    /// The time it took to complete a connection.
+    /// The time it took to complete the last handshake, from the TCP connection to the node's
+    /// `Verack`.
     pub handshake_time: Option<Duration>,
     /// The node's protocol version.
     pub protocol_version: Option<ProtocolVersion>,
@@ -38,26 +230,164 @@ pub struct KnownNode {
     pub services: Option<u64>,
     /// The number of subsequent connection errors.
     pub connection_failures: u8,
+    /// The total number of connection attempts.
+    pub connection_attempts: u32,
+    /// The total number of successful connections.
+    pub successful_connections: u32,
+    /// The total number of disconnections from a connected state.
+    pub disconnections: u32,
+    /// The number of times the node was disconnected for exceeding the message rate.
+    pub throttle_events: u32,
+    /// The reason of the last failed connection attempt, cleared once a connection succeeds.
+    pub last_failure: Option<ConnectionFailure>,
+    /// The time the crawler connected to the node, while it's still waiting for the node's
+    /// version.
+    pub awaiting_version_since: Option<Instant>,
+    /// Whether a connection with the node was ever established, in either direction.
+    pub ever_contacted: bool,
+    /// The number of crawling sessions in which the node was successfully contacted.
+    pub sessions_seen: u32,
+    /// The last time the node was successfully contacted.
+    pub last_seen: Option<OffsetDateTime>,
+    /// The last session in which the node was successfully contacted.
+    pub last_seen_session: Option<u32>,
     /// The node's state.
     pub state: ConnectionState,
 }
 
+impl KnownNode {
+    /// Records the result of a connection attempt.
+    pub fn record_connection_attempt(&mut self, successful: bool) {
+        self.connection_attempts += 1;
+        if successful {
+            self.successful_connections += 1;
+        }
+    }
+
+    /// Records a successful contact with the node in the given session, counting each session
+    /// once.
+    pub fn record_seen(&mut self, session: u32) {
+        self.ever_contacted = true;
+        self.last_seen = Some(OffsetDateTime::now_utc());
+        if self.last_seen_session != Some(session) {
+            self.sessions_seen += 1;
+            self.last_seen_session = Some(session);
+        }
+    }
+
+    /// Returns the node's stability score, which is the fraction of the `num_sessions` sessions
+    /// in which it was successfully contacted.
+    pub fn stability(&self, num_sessions: u32) -> f64 {
+        if num_sessions == 0 {
+            return 0.0;
+        }
+
+        f64::from(self.sessions_seen) / f64::from(num_sessions)
+    }
+
+    /// Returns the node's flakiness score, which is the sum of its failed connection attempts and
+    /// disconnections.
+    ///
+    /// The counters can be inconsistent with one another once restored from a state file, so
+    /// they're never assumed to be ordered.
+    pub fn flakiness(&self) -> u32 {
+        let failed_attempts = self
+            .connection_attempts
+            .saturating_sub(self.successful_connections);
+        failed_attempts.saturating_add(self.disconnections)
+    }
+}
+
+/// The reconnection statistics of a node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FlakyNode {
+    pub addr: SocketAddr,
+    pub connection_attempts: u32,
+    pub successful_connections: u32,
+    pub disconnections: u32,
+}
+
+/// The stability of the contacted nodes across crawling sessions.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct StabilityStats {
+    pub num_sessions: u32,
+    /// The number of nodes contacted in every session.
+    pub num_stable_nodes: usize,
+    /// The average stability score of the nodes contacted at least once, 0 if there are none.
+    pub avg_stability: f64,
+}
+
+/// A time-bounded cache of the addresses recently gossiped by any peer, used to skip processing
+/// them again while they're within the window.
+///
+/// Only the processing of the addresses is skipped, the connections they add to the network graph
+/// are recorded for every peer gossiping them.
+#[derive(Default)]
+pub struct RecentAddrs {
+    window: Duration,
+    seen: HashMap<SocketAddr, Instant>,
+}
+
+impl RecentAddrs {
+    /// Creates a cache skipping addresses seen within `window`; a zero `window` disables it.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            seen: Default::default(),
+        }
+    }
+
+    /// Returns the addresses which weren't seen within the window, and records them as seen.
+    pub fn filter_new(&mut self, addrs: &[SocketAddr]) -> Vec<SocketAddr> {
+        if self.window.is_zero() {
+            return addrs.to_vec();
+        }
+
+        let now = Instant::now();
+        let window = self.window;
+        self.seen
+            .retain(|_, last_seen| now.duration_since(*last_seen) < window);
+
+        addrs
+            .iter()
+            .filter(|addr| self.seen.insert(**addr, now).is_none())
+            .copied()
+            .collect()
+    }
+
+    /// Forgets all the recently seen addresses.
+    pub fn clear(&mut self) {
+        self.seen.clear();
+    }
+}
+
 /// The list of nodes and connections the crawler is aware of.
 #[derive(Default)]
 pub struct KnownNetwork {
     pub nodes: RwLock<HashMap<SocketAddr, KnownNode>>,
     pub connections: RwLock<HashSet<KnownConnection>>,
+    /// The index of the current crawling session, starting at 0. It's only ever incremented when
+    /// the known nodes are carried over from a previous session.
+    pub session: AtomicU32,
 }
 
 impl KnownNetwork {
     /// Extends the list of known nodes and connections.
     pub fn add_addrs(&self, source: SocketAddr, listening_addrs: &[SocketAddr]) {
-        {
-            let connections = &mut self.connections.write();
-            for addr in listening_addrs {
-                connections.insert(KnownConnection::new(source, *addr));
-            }
+        self.add_connections(source, listening_addrs);
+        self.add_nodes(source, listening_addrs);
+    }
+
+    /// Records the connections between `source` and the addresses it gossiped.
+    pub fn add_connections(&self, source: SocketAddr, listening_addrs: &[SocketAddr]) {
+        let connections = &mut self.connections.write();
+        for addr in listening_addrs {
+            connections.insert(KnownConnection::new(source, *addr));
         }
+    }
+
+    /// Adds `source` and the addresses it gossiped to the known nodes, if they aren't known yet.
+    pub fn add_nodes(&self, source: SocketAddr, listening_addrs: &[SocketAddr]) {
         let mut nodes = self.nodes.write();
         nodes.entry(source).or_default();
         listening_addrs.iter().for_each(|addr| {
@@ -65,13 +395,178 @@ impl KnownNetwork {
         });
     }
 
-    /// Sets the node's connection state.
+    /// Sets the node's connection state, counting transitions from connected to disconnected.
     pub fn set_node_state(&self, addr: SocketAddr, state: ConnectionState) {
         if let Some(node) = self.nodes.write().get_mut(&addr) {
+            if node.state == ConnectionState::Connected && state == ConnectionState::Disconnected {
+                node.disconnections += 1;
+            }
             node.state = state;
         }
     }
 
+    /// Records a failure to establish a working connection with the node, after the connection
+    /// itself succeeded. The failure isn't reclassified when the connection is closed.
+    pub fn record_failure(&self, addr: SocketAddr, failure: ConnectionFailure) {
+        if let Some(node) = self.nodes.write().get_mut(&addr) {
+            node.connection_failures = node.connection_failures.saturating_add(1);
+            node.last_failure = Some(failure);
+            node.awaiting_version_since = None;
+        }
+    }
+
+    /// Records the closing of the connection with the node, which is counted as a ban if the node
+    /// didn't send its version within [`BAN_DISCONNECT_WINDOW`].
+    pub fn record_disconnect(&self, addr: SocketAddr) {
+        let awaiting_version_since = match self.nodes.write().get_mut(&addr) {
+            Some(node) => node.awaiting_version_since.take(),
+            None => return,
+        };
+
+        if let Some(since) = awaiting_version_since {
+            if since.elapsed() < BAN_DISCONNECT_WINDOW {
+                self.record_failure(addr, ConnectionFailure::Banned);
+            }
+        }
+    }
+
+    /// Returns up to `n` nodes with the highest non-zero flakiness, the flakiest first.
+    pub fn flakiest_nodes(&self, n: usize) -> Vec<FlakyNode> {
+        let nodes = self.nodes.read();
+        let mut flaky_nodes = nodes
+            .iter()
+            .filter(|(_, node)| node.flakiness() > 0)
+            .collect::<Vec<_>>();
+        flaky_nodes.sort_by_key(|(_, node)| Reverse(node.flakiness()));
+
+        flaky_nodes
+            .into_iter()
+            .take(n)
+            .map(|(addr, node)| FlakyNode {
+                addr: *addr,
+                connection_attempts: node.connection_attempts,
+                successful_connections: node.successful_connections,
+                disconnections: node.disconnections,
+            })
+            .collect()
+    }
+
+    /// Returns an [`Addr`] listing the chosen known nodes, the most recently seen first.
+    ///
+    /// Only nodes the crawler has connected to are listed, with their last connection time as the
+    /// last-seen timestamp and the default services. The list is capped at `limit` addresses, and
+    /// never exceeds [`MAX_ADDRS`].
+    pub fn addr_from_nodes(&self, addrs: &[SocketAddr], limit: usize) -> Addr {
+        let mut seen_nodes = {
+            let nodes = self.nodes.read();
+            addrs
+                .iter()
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .filter_map(|addr| Some((*addr, nodes.get(addr)?.last_connected?)))
+                .collect::<Vec<_>>()
+        };
+        seen_nodes.sort_by_key(|(_, last_connected)| Reverse(*last_connected));
+
+        let now = OffsetDateTime::now_utc();
+        let seen_nodes = seen_nodes
+            .into_iter()
+            .map(|(addr, last_connected)| (addr, now - last_connected.elapsed()));
+
+        addr_with_last_seen(seen_nodes, limit)
+    }
+
+    /// Returns the addresses of the known nodes a connection was ever established with, sorted.
+    pub fn good_nodes(&self) -> Vec<SocketAddr> {
+        let mut addrs = self
+            .nodes
+            .read()
+            .iter()
+            .filter(|(_, node)| node.ever_contacted)
+            .map(|(addr, _)| *addr)
+            .collect::<Vec<_>>();
+        addrs.sort_unstable();
+
+        addrs
+    }
+
+    /// Forgets all the known nodes and connections, except for the supplied seed nodes which are
+    /// re-inserted with a blank state.
+    pub fn reset(&self, seed_addrs: &[SocketAddr]) {
+        self.connections.write().clear();
+
+        let mut nodes = self.nodes.write();
+        nodes.clear();
+        for addr in seed_addrs {
+            nodes.insert(*addr, KnownNode::default());
+        }
+    }
+
+    /// Returns the state of the known nodes to persist across runs.
+    ///
+    /// The known connections aren't part of it, as they expire after [`LAST_SEEN_CUTOFF`] anyway.
+    pub fn state(&self) -> NetworkState {
+        let nodes = self
+            .nodes
+            .read()
+            .iter()
+            .map(|(addr, node)| PersistedNode {
+                addr: *addr,
+                addr_from: node.addr_from,
+                handshake_time: node.handshake_time,
+                protocol_version: node.protocol_version.map(|version| version.0),
+                user_agent: node.user_agent.as_ref().map(|agent| agent.0.clone()),
+                start_height: node.start_height,
+                services: node.services,
+                connection_attempts: node.connection_attempts,
+                successful_connections: node.successful_connections,
+                disconnections: node.disconnections,
+                throttle_events: node.throttle_events,
+                ever_contacted: node.ever_contacted,
+                sessions_seen: node.sessions_seen,
+                last_seen: node.last_seen.map(|time| time.unix_timestamp()),
+                last_seen_session: node.last_seen_session,
+            })
+            .collect();
+
+        NetworkState {
+            session: self.session.load(Ordering::Relaxed),
+            nodes,
+        }
+    }
+
+    /// Restores the known nodes persisted by a previous run, which starts the session following
+    /// the persisted one.
+    ///
+    /// The restored nodes are disconnected and due a reconnection attempt. Nodes which are already
+    /// known are left untouched.
+    pub fn restore(&self, state: NetworkState) {
+        self.session.store(state.session + 1, Ordering::Relaxed);
+
+        let mut nodes = self.nodes.write();
+        for node in state.nodes {
+            nodes.entry(node.addr).or_insert_with(|| KnownNode {
+                addr_from: node.addr_from,
+                handshake_time: node.handshake_time,
+                protocol_version: node.protocol_version.map(ProtocolVersion),
+                user_agent: node.user_agent.map(VarStr),
+                start_height: node.start_height,
+                services: node.services,
+                connection_attempts: node.connection_attempts,
+                successful_connections: node.successful_connections,
+                disconnections: node.disconnections,
+                throttle_events: node.throttle_events,
+                ever_contacted: node.ever_contacted,
+                sessions_seen: node.sessions_seen,
+                last_seen: node
+                    .last_seen
+                    .and_then(|time| OffsetDateTime::from_unix_timestamp(time).ok()),
+                last_seen_session: node.last_seen_session,
+                ..Default::default()
+            });
+        }
+    }
+
     /// Returns a snapshot of the known connections.
     pub fn connections(&self) -> HashSet<KnownConnection> {
         self.connections.read().clone()
@@ -82,11 +577,157 @@ impl KnownNetwork {
         self.nodes.read().clone()
     }
 
+    /// Returns the details of the node at `addr`, if it's known.
+    pub fn node_details(&self, addr: SocketAddr) -> Option<NodeDetails> {
+        let addr = normalize_addr(addr);
+        let nodes = self.nodes.read();
+        let node = nodes.get(&addr)?;
+
+        Some(NodeDetails {
+            addr,
+            connected: node.state == ConnectionState::Connected,
+            addr_from: node.addr_from,
+            protocol_version: node.protocol_version.map(|version| version.0),
+            user_agent: node.user_agent.as_ref().map(|agent| agent.0.clone()),
+            start_height: node.start_height,
+            services: node.services,
+            handshake_time_ms: node.handshake_time.map(|time| time.as_millis()),
+            last_seen: node.last_seen.map(|time| time.unix_timestamp()),
+            sessions_seen: node.sessions_seen,
+            connection_attempts: node.connection_attempts,
+            successful_connections: node.successful_connections,
+            disconnections: node.disconnections,
+            connection_failures: node.connection_failures,
+            last_failure: node.last_failure,
+        })
+    }
+
+    /// Returns the peers of the node at `addr` in the network graph, i.e. the addresses it
+    /// advertised and the nodes which advertised it, if the node is known.
+    ///
+    /// Like the graph, the list only covers connections seen within [`LAST_SEEN_CUTOFF`].
+    pub fn peers_of(&self, addr: SocketAddr) -> Option<Vec<SocketAddr>> {
+        let addr = normalize_addr(addr);
+        if !self.nodes.read().contains_key(&addr) {
+            return None;
+        }
+
+        let mut peers = self
+            .connections
+            .read()
+            .iter()
+            .filter_map(|conn| match (conn.a, conn.b) {
+                (a, b) if a == addr => Some(b),
+                (a, b) if b == addr => Some(a),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        peers.sort_unstable();
+        peers.dedup();
+
+        Some(peers)
+    }
+
     /// Returns the number of known connections.
     pub fn num_connections(&self) -> usize {
         self.connections.read().len()
     }
 
+    /// Returns the throttle events across all known nodes.
+    pub fn throttle_stats(&self) -> ThrottleStats {
+        let nodes = self.nodes.read();
+        let throttled = nodes.values().filter(|node| node.throttle_events > 0);
+
+        ThrottleStats {
+            num_throttle_events: throttled.clone().map(|node| node.throttle_events).sum(),
+            num_throttled_nodes: throttled.count(),
+        }
+    }
+
+    /// Returns the number of known nodes failing to connect, by the reason of their last failure.
+    pub fn connection_failure_counts(&self) -> ConnectionFailureCounts {
+        let mut counts = ConnectionFailureCounts::default();
+        for failure in self
+            .nodes
+            .read()
+            .values()
+            .filter_map(|node| node.last_failure)
+        {
+            match failure {
+                ConnectionFailure::Refused => counts.refused += 1,
+                ConnectionFailure::Timeout => counts.timeout += 1,
+                ConnectionFailure::Reset => counts.reset += 1,
+                ConnectionFailure::HandshakeFailed => counts.handshake_failed += 1,
+                ConnectionFailure::VersionRejected => counts.version_rejected += 1,
+                ConnectionFailure::Banned => counts.banned += 1,
+            }
+        }
+
+        counts
+    }
+
+    /// Returns the number of known nodes by the class of their address.
+    pub fn addr_class_counts(&self) -> AddrClassCounts {
+        let mut counts = AddrClassCounts::default();
+        for addr in self.nodes.read().keys() {
+            match AddrClass::of(addr) {
+                AddrClass::Ipv4 => counts.ipv4 += 1,
+                AddrClass::Ipv6 => counts.ipv6 += 1,
+                AddrClass::Tor => counts.tor += 1,
+                AddrClass::I2p => counts.i2p += 1,
+                AddrClass::Cjdns => counts.cjdns += 1,
+            }
+        }
+
+        counts
+    }
+
+    /// Returns the number and percentage of known nodes which were never successfully contacted.
+    pub fn never_contacted_stats(&self) -> NeverContactedStats {
+        let nodes = self.nodes.read();
+        let num_never_contacted = nodes.values().filter(|node| !node.ever_contacted).count();
+        let never_contacted_pct = if nodes.is_empty() {
+            0.0
+        } else {
+            num_never_contacted as f64 * 100.0 / nodes.len() as f64
+        };
+
+        NeverContactedStats {
+            num_never_contacted,
+            never_contacted_pct,
+        }
+    }
+
+    /// Returns the stability of the contacted nodes across the crawling sessions so far.
+    pub fn stability_stats(&self) -> StabilityStats {
+        let num_sessions = self.session.load(Ordering::Relaxed) + 1;
+        let nodes = self.nodes.read();
+        let contacted = nodes
+            .values()
+            .filter(|node| node.sessions_seen > 0)
+            .collect::<Vec<_>>();
+
+        let num_stable_nodes = contacted
+            .iter()
+            .filter(|node| node.sessions_seen >= num_sessions)
+            .count();
+        let avg_stability = if contacted.is_empty() {
+            0.0
+        } else {
+            contacted
+                .iter()
+                .map(|node| node.stability(num_sessions))
+                .sum::<f64>()
+                / contacted.len() as f64
+        };
+
+        StabilityStats {
+            num_sessions,
+            num_stable_nodes,
+            avg_stability,
+        }
+    }
+
     /// Returns the number of known nodes.
     pub fn num_nodes(&self) -> usize {
         self.nodes.read().len()
@@ -109,3 +750,502 @@ impl KnownNetwork {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flakiness_of_inconsistent_counters() {
+        let node = KnownNode {
+            connection_attempts: 1,
+            successful_connections: 3,
+            disconnections: 5,
+            ..Default::default()
+        };
+        assert_eq!(node.flakiness(), 5);
+
+        let node = KnownNode {
+            connection_attempts: u32::MAX,
+            disconnections: u32::MAX,
+            ..Default::default()
+        };
+        assert_eq!(node.flakiness(), u32::MAX);
+    }
+
+    #[test]
+    fn reconnection_statistics() {
+        let stable: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let flaky: SocketAddr = "127.0.0.2:8233".parse().unwrap();
+
+        let network = KnownNetwork::default();
+        network.add_addrs(stable, &[flaky]);
+
+        {
+            let mut nodes = network.nodes.write();
+            nodes
+                .get_mut(&stable)
+                .unwrap()
+                .record_connection_attempt(true);
+
+            let flaky_node = nodes.get_mut(&flaky).unwrap();
+            flaky_node.record_connection_attempt(false);
+            flaky_node.record_connection_attempt(true);
+        }
+        network.set_node_state(stable, ConnectionState::Connected);
+        network.set_node_state(flaky, ConnectionState::Connected);
+
+        // Only a transition from a connected state counts as a disconnection.
+        network.set_node_state(flaky, ConnectionState::Disconnected);
+        network.set_node_state(flaky, ConnectionState::Disconnected);
+
+        let nodes = network.nodes();
+        assert_eq!(nodes[&stable].connection_attempts, 1);
+        assert_eq!(nodes[&stable].successful_connections, 1);
+        assert_eq!(nodes[&stable].disconnections, 0);
+        assert_eq!(nodes[&flaky].connection_attempts, 2);
+        assert_eq!(nodes[&flaky].successful_connections, 1);
+        assert_eq!(nodes[&flaky].disconnections, 1);
+
+        assert_eq!(
+            network.flakiest_nodes(NUM_FLAKIEST_NODES),
+            vec![FlakyNode {
+                addr: flaky,
+                connection_attempts: 2,
+                successful_connections: 1,
+                disconnections: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn connection_failure_classification() {
+        let classify = |kind| ConnectionFailure::from(&io::Error::from(kind));
+
+        assert_eq!(
+            classify(io::ErrorKind::ConnectionRefused),
+            ConnectionFailure::Refused
+        );
+        assert_eq!(
+            classify(io::ErrorKind::TimedOut),
+            ConnectionFailure::Timeout
+        );
+        for kind in [
+            io::ErrorKind::ConnectionReset,
+            io::ErrorKind::ConnectionAborted,
+            io::ErrorKind::BrokenPipe,
+        ] {
+            assert_eq!(classify(kind), ConnectionFailure::Reset);
+        }
+        for kind in [io::ErrorKind::InvalidData, io::ErrorKind::UnexpectedEof] {
+            assert_eq!(classify(kind), ConnectionFailure::HandshakeFailed);
+        }
+    }
+
+    #[test]
+    fn connection_failure_counts_by_reason() {
+        let source: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let peers = [
+            "127.0.0.2:8233".parse().unwrap(),
+            "127.0.0.3:8233".parse().unwrap(),
+            "127.0.0.4:8233".parse().unwrap(),
+        ];
+
+        let network = KnownNetwork::default();
+        network.add_addrs(source, &peers);
+        {
+            let mut nodes = network.nodes.write();
+            nodes.get_mut(&peers[0]).unwrap().last_failure = Some(ConnectionFailure::Refused);
+            nodes.get_mut(&peers[1]).unwrap().last_failure = Some(ConnectionFailure::Refused);
+            nodes.get_mut(&peers[2]).unwrap().last_failure = Some(ConnectionFailure::Timeout);
+        }
+
+        assert_eq!(
+            network.connection_failure_counts(),
+            ConnectionFailureCounts {
+                refused: 2,
+                timeout: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn node_details_and_peers() {
+        let source: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let peers: [SocketAddr; 2] = [
+            "127.0.0.2:8233".parse().unwrap(),
+            "127.0.0.3:8233".parse().unwrap(),
+        ];
+        let unknown: SocketAddr = "127.0.0.4:8233".parse().unwrap();
+
+        let network = KnownNetwork::default();
+        network.add_addrs(source, &peers);
+        network.add_addrs(peers[0], &[peers[1]]);
+        {
+            let mut nodes = network.nodes.write();
+            let node = nodes.get_mut(&source).unwrap();
+            node.record_connection_attempt(true);
+            node.state = ConnectionState::Connected;
+            node.user_agent = Some(VarStr("/MagicBean:5.0.0/".to_owned()));
+            node.last_failure = Some(ConnectionFailure::Timeout);
+        }
+
+        let details = network.node_details(source).unwrap();
+        assert!(details.connected);
+        assert_eq!(details.successful_connections, 1);
+        assert_eq!(details.user_agent.as_deref(), Some("/MagicBean:5.0.0/"));
+        assert_eq!(details.last_failure, Some(ConnectionFailure::Timeout));
+        assert!(network.node_details(unknown).is_none());
+
+        assert_eq!(network.peers_of(source).unwrap(), peers);
+        // The peers include the nodes advertising the queried one.
+        assert_eq!(network.peers_of(peers[0]).unwrap(), [source, peers[1]]);
+        assert!(network.peers_of(unknown).is_none());
+    }
+
+    #[test]
+    fn early_disconnect_before_version_is_a_ban() {
+        let source: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let peers = [
+            "127.0.0.2:8233".parse().unwrap(),
+            "127.0.0.3:8233".parse().unwrap(),
+            "127.0.0.4:8233".parse().unwrap(),
+        ];
+
+        let network = KnownNetwork::default();
+        network.add_addrs(source, &peers);
+        {
+            let mut nodes = network.nodes.write();
+            // Disconnected right after connecting.
+            nodes.get_mut(&peers[0]).unwrap().awaiting_version_since = Some(Instant::now());
+            // Disconnected long after connecting, e.g. timed out by the crawler.
+            nodes.get_mut(&peers[1]).unwrap().awaiting_version_since =
+                Some(Instant::now() - BAN_DISCONNECT_WINDOW);
+            // peers[2] sent its version.
+        }
+        for peer in peers {
+            network.record_disconnect(peer);
+        }
+        network.record_failure(peers[2], ConnectionFailure::VersionRejected);
+
+        let nodes = network.nodes();
+        assert_eq!(
+            nodes[&peers[0]].last_failure,
+            Some(ConnectionFailure::Banned)
+        );
+        assert_eq!(nodes[&peers[1]].last_failure, None);
+        assert!(nodes
+            .values()
+            .all(|node| node.awaiting_version_since.is_none()));
+        assert_eq!(
+            network.connection_failure_counts(),
+            ConnectionFailureCounts {
+                version_rejected: 1,
+                banned: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn addr_classification() {
+        let classify = |addr: &str| AddrClass::of(&addr.parse().unwrap());
+
+        assert_eq!(classify("1.2.3.4:8233"), AddrClass::Ipv4);
+        assert_eq!(classify("[::ffff:1.2.3.4]:8233"), AddrClass::Ipv4);
+        assert_eq!(classify("[2001:db8::1]:8233"), AddrClass::Ipv6);
+        assert_eq!(classify("[fd87:d87e:eb43:1234::1]:8233"), AddrClass::Tor);
+        assert_eq!(classify("[fd60:db4d:ddb5:1234::1]:8233"), AddrClass::I2p);
+        assert_eq!(classify("[fc12::1]:8233"), AddrClass::Cjdns);
+        // Other unique local addresses aren't overlays.
+        assert_eq!(classify("[fd00::1]:8233"), AddrClass::Ipv6);
+
+        assert_eq!(
+            normalize_addr("[::ffff:1.2.3.4]:8233".parse().unwrap()),
+            "1.2.3.4:8233".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn addr_class_counts_of_mixed_nodes() {
+        let source: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let peers = [
+            "[2001:db8::1]:8233".parse().unwrap(),
+            "[fd87:d87e:eb43::1]:8233".parse().unwrap(),
+            "[fd87:d87e:eb43::2]:8233".parse().unwrap(),
+            "[fd60:db4d:ddb5::1]:8233".parse().unwrap(),
+        ];
+
+        let network = KnownNetwork::default();
+        network.add_addrs(source, &peers);
+
+        assert_eq!(
+            network.addr_class_counts(),
+            AddrClassCounts {
+                ipv4: 1,
+                ipv6: 1,
+                tor: 2,
+                i2p: 1,
+                cjdns: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn never_contacted_stats_of_mixed_nodes() {
+        let source: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let peers = [
+            "127.0.0.2:8233".parse().unwrap(),
+            "127.0.0.3:8233".parse().unwrap(),
+            "127.0.0.4:8233".parse().unwrap(),
+        ];
+
+        let network = KnownNetwork::default();
+        assert_eq!(
+            network.never_contacted_stats(),
+            NeverContactedStats::default()
+        );
+
+        network.add_addrs(source, &peers);
+        network
+            .nodes
+            .write()
+            .get_mut(&source)
+            .unwrap()
+            .ever_contacted = true;
+
+        assert_eq!(
+            network.never_contacted_stats(),
+            NeverContactedStats {
+                num_never_contacted: 3,
+                never_contacted_pct: 75.0,
+            }
+        );
+    }
+
+    #[test]
+    fn throttle_stats_of_mixed_nodes() {
+        let source: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let peers = [
+            "127.0.0.2:8233".parse().unwrap(),
+            "127.0.0.3:8233".parse().unwrap(),
+        ];
+
+        let network = KnownNetwork::default();
+        assert_eq!(network.throttle_stats(), ThrottleStats::default());
+
+        network.add_addrs(source, &peers);
+        network
+            .nodes
+            .write()
+            .get_mut(&peers[0])
+            .unwrap()
+            .throttle_events = 3;
+        network
+            .nodes
+            .write()
+            .get_mut(&peers[1])
+            .unwrap()
+            .throttle_events = 1;
+
+        assert_eq!(
+            network.throttle_stats(),
+            ThrottleStats {
+                num_throttle_events: 4,
+                num_throttled_nodes: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn recent_addrs_are_skipped_within_the_window() {
+        const WINDOW: Duration = Duration::from_millis(100);
+
+        let addrs: [SocketAddr; 2] = [
+            "127.0.0.3:8233".parse().unwrap(),
+            "127.0.0.4:8233".parse().unwrap(),
+        ];
+
+        let mut recent_addrs = RecentAddrs::new(WINDOW);
+        assert_eq!(recent_addrs.filter_new(&addrs[..1]), &addrs[..1]);
+
+        // Only the unseen address is processed within the window.
+        assert_eq!(recent_addrs.filter_new(&addrs), &addrs[1..]);
+        assert!(recent_addrs.filter_new(&addrs).is_empty());
+
+        // Past the window, the addresses are processed again.
+        std::thread::sleep(WINDOW);
+        assert_eq!(recent_addrs.filter_new(&addrs), &addrs);
+
+        // A zero window disables the cache.
+        let mut disabled = RecentAddrs::new(Duration::ZERO);
+        assert_eq!(disabled.filter_new(&addrs), &addrs);
+        assert_eq!(disabled.filter_new(&addrs), &addrs);
+    }
+
+    #[test]
+    fn recent_addrs_from_different_sources_are_skipped() {
+        let source: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let other_source: SocketAddr = "127.0.0.2:8233".parse().unwrap();
+        let addr: SocketAddr = "127.0.0.3:8233".parse().unwrap();
+
+        let network = KnownNetwork::default();
+        let mut recent_addrs = RecentAddrs::new(Duration::from_secs(60));
+        for source in [source, other_source] {
+            network.add_connections(source, &[addr]);
+            let new_addrs = recent_addrs.filter_new(&[addr]);
+            network.add_nodes(source, &new_addrs);
+
+            // The address is only processed when first gossiped.
+            assert_eq!(new_addrs.is_empty(), source == other_source);
+        }
+
+        // Both peers are still connected to the address in the network graph.
+        let connections = network.connections.read();
+        assert!(connections.contains(&KnownConnection::new(source, addr)));
+        assert!(connections.contains(&KnownConnection::new(other_source, addr)));
+        assert_eq!(network.nodes().len(), 3);
+    }
+
+    #[test]
+    fn stability_increments_across_sessions() {
+        let stable: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let unstable: SocketAddr = "127.0.0.2:8233".parse().unwrap();
+
+        let network = KnownNetwork::default();
+        network.add_addrs(stable, &[unstable]);
+        let record_seen = |addr| {
+            let session = network.session.load(Ordering::Relaxed);
+            network
+                .nodes
+                .write()
+                .get_mut(&addr)
+                .unwrap()
+                .record_seen(session);
+        };
+
+        // First session: both nodes are seen, repeated contacts count once.
+        record_seen(stable);
+        record_seen(stable);
+        record_seen(unstable);
+        assert_eq!(network.nodes()[&stable].sessions_seen, 1);
+
+        // Second session: only the stable node is seen.
+        network.session.fetch_add(1, Ordering::Relaxed);
+        record_seen(stable);
+
+        let nodes = network.nodes();
+        assert_eq!(nodes[&stable].sessions_seen, 2);
+        assert_eq!(nodes[&unstable].sessions_seen, 1);
+        assert_eq!(nodes[&stable].stability(2), 1.0);
+        assert_eq!(nodes[&unstable].stability(2), 0.5);
+
+        assert_eq!(
+            network.stability_stats(),
+            StabilityStats {
+                num_sessions: 2,
+                num_stable_nodes: 1,
+                avg_stability: 0.75,
+            }
+        );
+    }
+
+    #[test]
+    fn restored_state_starts_the_next_session() {
+        let contacted: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let gossiped: SocketAddr = "127.0.0.2:8233".parse().unwrap();
+
+        let network = KnownNetwork::default();
+        network.add_addrs(contacted, &[gossiped]);
+        {
+            let mut nodes = network.nodes.write();
+            let node = nodes.get_mut(&contacted).unwrap();
+            node.protocol_version = Some(ProtocolVersion(170_100));
+            node.user_agent = Some(VarStr("/Zebra:1.0.0/".to_owned()));
+            node.start_height = Some(2_000_000);
+            node.record_connection_attempt(true);
+            node.record_seen(0);
+            node.last_connected = Some(Instant::now());
+            node.state = ConnectionState::Connected;
+        }
+
+        let restored = KnownNetwork::default();
+        restored.restore(network.state());
+
+        assert_eq!(restored.session.load(Ordering::Relaxed), 1);
+        assert_eq!(restored.state().nodes.len(), 2);
+
+        let nodes = restored.nodes();
+        let node = &nodes[&contacted];
+        assert_eq!(node.protocol_version, Some(ProtocolVersion(170_100)));
+        assert_eq!(node.user_agent, Some(VarStr("/Zebra:1.0.0/".to_owned())));
+        assert_eq!(node.start_height, Some(2_000_000));
+        assert_eq!(node.successful_connections, 1);
+        assert_eq!(node.sessions_seen, 1);
+        assert!(node.last_seen.is_some());
+        // The connection itself doesn't carry over.
+        assert_eq!(node.state, ConnectionState::Disconnected);
+        assert!(node.last_connected.is_none());
+        assert!(!nodes[&gossiped].ever_contacted);
+    }
+
+    #[test]
+    fn reset_keeps_only_the_seeds() {
+        let seed: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let peers = [
+            "127.0.0.2:8233".parse().unwrap(),
+            "127.0.0.3:8233".parse().unwrap(),
+        ];
+
+        let network = KnownNetwork::default();
+        network.add_addrs(seed, &peers);
+        network.set_node_state(seed, ConnectionState::Connected);
+        assert_eq!(network.num_nodes(), 3);
+
+        network.reset(&[seed]);
+
+        assert_eq!(network.num_connections(), 0);
+        let nodes = network.nodes();
+        assert_eq!(nodes.keys().collect::<Vec<_>>(), vec![&seed]);
+        assert_eq!(nodes[&seed].state, ConnectionState::Disconnected);
+    }
+
+    #[test]
+    fn addr_from_nodes_is_capped_and_ordered() {
+        let source: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let addrs = (0..MAX_ADDRS + 100)
+            .map(|i| SocketAddr::from(([10, 0, (i / 256) as u8, (i % 256) as u8], 8233)))
+            .collect::<Vec<_>>();
+
+        let network = KnownNetwork::default();
+        network.add_addrs(source, &addrs);
+
+        // Nodes with a higher index were connected to more recently, the source never was.
+        let start = Instant::now();
+        {
+            let mut nodes = network.nodes.write();
+            for (i, addr) in addrs.iter().enumerate() {
+                nodes.get_mut(addr).unwrap().last_connected =
+                    Some(start + Duration::from_millis(i as u64));
+            }
+        }
+
+        let mut chosen = addrs.clone();
+        chosen.push(source);
+        let addr = network.addr_from_nodes(&chosen, usize::MAX);
+
+        assert_eq!(addr.addrs.len(), MAX_ADDRS);
+        let listed = addr.iter().map(|addr| addr.addr).collect::<Vec<_>>();
+        let expected = addrs
+            .iter()
+            .rev()
+            .take(MAX_ADDRS)
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(listed, expected);
+        assert!(addr
+            .iter()
+            .all(|addr| addr.last_seen.is_some() && addr.services == 1));
+    }
+}
@@ -1,28 +1,70 @@
-use std::{io, net::SocketAddr, sync::Arc, time::Instant};
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
 
 use futures_util::SinkExt;
+use parking_lot::Mutex;
 use pea2pea::{
-    protocols::{Handshake, Reading, Writing},
+    protocols::{Disconnect, Handshake, Reading, Writing},
     Config, Connection, ConnectionSide, Node as Pea2PeaNode, Pea2Pea,
 };
 use tokio_util::codec::Framed;
 use tracing::*;
 use ziggurat_zcash::{
     protocol::{
-        message::Message,
-        payload::{block::Headers, Addr, Version},
+        message::{
+            constants::{Network, MAX_ADDRS},
+            Message,
+        },
+        payload::{
+            block::{Block, Headers, LocatorHashes},
+            Addr, Inv, Nonce, Version,
+        },
     },
     tools::synthetic_node::MessageCodec,
 };
 
-use super::network::KnownNetwork;
-use crate::network::ConnectionState;
+use super::network::{KnownNetwork, RecentAddrs};
+use crate::network::{addr_from_list, normalize_addr, ConnectionFailure, ConnectionState};
 
 pub const NUM_CONN_ATTEMPTS_PERIODIC: usize = 500;
 pub const MAX_CONCURRENT_CONNECTIONS: u16 = 1200;
 pub const MAIN_LOOP_INTERVAL_SECS: u64 = 20;
 pub const RECONNECT_INTERVAL_SECS: u64 = 5 * 60;
 pub const MAX_WAIT_FOR_ADDR_SECS: u64 = 3 * 60;
+const MSG_RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// The crawler's optional behaviours.
+#[derive(Debug, Default, Clone)]
+pub struct CrawlerConfig {
+    /// Whether to answer block queries with data from the embedded testnet blocks.
+    pub cooperative: bool,
+    /// If set, inbound connections are accepted at this address.
+    pub listening_addr: Option<SocketAddr>,
+    /// If set, peers sending more messages per second are disconnected.
+    pub max_msg_rate: Option<u32>,
+    /// The window within which addresses already gossiped by any peer aren't processed again; zero
+    /// disables the deduplication.
+    pub addr_dedup_window: Duration,
+    /// If set, `GetAddr` is answered with up to this many of the known nodes the crawler has
+    /// connected to, instead of an empty `Addr`.
+    pub served_addr_limit: Option<usize>,
+    /// If set, `GetAddr` is answered with a random sample of these addresses instead of the known
+    /// nodes, capped by `served_addr_limit` if it's set as well.
+    pub served_peers: Option<Arc<[SocketAddr]>>,
+    /// The network the crawler's messages are encoded for.
+    pub network: Network,
+}
+
+/// The number of messages received from a peer in the current window.
+struct MessageRate {
+    window_start: Instant,
+    count: u32,
+}
 
 /// Represents the crawler together with network metrics it has collected.
 #[derive(Clone)]
@@ -30,6 +72,11 @@ pub struct Crawler {
     node: Pea2PeaNode,
     pub known_network: Arc<KnownNetwork>,
     pub start_time: Instant,
+    pub config: CrawlerConfig,
+    msg_rates: Arc<Mutex<HashMap<SocketAddr, MessageRate>>>,
+    recent_addrs: Arc<Mutex<RecentAddrs>>,
+    /// The handshake latencies measured since they were last taken by the metrics.
+    handshake_latencies: Arc<Mutex<Vec<Duration>>>,
 }
 
 impl Pea2Pea for Crawler {
@@ -40,18 +87,129 @@ impl Pea2Pea for Crawler {
 
 impl Crawler {
     /// Creates a new instance of the `Crawler` without starting it.
-    pub async fn new() -> Self {
-        let config = Config {
+    pub async fn new(config: CrawlerConfig) -> Self {
+        let node_config = Config {
             name: Some("crawler".into()),
-            listener_ip: None,
+            listener_ip: config.listening_addr.map(|addr| addr.ip()),
+            desired_listening_port: config.listening_addr.map(|addr| addr.port()),
             max_connections: MAX_CONCURRENT_CONNECTIONS,
             ..Default::default()
         };
 
         Self {
-            node: Pea2PeaNode::new(config),
+            node: Pea2PeaNode::new(node_config),
             known_network: Default::default(),
             start_time: Instant::now(),
+            msg_rates: Default::default(),
+            recent_addrs: Arc::new(Mutex::new(RecentAddrs::new(config.addr_dedup_window))),
+            handshake_latencies: Default::default(),
+            config,
+        }
+    }
+
+    /// Disconnects from all the peers and resets the known network to the supplied seed nodes,
+    /// which are then reconnected to by the crawling loop.
+    pub async fn reset(&self, seed_addrs: &[SocketAddr]) {
+        for addr in self.node().connected_addrs() {
+            self.node().disconnect(addr).await;
+        }
+
+        self.msg_rates.lock().clear();
+        self.recent_addrs.lock().clear();
+        self.handshake_latencies.lock().clear();
+        self.known_network.reset(seed_addrs);
+    }
+
+    /// Returns the handshake latencies measured since the last call.
+    pub fn take_handshake_latencies(&self) -> Vec<Duration> {
+        std::mem::take(&mut *self.handshake_latencies.lock())
+    }
+
+    /// Records the latency of the handshake with `source`, which completes with its `Verack`,
+    /// measured from the TCP connection.
+    fn record_handshake_latency(&self, source: SocketAddr) {
+        let latency = self
+            .known_network
+            .nodes
+            .write()
+            .get_mut(&source)
+            .and_then(|known_node| {
+                let latency = known_node.last_connected?.elapsed();
+                known_node.handshake_time = Some(latency);
+                Some(latency)
+            });
+
+        if let Some(latency) = latency {
+            self.handshake_latencies.lock().push(latency);
+        }
+    }
+
+    /// Counts a message received from `source` and returns `true` if the peer exceeded the
+    /// configured message rate.
+    fn exceeds_msg_rate(&self, source: SocketAddr) -> bool {
+        let max_msg_rate = match self.config.max_msg_rate {
+            Some(max_msg_rate) => max_msg_rate,
+            None => return false,
+        };
+
+        let mut msg_rates = self.msg_rates.lock();
+        let rate = msg_rates.entry(source).or_insert_with(|| MessageRate {
+            window_start: Instant::now(),
+            count: 0,
+        });
+
+        if rate.window_start.elapsed() >= MSG_RATE_WINDOW {
+            rate.window_start = Instant::now();
+            rate.count = 0;
+        }
+        rate.count += 1;
+
+        if rate.count > max_msg_rate {
+            msg_rates.remove(&source);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the blocks following the first known locator hash, drawn from the embedded testnet
+    /// blocks. All the blocks are returned if none of the locator hashes is known.
+    fn blocks_after_locator(locator: &LocatorHashes) -> Vec<Block> {
+        let blocks = Block::initial_testnet_blocks();
+
+        let start = locator
+            .block_locator_hashes
+            .iter()
+            .find_map(|hash| {
+                blocks
+                    .iter()
+                    .position(|block| block.double_sha256().unwrap() == *hash)
+            })
+            .map_or(0, |height| height + 1);
+
+        blocks.into_iter().skip(start).collect()
+    }
+
+    /// Returns the response to a block query when running in cooperative mode.
+    fn cooperative_response(&self, message: &Message) -> Option<Message> {
+        if !self.config.cooperative {
+            return None;
+        }
+
+        match message {
+            Message::GetHeaders(locator) => Some(Message::Headers(Headers::new(
+                Self::blocks_after_locator(locator)
+                    .into_iter()
+                    .map(|block| block.header)
+                    .collect(),
+            ))),
+            Message::GetBlocks(locator) => Some(Message::Inv(Inv::new(
+                Self::blocks_after_locator(locator)
+                    .iter()
+                    .map(|block| block.inv_hash())
+                    .collect(),
+            ))),
+            _ => None,
         }
     }
 
@@ -62,18 +220,25 @@ impl Crawler {
         let timestamp = Instant::now();
 
         let result = self.node.connect(addr).await;
+        let session = self.known_network.session.load(Ordering::Relaxed);
 
         if let Some(ref mut known_node) = self.known_network.nodes.write().get_mut(&addr) {
+            known_node.record_connection_attempt(result.is_ok());
+
             match result {
                 Ok(_) => {
+                    info!(parent: self.node().span(), crawl_event = "connected", peer = %addr, "connected to {}", addr);
                     known_node.connection_failures = 0;
                     known_node.last_connected = Some(timestamp);
-                    known_node.handshake_time = Some(timestamp.elapsed());
                     known_node.state = ConnectionState::Connected;
+                    known_node.last_failure = None;
+                    known_node.record_seen(session);
                 }
-                Err(_) => {
-                    trace!(parent: self.node().span(), "failed to connect to {}", addr);
+                Err(ref e) => {
+                    let failure = ConnectionFailure::from(e);
+                    trace!(parent: self.node().span(), "failed to connect to {}: {} ({:?})", addr, e, failure);
                     known_node.connection_failures += 1;
+                    known_node.last_failure = Some(failure);
                 }
             }
         }
@@ -81,6 +246,33 @@ impl Crawler {
         result
     }
 
+    /// Records the addresses gossiped by `source`, then disconnects from it unless it only
+    /// advertised its own address.
+    async fn process_addrs(&self, source: SocketAddr, addrs: Vec<SocketAddr>) {
+        let len = addrs.len();
+        info!(parent: self.node().span(), crawl_event = "addr_received", peer = %source, count = len, "got {} address(es) from {}", len, source);
+
+        // Overlay addresses are kept as well, they're only reported but never dialed.
+        let listening_addrs = addrs.into_iter().map(normalize_addr).collect::<Vec<_>>();
+
+        // The connections are recorded for every peer, but addresses recently gossiped by any peer
+        // aren't processed again.
+        self.known_network.add_connections(source, &listening_addrs);
+        let new_addrs = self.recent_addrs.lock().filter_new(&listening_addrs);
+        self.known_network.add_nodes(source, &new_addrs);
+
+        // Disconnect after getting more than 1 addresses or if the received address is
+        // not the same as the source address.
+        // In theory, zero length addr response has no sense but it's not
+        // forbidden by the standard so we should handle it. (that's why there is len == 1
+        // condition preventing address comparision to source when len would be 0).
+        if len > 1 || (len == 1 && listening_addrs[0] != source) {
+            self.node().disconnect(source).await;
+            self.known_network
+                .set_node_state(source, ConnectionState::Disconnected);
+        }
+    }
+
     /// Checks to see if crawler should connect to the given address.
     pub fn should_connect(&self, addr: SocketAddr) -> bool {
         if self.known_network.nodes().get(&addr).is_some() {
@@ -111,11 +303,27 @@ impl Handshake for Crawler {
     async fn perform_handshake(&self, mut conn: Connection) -> io::Result<Connection> {
         let conn_addr = conn.addr();
         let own_listening_addr: SocketAddr = ([127, 0, 0, 1], 0).into();
-        let mut framed_stream = Framed::new(self.borrow_stream(&mut conn), MessageCodec::default());
+        let mut framed_stream = Framed::new(
+            self.borrow_stream(&mut conn),
+            MessageCodec::default().with_network(self.config.network),
+        );
 
         let own_version = Message::Version(Version::new(conn_addr, own_listening_addr));
         framed_stream.send(own_version).await?;
 
+        // Record inbound peers, outbound ones are already known.
+        if conn.side() == ConnectionSide::Initiator {
+            let mut nodes = self.known_network.nodes.write();
+            let known_node = nodes.entry(conn_addr).or_default();
+            known_node.last_connected = Some(Instant::now());
+            known_node.state = ConnectionState::Connected;
+            known_node.record_seen(self.known_network.session.load(Ordering::Relaxed));
+        } else if let Some(known_node) = self.known_network.nodes.write().get_mut(&conn_addr) {
+            // Outbound peers closing the connection before sending their version are regarded
+            // as banning the crawler.
+            known_node.awaiting_version_since = Some(Instant::now());
+        }
+
         // Here should be waiting for remote version message but as some nodes don't send it
         // quickly enough we will wait for it in the process_message function.
         // @see process_message function for more details.
@@ -130,38 +338,62 @@ impl Reading for Crawler {
     type Codec = MessageCodec;
 
     fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+        MessageCodec::default().with_network(self.config.network)
     }
 
     async fn process_message(&self, source: SocketAddr, message: Self::Message) -> io::Result<()> {
-        match message {
-            Message::Addr(addr) => {
-                let len = addr.addrs.len();
-                info!(parent: self.node().span(), "got {} address(es) from {}", len, source);
+        if self.exceeds_msg_rate(source) {
+            warn!(parent: self.node().span(), "disconnecting from node {} because it exceeded the message rate", source);
+            if let Some(known_node) = self.known_network.nodes.write().get_mut(&source) {
+                known_node.throttle_events += 1;
+            }
+            self.node().disconnect(source).await;
+            self.known_network
+                .set_node_state(source, ConnectionState::Disconnected);
 
-                let mut listening_addrs = Vec::with_capacity(len);
-                for addr in &addr.addrs {
-                    listening_addrs.push(addr.addr);
-                }
+            return Ok(());
+        }
 
-                self.known_network.add_addrs(source, &listening_addrs);
-
-                // Disconnect after getting more than 1 addresses or if the received address is
-                // not the same as the source address.
-                // In theory, zero length addr response has no sense but it's not
-                // forbidden by the standard so we should handle it. (that's why there is len == 1
-                // condition preventing address comparision to source when len would be 0).
-                if len > 1 || (len == 1 && addr.addrs[0].addr != source) {
-                    self.node().disconnect(source).await;
-                    self.known_network
-                        .set_node_state(source, ConnectionState::Disconnected);
-                }
+        if let Some(response) = self.cooperative_response(&message) {
+            let _ = self.unicast(source, response)?.await;
+            return Ok(());
+        }
+
+        match message {
+            Message::Addr(addr) => {
+                let addrs = addr.iter().map(|addr| addr.addr).collect::<Vec<_>>();
+                self.process_addrs(source, addrs).await;
+            }
+            Message::AddrV2(addrv2) => {
+                // Addresses which can't be represented as a socket address (Tor v3, I2P) are
+                // skipped.
+                let addrs = addrv2
+                    .iter()
+                    .filter_map(|addr| addr.socket_addr())
+                    .collect::<Vec<_>>();
+                self.process_addrs(source, addrs).await;
             }
             Message::Ping(nonce) => {
                 let _ = self.unicast(source, Message::Pong(nonce))?.await;
             }
             Message::GetAddr => {
-                let _ = self.unicast(source, Message::Addr(Addr::empty()))?.await;
+                let addr = match (&self.config.served_peers, self.config.served_addr_limit) {
+                    (Some(served_peers), limit) => {
+                        addr_from_list(served_peers, limit.unwrap_or(MAX_ADDRS))
+                    }
+                    (None, Some(limit)) => {
+                        let known_addrs = self
+                            .known_network
+                            .nodes
+                            .read()
+                            .keys()
+                            .copied()
+                            .collect::<Vec<_>>();
+                        self.known_network.addr_from_nodes(&known_addrs, limit)
+                    }
+                    (None, None) => Addr::empty(),
+                };
+                let _ = self.unicast(source, Message::Addr(addr))?.await;
             }
             Message::GetHeaders(_) => {
                 let _ = self
@@ -171,13 +403,26 @@ impl Reading for Crawler {
             Message::GetData(inv) => {
                 let _ = self.unicast(source, Message::NotFound(inv.clone()))?.await;
             }
+            Message::Reject(reject) if reject.message.0 == "version" => {
+                warn!(parent: self.node().span(), "node {} rejected our version: {:?} ({})", source, reject.ccode, reject.reason.0);
+                self.known_network
+                    .record_failure(source, ConnectionFailure::VersionRejected);
+                self.node().disconnect(source).await;
+                self.known_network
+                    .set_node_state(source, ConnectionState::Disconnected);
+            }
+            Message::Verack => self.record_handshake_latency(source),
             Message::Version(ver) => {
+                info!(parent: self.node().span(), crawl_event = "handshake_complete", peer = %source, protocol_version = ver.version.0, user_agent = %ver.user_agent.0, start_height = ver.start_height, "completed the handshake with {}", source);
+
                 // Update source node with information from version.
                 if let Some(known_node) = self.known_network.nodes.write().get_mut(&source) {
+                    known_node.addr_from = Some(ver.addr_from.addr);
                     known_node.protocol_version = Some(ver.version);
                     known_node.user_agent = Some(ver.user_agent);
                     known_node.services = Some(ver.services);
                     known_node.start_height = Some(ver.start_height);
+                    known_node.awaiting_version_since = None;
                 }
 
                 let _ = self.unicast(source, Message::Verack)?.await;
@@ -200,11 +445,361 @@ impl Reading for Crawler {
     }
 }
 
+#[async_trait::async_trait]
+impl Disconnect for Crawler {
+    async fn handle_disconnect(&self, addr: SocketAddr) {
+        info!(parent: self.node().span(), crawl_event = "disconnected", peer = %addr, "disconnected from {}", addr);
+        self.msg_rates.lock().remove(&addr);
+        self.known_network.record_disconnect(addr);
+        // Count the disconnection right away, rather than when the node is next regarded as stale.
+        self.known_network
+            .set_node_state(addr, ConnectionState::Disconnected);
+    }
+}
+
 impl Writing for Crawler {
     type Message = Message;
     type Codec = MessageCodec;
 
     fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+        MessageCodec::default().with_network(self.config.network)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ziggurat_zcash::{
+        protocol::payload::{
+            addrv2::{AddrV2Address, NetworkAddrV2},
+            AddrV2,
+        },
+        tools::{synthetic_node::SyntheticNode, LONG_TIMEOUT},
+        wait_until,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn cooperative_get_headers() {
+        let locator = LocatorHashes::after_height(&Block::initial_testnet_blocks(), 5);
+        let get_headers = Message::GetHeaders(locator);
+
+        let crawler = Crawler::new(CrawlerConfig::default()).await;
+        assert!(crawler.cooperative_response(&get_headers).is_none());
+
+        let crawler = Crawler::new(CrawlerConfig {
+            cooperative: true,
+            ..Default::default()
+        })
+        .await;
+        match crawler.cooperative_response(&get_headers) {
+            Some(Message::Headers(headers)) => {
+                assert!(!headers.headers.is_empty());
+                // Only the blocks after the locator's tip are returned.
+                assert_eq!(
+                    headers.headers[0],
+                    Block::initial_testnet_blocks()[6].header
+                );
+            }
+            other => panic!("expected Headers, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn passive_records_inbound_peer() {
+        const USER_AGENT: &str = "/Inbound:1.0.0/";
+
+        let crawler = Crawler::new(CrawlerConfig {
+            listening_addr: Some(([127, 0, 0, 1], 0).into()),
+            ..Default::default()
+        })
+        .await;
+        crawler.enable_handshake().await;
+        crawler.enable_reading().await;
+        crawler.enable_writing().await;
+        let crawler_addr = crawler.node().start_listening().await.unwrap();
+
+        // The crawler sends its Version without waiting for a Verack, so a version exchange is
+        // enough to complete the handshake on both sides.
+        let synthetic_node = SyntheticNode::builder()
+            .with_version_exchange_handshake()
+            .with_user_agent(USER_AGENT.to_string())
+            .build()
+            .await
+            .unwrap();
+        synthetic_node.connect(crawler_addr).await.unwrap();
+
+        wait_until!(LONG_TIMEOUT, crawler.node().num_connected() == 1);
+        let peer_addr = crawler.node().connected_addrs()[0];
+        wait_until!(
+            LONG_TIMEOUT,
+            crawler
+                .known_network
+                .nodes()
+                .get(&peer_addr)
+                .is_some_and(|node| node.user_agent.is_some())
+        );
+
+        let known_node = crawler.known_network.nodes()[&peer_addr].clone();
+        assert_eq!(known_node.state, ConnectionState::Connected);
+        assert_eq!(known_node.user_agent.unwrap().0, USER_AGENT);
+        assert_eq!(
+            known_node.addr_from,
+            Some(synthetic_node.listening_addr().unwrap())
+        );
+
+        synthetic_node.shut_down().await;
+        crawler.node().shut_down().await;
+    }
+
+    #[tokio::test]
+    async fn served_addr_respects_the_limit() {
+        const SERVED_ADDR_LIMIT: usize = 3;
+
+        let crawler = Crawler::new(CrawlerConfig {
+            listening_addr: Some(([127, 0, 0, 1], 0).into()),
+            served_addr_limit: Some(SERVED_ADDR_LIMIT),
+            ..Default::default()
+        })
+        .await;
+        crawler.enable_handshake().await;
+        crawler.enable_reading().await;
+        crawler.enable_writing().await;
+        let crawler_addr = crawler.node().start_listening().await.unwrap();
+
+        // More known nodes the crawler has connected to than it may serve.
+        let source = SocketAddr::from(([10, 0, 0, 1], 8233));
+        let addrs = (0..10)
+            .map(|i| SocketAddr::from(([10, 0, 1, i], 8233)))
+            .collect::<Vec<_>>();
+        crawler.known_network.add_addrs(source, &addrs);
+        for node in crawler.known_network.nodes.write().values_mut() {
+            node.last_connected = Some(Instant::now());
+        }
+
+        let mut synthetic_node = SyntheticNode::builder()
+            .with_version_exchange_handshake()
+            .build()
+            .await
+            .unwrap();
+        synthetic_node.connect(crawler_addr).await.unwrap();
+        synthetic_node
+            .unicast(crawler_addr, Message::GetAddr)
+            .unwrap();
+
+        // Skip the crawler's own queries until its reply arrives.
+        let served = loop {
+            match synthetic_node.recv_message_timeout(LONG_TIMEOUT).await {
+                Ok((_, Message::Addr(addr))) => break addr,
+                Ok(_) => continue,
+                Err(e) => panic!("no Addr received: {e}"),
+            }
+        };
+        assert_eq!(served.addrs.len(), SERVED_ADDR_LIMIT);
+
+        synthetic_node.shut_down().await;
+        crawler.node().shut_down().await;
+    }
+
+    #[tokio::test]
+    async fn served_peers_answer_getaddr() {
+        let served_peers = (0..10)
+            .map(|i| SocketAddr::from(([10, 0, 2, i], 8233)))
+            .collect::<Vec<_>>();
+
+        let crawler = Crawler::new(CrawlerConfig {
+            listening_addr: Some(([127, 0, 0, 1], 0).into()),
+            served_addr_limit: Some(4),
+            served_peers: Some(served_peers.clone().into()),
+            ..Default::default()
+        })
+        .await;
+        crawler.enable_handshake().await;
+        crawler.enable_reading().await;
+        crawler.enable_writing().await;
+        let crawler_addr = crawler.node().start_listening().await.unwrap();
+
+        let mut synthetic_node = SyntheticNode::builder()
+            .with_version_exchange_handshake()
+            .build()
+            .await
+            .unwrap();
+        synthetic_node.connect(crawler_addr).await.unwrap();
+        synthetic_node
+            .unicast(crawler_addr, Message::GetAddr)
+            .unwrap();
+
+        let served = loop {
+            match synthetic_node.recv_message_timeout(LONG_TIMEOUT).await {
+                Ok((_, Message::Addr(addr))) => break addr,
+                Ok(_) => continue,
+                Err(e) => panic!("no Addr received: {e}"),
+            }
+        };
+        // The list is served instead of the known nodes, within the limit.
+        assert_eq!(served.addrs.len(), 4);
+        assert!(served
+            .addrs
+            .iter()
+            .all(|addr| served_peers.contains(&addr.addr)));
+
+        synthetic_node.shut_down().await;
+        crawler.node().shut_down().await;
+    }
+
+    #[tokio::test]
+    async fn addrv2_addrs_are_recorded() {
+        let crawler = Crawler::new(CrawlerConfig {
+            listening_addr: Some(([127, 0, 0, 1], 0).into()),
+            ..Default::default()
+        })
+        .await;
+        crawler.enable_handshake().await;
+        crawler.enable_reading().await;
+        crawler.enable_writing().await;
+        let crawler_addr = crawler.node().start_listening().await.unwrap();
+
+        let synthetic_node = SyntheticNode::builder()
+            .with_version_exchange_handshake()
+            .build()
+            .await
+            .unwrap();
+        synthetic_node.connect(crawler_addr).await.unwrap();
+
+        let ipv6: SocketAddr = "[2001:db8::1]:8233".parse().unwrap();
+        let mut tor_v3 = NetworkAddrV2::new(ipv6);
+        tor_v3.addr = AddrV2Address::TorV3([0; 32]);
+        let addrv2 = AddrV2::new(vec![NetworkAddrV2::new(ipv6), tor_v3]);
+        synthetic_node
+            .unicast(crawler_addr, Message::AddrV2(addrv2))
+            .unwrap();
+
+        // The Tor v3 address can't be represented, so only the IPv6 one is known.
+        wait_until!(
+            LONG_TIMEOUT,
+            crawler.known_network.nodes().contains_key(&ipv6)
+        );
+        assert_eq!(crawler.known_network.num_nodes(), 2);
+
+        synthetic_node.shut_down().await;
+        crawler.node().shut_down().await;
+    }
+
+    #[tokio::test]
+    async fn peer_exceeding_msg_rate_is_disconnected() {
+        const MAX_MSG_RATE: u32 = 10;
+
+        let crawler = Crawler::new(CrawlerConfig {
+            listening_addr: Some(([127, 0, 0, 1], 0).into()),
+            max_msg_rate: Some(MAX_MSG_RATE),
+            ..Default::default()
+        })
+        .await;
+        crawler.enable_handshake().await;
+        crawler.enable_reading().await;
+        crawler.enable_writing().await;
+        let crawler_addr = crawler.node().start_listening().await.unwrap();
+
+        let synthetic_node = SyntheticNode::builder()
+            .with_version_exchange_handshake()
+            .build()
+            .await
+            .unwrap();
+        synthetic_node.connect(crawler_addr).await.unwrap();
+
+        wait_until!(LONG_TIMEOUT, crawler.node().num_connected() == 1);
+        let peer_addr = crawler.node().connected_addrs()[0];
+
+        // Flood the crawler, well within a single rate window.
+        for _ in 0..MAX_MSG_RATE * 2 {
+            synthetic_node
+                .unicast(crawler_addr, Message::Ping(Nonce::default()))
+                .unwrap();
+        }
+
+        wait_until!(LONG_TIMEOUT, synthetic_node.num_connected() == 0);
+        assert_eq!(crawler.known_network.nodes()[&peer_addr].throttle_events, 1);
+        assert_eq!(
+            crawler.known_network.throttle_stats().num_throttled_nodes,
+            1
+        );
+
+        synthetic_node.shut_down().await;
+        crawler.node().shut_down().await;
+    }
+
+    #[tokio::test]
+    async fn msg_rate_is_forgotten_on_disconnect() {
+        let crawler = Crawler::new(CrawlerConfig {
+            listening_addr: Some(([127, 0, 0, 1], 0).into()),
+            max_msg_rate: Some(10),
+            ..Default::default()
+        })
+        .await;
+        crawler.enable_handshake().await;
+        crawler.enable_reading().await;
+        crawler.enable_writing().await;
+        crawler.enable_disconnect().await;
+        let crawler_addr = crawler.node().start_listening().await.unwrap();
+
+        let synthetic_node = SyntheticNode::builder()
+            .with_version_exchange_handshake()
+            .build()
+            .await
+            .unwrap();
+        synthetic_node.connect(crawler_addr).await.unwrap();
+        wait_until!(LONG_TIMEOUT, crawler.node().num_connected() == 1);
+
+        synthetic_node
+            .unicast(crawler_addr, Message::Ping(Nonce::default()))
+            .unwrap();
+        wait_until!(LONG_TIMEOUT, crawler.msg_rates.lock().len() == 1);
+
+        // The peer's rate is dropped along with the connection, rather than kept until a reset.
+        synthetic_node.shut_down().await;
+        wait_until!(LONG_TIMEOUT, crawler.node().num_connected() == 0);
+        wait_until!(LONG_TIMEOUT, crawler.msg_rates.lock().is_empty());
+
+        crawler.node().shut_down().await;
+    }
+
+    #[tokio::test]
+    async fn remote_disconnect_is_counted() {
+        let crawler = Crawler::new(CrawlerConfig {
+            listening_addr: Some(([127, 0, 0, 1], 0).into()),
+            ..Default::default()
+        })
+        .await;
+        crawler.enable_handshake().await;
+        crawler.enable_reading().await;
+        crawler.enable_writing().await;
+        crawler.enable_disconnect().await;
+        let crawler_addr = crawler.node().start_listening().await.unwrap();
+
+        let synthetic_node = SyntheticNode::builder()
+            .with_version_exchange_handshake()
+            .build()
+            .await
+            .unwrap();
+        synthetic_node.connect(crawler_addr).await.unwrap();
+        wait_until!(LONG_TIMEOUT, crawler.node().num_connected() == 1);
+        let peer_addr = crawler.node().connected_addrs()[0];
+        assert_eq!(
+            crawler.known_network.nodes()[&peer_addr].state,
+            ConnectionState::Connected
+        );
+
+        // The peer closing the connection is counted without waiting for the stale node sweep.
+        synthetic_node.shut_down().await;
+        wait_until!(
+            LONG_TIMEOUT,
+            crawler.known_network.nodes()[&peer_addr].disconnections == 1
+        );
+        assert_eq!(
+            crawler.known_network.nodes()[&peer_addr].state,
+            ConnectionState::Disconnected
+        );
+
+        crawler.node().shut_down().await;
     }
 }
@@ -5,15 +5,45 @@ use parking_lot::Mutex;
 use tracing::debug;
 use ziggurat_core_crawler::summary::NetworkSummary;
 
-pub struct RpcContext(Arc<Mutex<NetworkSummary>>);
+use crate::{
+    metrics::{DegreeStats, HandshakeLatencyStats, HeightStats, ProtocolVersionCounts},
+    network::NUM_FLAKIEST_NODES,
+    protocol::Crawler,
+};
+
+pub struct RpcContext {
+    summary: Arc<Mutex<NetworkSummary>>,
+    crawler: Crawler,
+    seed_addrs: Vec<SocketAddr>,
+    degree_stats: Arc<Mutex<DegreeStats>>,
+    height_stats: Arc<Mutex<HeightStats>>,
+    protocol_version_counts: Arc<Mutex<ProtocolVersionCounts>>,
+    handshake_latency_stats: Arc<Mutex<HandshakeLatencyStats>>,
+}
 
 /// Allow JSON-RPC response size to be up to 200MB
 pub const MAX_RESPONSE_SIZE: u32 = 200_000_000;
 
 impl RpcContext {
     /// Creates a new RpcContext.
-    pub fn new(known_network: Arc<Mutex<NetworkSummary>>) -> RpcContext {
-        RpcContext(known_network)
+    pub fn new(
+        summary: Arc<Mutex<NetworkSummary>>,
+        crawler: Crawler,
+        seed_addrs: Vec<SocketAddr>,
+        degree_stats: Arc<Mutex<DegreeStats>>,
+        height_stats: Arc<Mutex<HeightStats>>,
+        protocol_version_counts: Arc<Mutex<ProtocolVersionCounts>>,
+        handshake_latency_stats: Arc<Mutex<HandshakeLatencyStats>>,
+    ) -> RpcContext {
+        RpcContext {
+            summary,
+            crawler,
+            seed_addrs,
+            degree_stats,
+            height_stats,
+            protocol_version_counts,
+            handshake_latency_stats,
+        }
     }
 }
 
@@ -21,7 +51,7 @@ impl std::ops::Deref for RpcContext {
     type Target = Mutex<NetworkSummary>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.summary
     }
 }
 
@@ -49,5 +79,92 @@ fn create_rpc_module(rpc_context: RpcContext) -> RpcModule<RpcContext> {
         })
         .unwrap();
 
+    module
+        .register_method("getflakiestnodes", |_, rpc_context| {
+            Ok(rpc_context
+                .crawler
+                .known_network
+                .flakiest_nodes(NUM_FLAKIEST_NODES))
+        })
+        .unwrap();
+
+    module
+        .register_method("getconnectionfailures", |_, rpc_context| {
+            Ok(rpc_context
+                .crawler
+                .known_network
+                .connection_failure_counts())
+        })
+        .unwrap();
+
+    module
+        .register_method("getnevercontacted", |_, rpc_context| {
+            Ok(rpc_context.crawler.known_network.never_contacted_stats())
+        })
+        .unwrap();
+
+    module
+        .register_method("getaddrclasscounts", |_, rpc_context| {
+            Ok(rpc_context.crawler.known_network.addr_class_counts())
+        })
+        .unwrap();
+
+    module
+        .register_method("getthrottlestats", |_, rpc_context| {
+            Ok(rpc_context.crawler.known_network.throttle_stats())
+        })
+        .unwrap();
+
+    module
+        .register_method("getstability", |_, rpc_context| {
+            Ok(rpc_context.crawler.known_network.stability_stats())
+        })
+        .unwrap();
+
+    module
+        .register_method("getnode", |params, rpc_context| {
+            let addr: SocketAddr = params.one()?;
+            Ok(rpc_context.crawler.known_network.node_details(addr))
+        })
+        .unwrap();
+
+    module
+        .register_method("getpeersof", |params, rpc_context| {
+            let addr: SocketAddr = params.one()?;
+            Ok(rpc_context.crawler.known_network.peers_of(addr))
+        })
+        .unwrap();
+
+    module
+        .register_method("getdegreestats", |_, rpc_context| {
+            Ok(*rpc_context.degree_stats.lock())
+        })
+        .unwrap();
+
+    module
+        .register_method("getheightstats", |_, rpc_context| {
+            Ok(*rpc_context.height_stats.lock())
+        })
+        .unwrap();
+
+    module
+        .register_method("getprotocolversioncounts", |_, rpc_context| {
+            Ok(rpc_context.protocol_version_counts.lock().clone())
+        })
+        .unwrap();
+
+    module
+        .register_method("gethandshakelatencies", |_, rpc_context| {
+            Ok(*rpc_context.handshake_latency_stats.lock())
+        })
+        .unwrap();
+
+    module
+        .register_async_method("reset", |_, rpc_context| async move {
+            rpc_context.crawler.reset(&rpc_context.seed_addrs).await;
+            Ok(())
+        })
+        .unwrap();
+
     module
 }
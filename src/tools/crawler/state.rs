@@ -0,0 +1,151 @@
+//! The crawler's state persisted across runs, so a restarted crawler resumes from the nodes it
+//! already knows instead of re-crawling the whole network from the seed nodes.
+//!
+//! Plain peer lists, one address per line, can also be imported to be served to the crawled nodes
+//! and exported for other tools.
+
+use std::{fs, io, net::SocketAddr, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// The persisted state of a known node.
+///
+/// Only what outlives a connection is kept, the connection state and monotonic timestamps are
+/// reset when the state is restored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedNode {
+    pub addr: SocketAddr,
+    pub addr_from: Option<SocketAddr>,
+    pub handshake_time: Option<Duration>,
+    pub protocol_version: Option<u32>,
+    pub user_agent: Option<String>,
+    pub start_height: Option<i32>,
+    pub services: Option<u64>,
+    pub connection_attempts: u32,
+    pub successful_connections: u32,
+    pub disconnections: u32,
+    pub throttle_events: u32,
+    pub ever_contacted: bool,
+    pub sessions_seen: u32,
+    /// The last time the node was successfully contacted, as a Unix timestamp.
+    pub last_seen: Option<i64>,
+    pub last_seen_session: Option<u32>,
+}
+
+/// The persisted state of the known network.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkState {
+    /// The index of the crawling session the state was saved in.
+    pub session: u32,
+    pub nodes: Vec<PersistedNode>,
+}
+
+impl NetworkState {
+    /// Loads the state from the JSON file at `path`.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = fs::read(path)?;
+        serde_json::from_slice(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Saves the state as JSON to the file at `path`.
+    ///
+    /// The state is written to a temporary file first, which then replaces the previous one, so
+    /// an interrupted checkpoint never leaves a truncated state behind.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json =
+            serde_json::to_vec(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, json)?;
+        fs::rename(&tmp_path, path)
+    }
+}
+
+/// Loads a newline-separated list of addresses from the file at `path`, skipping blank lines.
+pub fn load_peer_list(path: &Path) -> io::Result<Vec<SocketAddr>> {
+    fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            line.parse().map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid address {line:?} on line {}: {e}", i + 1),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Saves the addresses to the file at `path`, one per line.
+pub fn save_peer_list(path: &Path, addrs: &[SocketAddr]) -> io::Result<()> {
+    let list = addrs
+        .iter()
+        .map(|addr| format!("{addr}\n"))
+        .collect::<String>();
+
+    fs::write(path, list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_file_round_trip() {
+        let state = NetworkState {
+            session: 3,
+            nodes: vec![PersistedNode {
+                addr: "127.0.0.1:8233".parse().unwrap(),
+                addr_from: Some("127.0.0.1:8233".parse().unwrap()),
+                handshake_time: Some(Duration::from_millis(150)),
+                protocol_version: Some(170_100),
+                user_agent: Some("/Zebra:1.0.0/".to_owned()),
+                start_height: Some(2_000_000),
+                services: Some(1),
+                connection_attempts: 4,
+                successful_connections: 3,
+                disconnections: 1,
+                throttle_events: 0,
+                ever_contacted: true,
+                sessions_seen: 2,
+                last_seen: Some(1_700_000_000),
+                last_seen_session: Some(3),
+            }],
+        };
+
+        let path = std::env::temp_dir().join(format!("crawler-state-{}.json", std::process::id()));
+        state.save(&path).unwrap();
+        let loaded = NetworkState::load(&path);
+        fs::write(&path, "not json").unwrap();
+        let corrupted = NetworkState::load(&path);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(loaded.unwrap(), state);
+        assert_eq!(corrupted.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn peer_list_round_trip() {
+        let addrs: Vec<SocketAddr> = vec![
+            "127.0.0.1:8233".parse().unwrap(),
+            "[2001:db8::1]:18233".parse().unwrap(),
+        ];
+
+        let path = std::env::temp_dir().join(format!("crawler-peers-{}.txt", std::process::id()));
+        save_peer_list(&path, &addrs).unwrap();
+        let loaded = load_peer_list(&path);
+        fs::write(&path, "127.0.0.1:8233\n\n  10.0.0.1:8233 \n").unwrap();
+        let padded = load_peer_list(&path);
+        fs::write(&path, "127.0.0.1:8233\nnot an address\n").unwrap();
+        let invalid = load_peer_list(&path);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(loaded.unwrap(), addrs);
+        assert_eq!(padded.unwrap().len(), 2);
+        assert_eq!(invalid.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -1,8 +1,12 @@
 //! Useful helper functions for fuzzing.
 
+pub mod corpus;
+
 use std::{
     convert::TryInto,
-    net::{IpAddr, Ipv4Addr, SocketAddr},
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    ops::Range,
 };
 
 use bytes::BufMut;
@@ -12,13 +16,15 @@ use rand::{
     thread_rng,
 };
 use rand_chacha::ChaCha8Rng;
+use time::OffsetDateTime;
 
 use crate::protocol::{
     message::{constants::*, Message, MessageHeader},
     payload::{
+        addr::NetworkAddr,
         block::{Headers, LocatorHashes},
         codec::Codec,
-        Addr, Inv, Nonce, Version,
+        Addr, Inv, Nonce, VarInt, Version,
     },
 };
 
@@ -41,14 +47,36 @@ pub const COMMANDS_WITH_PAYLOADS: [[u8; 12]; 13] = [
 
 const CORRUPTION_PROBABILITY: f64 = 0.5;
 
+/// The default maximum length of the payloads generated by [`random_bytes`] and
+/// [`metadata_compliant_random_bytes`].
+pub const DEFAULT_RANDOM_PAYLOAD_MAX_LEN: usize = 64 * 1024;
+
+/// The maximum age of the entries generated by [`random_addr`].
+pub const MAX_RANDOM_ADDR_AGE_SECS: i64 = 3 * 60 * 60;
+
+/// The seed of the RNGs used for fuzzing.
+pub type RngSeed = <ChaCha8Rng as SeedableRng>::Seed;
+
 /// Returns a randomly seeded `ChaCha8Rng` instance, useful for making tests reproducible.
 pub fn seeded_rng() -> ChaCha8Rng {
-    let mut seed: <ChaCha8Rng as SeedableRng>::Seed = Default::default();
+    seeded_rng_with_seed().0
+}
+
+/// Returns a randomly seeded `ChaCha8Rng` instance along with its seed, which can be recorded
+/// with the generated payloads, see [`corpus::CorpusRecorder`].
+pub fn seeded_rng_with_seed() -> (ChaCha8Rng, RngSeed) {
+    let mut seed: RngSeed = Default::default();
     thread_rng().fill(&mut seed);
 
     // We print the seed for reproducibility.
     println!("Seed for RNG: {seed:?}");
 
+    (rng_from_seed(seed), seed)
+}
+
+/// Returns a `ChaCha8Rng` instance with the given seed, which regenerates the same sequence as
+/// the run the seed was recorded in.
+pub fn rng_from_seed(seed: RngSeed) -> ChaCha8Rng {
     // Isn't cryptographically secure but adequate enough as a general source of seeded randomness.
     ChaCha8Rng::from_seed(seed)
 }
@@ -77,6 +105,116 @@ pub fn default_fuzz_messages() -> Vec<Message> {
     ]
 }
 
+/// Returns an [`Addr`] message with as many entries as fit in an encoded body of `max_body_len`
+/// bytes.
+///
+/// Useful for sending messages which are valid, but close to [`MAX_MESSAGE_LEN`] in size.
+pub fn addr_with_body_len_up_to(max_body_len: usize) -> Message {
+    // Timestamp (4) + services (8) + IPv6 address (16) + port (2).
+    const NETWORK_ADDR_LEN: usize = 30;
+
+    let var_int_len = |n: usize| match n {
+        0..=0xfc => 1,
+        0xfd..=0xffff => 3,
+        0x10000..=0xffff_ffff => 5,
+        _ => 9,
+    };
+
+    let mut num_addrs = max_body_len / NETWORK_ADDR_LEN;
+    while var_int_len(num_addrs) + num_addrs * NETWORK_ADDR_LEN > max_body_len {
+        num_addrs -= 1;
+    }
+
+    let addr = NetworkAddr::new(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8233));
+    Message::Addr(Addr::new(vec![addr; num_addrs]))
+}
+
+/// Returns an [`Addr`] with `len` random IPv4 and IPv6 entries, advertised as seen within the
+/// last [`MAX_RANDOM_ADDR_AGE_SECS`].
+///
+/// Entries are unlikely to repeat, so each message presents the node with new addresses.
+pub fn random_addr(rng: &mut ChaCha8Rng, len: usize) -> Addr {
+    Addr::new((0..len).map(|_| random_network_addr(rng)).collect())
+}
+
+/// Returns an [`Addr`] with `len` entries picked at random from `pool`, so that entries repeat
+/// whenever `len` exceeds the size of the pool.
+///
+/// The pool can hold e.g. the node's own address or those of the senders, to probe the node's
+/// handling of self-advertisements. Panics if `pool` is empty.
+pub fn addr_from_pool(rng: &mut ChaCha8Rng, len: usize, pool: &[SocketAddr]) -> Addr {
+    let now = OffsetDateTime::now_utc();
+    Addr::new(
+        (0..len)
+            .map(|_| NetworkAddr {
+                last_seen: Some(now),
+                services: 1,
+                addr: *pool.choose(rng).unwrap(),
+            })
+            .collect(),
+    )
+}
+
+fn random_network_addr(rng: &mut ChaCha8Rng) -> NetworkAddr {
+    let ip = if rng.gen() {
+        IpAddr::V4(Ipv4Addr::from(rng.gen::<u32>()))
+    } else {
+        IpAddr::V6(Ipv6Addr::from(rng.gen::<u128>()))
+    };
+    let age = time::Duration::seconds(rng.gen_range(0..MAX_RANDOM_ADDR_AGE_SECS));
+
+    NetworkAddr {
+        last_seen: Some(OffsetDateTime::now_utc() - age),
+        services: 1,
+        addr: SocketAddr::new(ip, rng.gen_range(1..=u16::MAX)),
+    }
+}
+
+/// Returns the encoded bytes (header included) of a well-formed [`Version`] message sent from
+/// `from` to `to`.
+///
+/// Useful as a valid base for mutation, see [`encode_slightly_corrupted_versions`].
+pub fn valid_version_bytes(from: SocketAddr, to: SocketAddr) -> Vec<u8> {
+    Message::Version(Version::new(to, from)).to_vec().unwrap()
+}
+
+/// Returns the encoded bytes (header included) of `message` with its entry count replaced by
+/// `declared_len`, while the entries themselves are left untouched.
+///
+/// The header is valid for the resulting body, so only the count lies about the number of
+/// entries present. Only messages whose body starts with an entry count are supported, i.e.
+/// [`Addr`], [`Headers`] and the [`Inv`] based messages; an
+/// [`InvalidInput`](io::ErrorKind::InvalidInput) error is returned for any other message.
+pub fn encode_with_lying_len(message: &Message, declared_len: usize) -> io::Result<Vec<u8>> {
+    let command = match message {
+        Message::Addr(_) => ADDR_COMMAND,
+        Message::Headers(_) => HEADERS_COMMAND,
+        Message::Inv(_) => INV_COMMAND,
+        Message::GetData(_) => GETDATA_COMMAND,
+        Message::NotFound(_) => NOTFOUND_COMMAND,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{message:?} doesn't start with an entry count"),
+            ))
+        }
+    };
+
+    let bytes = message.to_vec()?;
+    let mut entries = &bytes[HEADER_LEN..];
+    VarInt::decode(&mut entries)?;
+
+    let mut body = Vec::with_capacity(bytes.len() - HEADER_LEN);
+    VarInt::new(declared_len).encode(&mut body)?;
+    body.extend_from_slice(entries);
+
+    let mut buffer = Vec::with_capacity(HEADER_LEN + body.len());
+    MessageHeader::new(command, &body).encode(&mut buffer)?;
+    buffer.append(&mut body);
+
+    Ok(buffer)
+}
+
 /// Returns `n` random length sets of zeroes.
 pub fn zeroes(rng: &mut ChaCha8Rng, n: usize) -> Vec<Vec<u8>> {
     (0..n)
@@ -87,11 +225,33 @@ pub fn zeroes(rng: &mut ChaCha8Rng, n: usize) -> Vec<Vec<u8>> {
         .collect()
 }
 
-/// Returns `n` random length sets of random bytes.
+/// Returns `n` sets of `len` zeroes, useful to target specific boundary lengths.
+pub fn zeroes_of_len(n: usize, len: usize) -> Vec<Vec<u8>> {
+    vec![vec![0u8; len]; n]
+}
+
+/// Returns `n` random length sets of random bytes, up to [`DEFAULT_RANDOM_PAYLOAD_MAX_LEN`] bytes
+/// long.
 pub fn random_bytes(rng: &mut ChaCha8Rng, n: usize) -> Vec<Vec<u8>> {
+    random_bytes_up_to(rng, n, DEFAULT_RANDOM_PAYLOAD_MAX_LEN)
+}
+
+/// Returns a random payload length between 1 and `max_len`, or 0 if `max_len` is 0.
+fn random_payload_len(rng: &mut ChaCha8Rng, max_len: usize) -> usize {
+    if max_len == 0 {
+        return 0;
+    }
+
+    rng.gen_range(1..=max_len)
+}
+
+/// Returns `n` random length sets of random bytes, up to `max_len` bytes long.
+///
+/// The payloads are empty if `max_len` is 0.
+pub fn random_bytes_up_to(rng: &mut ChaCha8Rng, n: usize, max_len: usize) -> Vec<Vec<u8>> {
     (0..n)
         .map(|_| {
-            let random_len: usize = rng.gen_range(1..(64 * 1024));
+            let random_len = random_payload_len(rng, max_len);
             let random_payload: Vec<u8> = rng.sample_iter(Standard).take(random_len).collect();
 
             random_payload
@@ -99,15 +259,30 @@ pub fn random_bytes(rng: &mut ChaCha8Rng, n: usize) -> Vec<Vec<u8>> {
         .collect()
 }
 
-/// Returns a message with a valid header and payload of random bytes.
+/// Returns `n` messages with a valid header and a payload of random bytes, up to
+/// [`DEFAULT_RANDOM_PAYLOAD_MAX_LEN`] bytes long.
 pub fn metadata_compliant_random_bytes(
     rng: &mut ChaCha8Rng,
     n: usize,
     commands: &[[u8; 12]],
+) -> Vec<Vec<u8>> {
+    metadata_compliant_random_bytes_up_to(rng, n, commands, DEFAULT_RANDOM_PAYLOAD_MAX_LEN)
+}
+
+/// Returns `n` messages with a valid header and a payload of random bytes, up to `max_len` bytes
+/// long.
+///
+/// The payloads are empty if `max_len` is 0. The payload isn't limited by the frame size, so e.g.
+/// [`MAX_MESSAGE_LEN`] can be used to probe the node's handling of payloads larger than it accepts.
+pub fn metadata_compliant_random_bytes_up_to(
+    rng: &mut ChaCha8Rng,
+    n: usize,
+    commands: &[[u8; 12]],
+    max_len: usize,
 ) -> Vec<Vec<u8>> {
     (0..n)
         .map(|_| {
-            let random_len: usize = rng.gen_range(1..(64 * 1024));
+            let random_len = random_payload_len(rng, max_len);
             let mut random_payload: Vec<u8> = rng.sample_iter(Standard).take(random_len).collect();
 
             let command = commands.choose(rng).unwrap();
@@ -136,11 +311,27 @@ pub fn encode_slightly_corrupted_messages(
         .collect()
 }
 
+/// Corrupts `n` copies of a valid [`Version`] message sent from `from` to `to` by replacing a
+/// random number of bytes with random bytes.
+pub fn encode_slightly_corrupted_versions(
+    rng: &mut ChaCha8Rng,
+    n: usize,
+    from: SocketAddr,
+    to: SocketAddr,
+) -> Vec<Vec<u8>> {
+    let valid_bytes = valid_version_bytes(from, to);
+
+    (0..n)
+        .map(|_| corrupt_message_bytes(rng, &valid_bytes))
+        .collect()
+}
+
 fn corrupt_message(rng: &mut ChaCha8Rng, message: &Message) -> Vec<u8> {
-    let mut bytes = Default::default();
-    message.encode(&mut bytes).unwrap();
-    let vec: Vec<_> = bytes.to_vec();
-    let (valid_header, valid_message) = vec.split_at(HEADER_LEN);
+    corrupt_message_bytes(rng, &message.to_vec().unwrap())
+}
+
+fn corrupt_message_bytes(rng: &mut ChaCha8Rng, bytes: &[u8]) -> Vec<u8> {
+    let (valid_header, valid_message) = bytes.split_at(HEADER_LEN);
 
     let mut corrupted_header = corrupt_bytes(rng, valid_header);
     let corrupted_message = corrupt_bytes(rng, valid_message);
@@ -166,9 +357,7 @@ fn corrupt_bytes(rng: &mut ChaCha8Rng, serialized: &[u8]) -> Vec<u8> {
 
 /// Encodes a message and corrupts the body length bytes.
 pub fn encode_message_with_corrupt_body_length(rng: &mut ChaCha8Rng, message: &Message) -> Vec<u8> {
-    let mut bytes = Default::default();
-    message.encode(&mut bytes).unwrap();
-    let mut vec: Vec<_> = bytes.to_vec();
+    let mut vec = message.to_vec().unwrap();
 
     let invalid_body_length = random_non_valid_u32(rng, (vec.len() - HEADER_LEN) as u32);
     (&mut vec[MAGIC_LEN + COMMAND_LEN..][..4]).put_u32_le(invalid_body_length);
@@ -178,9 +367,7 @@ pub fn encode_message_with_corrupt_body_length(rng: &mut ChaCha8Rng, message: &M
 
 /// Encodes a message and corrupts the checksum bytes.
 pub fn encode_message_with_corrupt_checksum(rng: &mut ChaCha8Rng, message: &Message) -> Vec<u8> {
-    let mut bytes = Default::default();
-    message.encode(&mut bytes).unwrap();
-    let mut vec: Vec<_> = bytes.to_vec();
+    let mut vec = message.to_vec().unwrap();
 
     let offset = MAGIC_LEN + COMMAND_LEN + 4; // 4 = sizeof MessageHeader.body_length
     let valid_checksum = u32::from_le_bytes(vec[offset..][..4].try_into().unwrap());
@@ -190,6 +377,63 @@ pub fn encode_message_with_corrupt_checksum(rng: &mut ChaCha8Rng, message: &Mess
     vec
 }
 
+/// A field of the [`MessageHeader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderField {
+    /// The network magic bytes.
+    Magic,
+    /// The message command bytes.
+    Command,
+    /// The body length bytes.
+    BodyLength,
+    /// The body checksum bytes.
+    Checksum,
+}
+
+impl HeaderField {
+    /// All the header fields, in their encoding order.
+    pub const ALL: [HeaderField; 4] = [
+        HeaderField::Magic,
+        HeaderField::Command,
+        HeaderField::BodyLength,
+        HeaderField::Checksum,
+    ];
+
+    /// Returns the byte range of the field within an encoded header.
+    pub fn range(&self) -> Range<usize> {
+        let (offset, len) = match self {
+            Self::Magic => (0, MAGIC_LEN),
+            Self::Command => (MAGIC_LEN, COMMAND_LEN),
+            Self::BodyLength => (MAGIC_LEN + COMMAND_LEN, 4),
+            Self::Checksum => (MAGIC_LEN + COMMAND_LEN + 4, 4),
+        };
+
+        offset..offset + len
+    }
+}
+
+/// Encodes a message and replaces the bytes of the supplied header field with random ones,
+/// leaving the rest of the header and the body valid.
+pub fn corrupt_header_field(
+    rng: &mut ChaCha8Rng,
+    message: &Message,
+    field: HeaderField,
+) -> Vec<u8> {
+    let mut vec = message.to_vec().unwrap();
+
+    let range = field.range();
+    let valid_field = vec[range.clone()].to_vec();
+
+    // Make sure the generated bytes aren't the same.
+    let mut corrupted_field = valid_field.clone();
+    while corrupted_field == valid_field {
+        corrupted_field = rng.sample_iter(Standard).take(range.len()).collect();
+    }
+    vec[range].copy_from_slice(&corrupted_field);
+
+    vec
+}
+
 /// Returns a random u32 which isn't the supplied value.
 fn random_non_valid_u32(rng: &mut ChaCha8Rng, value: u32) -> u32 {
     // Make sure the generated value isn't the same.
@@ -230,3 +474,167 @@ pub fn encode_messages_with_corrupt_checksum(
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    #[ignore]
+    fn zeroes_of_boundary_len() {
+        for len in [0, HEADER_LEN, MAX_MESSAGE_LEN] {
+            let payloads = zeroes_of_len(3, len);
+
+            assert_eq!(payloads.len(), 3);
+            assert!(payloads
+                .iter()
+                .all(|payload| payload.len() == len && payload.iter().all(|&byte| byte == 0)));
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn valid_version_bytes_decode_into_a_version() {
+        let from = SocketAddr::from(([127, 0, 0, 1], 18233));
+        let to = SocketAddr::from(([127, 0, 0, 2], 8233));
+
+        let bytes = valid_version_bytes(from, to);
+
+        let mut header_bytes = &bytes[..HEADER_LEN];
+        let header = MessageHeader::decode(&mut header_bytes).unwrap();
+        assert_eq!(header.command, VERSION_COMMAND);
+        assert_eq!(header.body_length as usize, bytes.len() - HEADER_LEN);
+
+        let mut body = &bytes[HEADER_LEN..];
+        let version = Version::decode(&mut body).unwrap();
+        assert!(body.is_empty());
+        assert_eq!(version.addr_from.addr, from);
+        assert_eq!(version.addr_recv.addr, to);
+    }
+
+    #[test]
+    #[ignore]
+    fn lying_len_inv_declares_more_entries_than_present() {
+        use crate::protocol::payload::{inv::InvHash, Hash};
+
+        let inv = Inv::new(vec![
+            InvHash::Block(Hash::new([1; 32])),
+            InvHash::Block(Hash::new([2; 32])),
+        ]);
+        let bytes = encode_with_lying_len(&Message::Inv(inv), 3).unwrap();
+
+        // The header matches the body, only the count lies.
+        let mut header_bytes = &bytes[..HEADER_LEN];
+        let header = MessageHeader::decode(&mut header_bytes).unwrap();
+        let body = &bytes[HEADER_LEN..];
+        assert_eq!(header.command, INV_COMMAND);
+        assert_eq!(header.body_length as usize, body.len());
+        assert_eq!(
+            header.checksum,
+            MessageHeader::new(INV_COMMAND, body).checksum
+        );
+
+        let mut count_bytes = body;
+        assert_eq!(*VarInt::decode(&mut count_bytes).unwrap(), 3);
+        assert!(Inv::decode(&mut &body[..]).is_err());
+
+        assert!(encode_with_lying_len(&Message::Verack, 1).is_err());
+    }
+
+    #[test]
+    #[ignore]
+    fn random_payload_lengths_respect_max_len() {
+        const MAX_LEN: usize = 16;
+        let mut rng = seeded_rng();
+
+        for payload in random_bytes_up_to(&mut rng, 100, MAX_LEN) {
+            assert!((1..=MAX_LEN).contains(&payload.len()));
+        }
+
+        for message in
+            metadata_compliant_random_bytes_up_to(&mut rng, 100, &COMMANDS_WITH_PAYLOADS, MAX_LEN)
+        {
+            assert!((HEADER_LEN + 1..=HEADER_LEN + MAX_LEN).contains(&message.len()));
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn zero_max_len_gives_empty_payloads() {
+        let mut rng = seeded_rng();
+
+        for payload in random_bytes_up_to(&mut rng, 10, 0) {
+            assert!(payload.is_empty());
+        }
+
+        for message in
+            metadata_compliant_random_bytes_up_to(&mut rng, 10, &COMMANDS_WITH_PAYLOADS, 0)
+        {
+            assert_eq!(message.len(), HEADER_LEN);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn addr_with_body_len_up_to_max_message_len() {
+        let bytes = addr_with_body_len_up_to(MAX_MESSAGE_LEN).to_vec().unwrap();
+
+        let mut header_bytes = &bytes[..HEADER_LEN];
+        let header = MessageHeader::decode(&mut header_bytes).unwrap();
+        let body = &bytes[HEADER_LEN..];
+
+        // The body fills the maximum length, bar less than a single entry.
+        assert!(body.len() <= MAX_MESSAGE_LEN);
+        assert!(body.len() > MAX_MESSAGE_LEN - 30);
+
+        // The header is valid.
+        let expected_header = MessageHeader::new(ADDR_COMMAND, body);
+        assert_eq!(header.command, ADDR_COMMAND);
+        assert_eq!(header.body_length, expected_header.body_length);
+        assert_eq!(header.checksum, expected_header.checksum);
+    }
+
+    #[test]
+    #[ignore]
+    fn addr_generators_fill_max_size_messages() {
+        let mut rng = seeded_rng();
+
+        let addr = random_addr(&mut rng, MAX_ADDRS);
+        assert_eq!(addr.addrs.len(), MAX_ADDRS);
+        let unique = addr.iter().map(|entry| entry.addr).collect::<HashSet<_>>();
+        assert_eq!(unique.len(), MAX_ADDRS);
+
+        let pool = [
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8233),
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 18233),
+        ];
+        let addr = addr_from_pool(&mut rng, MAX_ADDRS, &pool);
+        assert_eq!(addr.addrs.len(), MAX_ADDRS);
+        assert!(addr.iter().all(|entry| pool.contains(&entry.addr)));
+
+        // Either way, the message fits in a frame.
+        assert!(Message::Addr(addr).to_vec().unwrap().len() <= HEADER_LEN + MAX_MESSAGE_LEN);
+    }
+
+    #[test]
+    #[ignore]
+    fn corrupt_header_field_only_changes_the_field() {
+        let mut rng = seeded_rng();
+
+        for message in default_fuzz_messages() {
+            let clean = message.to_vec().unwrap();
+
+            for field in HeaderField::ALL {
+                let corrupted = corrupt_header_field(&mut rng, &message, field);
+                assert_eq!(corrupted.len(), clean.len());
+
+                let range = field.range();
+                assert_ne!(corrupted[range.clone()], clean[range.clone()]);
+                assert_eq!(corrupted[..range.start], clean[..range.start]);
+                assert_eq!(corrupted[range.end..], clean[range.end..]);
+            }
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! Recording and replaying of the payloads generated by fuzz tests.
+//!
+//! A [`CorpusRecorder`] writes the payloads of a failed test to disk, along with the seed they
+//! were generated from and their iteration index, so the failure can be reproduced with
+//! [`replay_corpus`] without re-running the whole seeded sequence.
+
+use std::{
+    fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::tools::{fuzzing::RngSeed, synthetic_node::SyntheticNodeBuilder};
+
+/// The directory corpora are written to, under Ziggurat's configuration directory.
+const CORPUS_ROOT: &str = ".ziggurat/fuzz_corpus";
+/// The name of the file holding the corpus' seed, hex-encoded.
+const SEED_FILE: &str = "seed";
+/// The extension of the payload files, which are named after their iteration index.
+const PAYLOAD_EXTENSION: &str = "bin";
+
+/// Returns the default directory corpora are written to, `~/.ziggurat/fuzz_corpus`.
+pub fn default_corpus_root() -> io::Result<PathBuf> {
+    home::home_dir()
+        .map(|home| home.join(CORPUS_ROOT))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "couldn't find home directory"))
+}
+
+/// The payloads generated by a fuzz test, along with the seed they were generated from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Corpus {
+    pub seed: RngSeed,
+    /// The payloads and their iteration index, in increasing index order.
+    pub payloads: Vec<(usize, Vec<u8>)>,
+}
+
+impl Corpus {
+    /// Creates an empty corpus for the given seed.
+    pub fn new(seed: RngSeed) -> Self {
+        Self {
+            seed,
+            payloads: Vec::new(),
+        }
+    }
+
+    /// Appends the payload, as the next iteration.
+    pub fn push(&mut self, payload: Vec<u8>) {
+        let iteration = self.payloads.len();
+        self.payloads.push((iteration, payload));
+    }
+
+    /// Writes the corpus to `dir`, one file per payload, replacing any previous corpus.
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        fs::create_dir_all(dir)?;
+
+        fs::write(dir.join(SEED_FILE), hex::encode(self.seed))?;
+        for (iteration, payload) in &self.payloads {
+            fs::write(
+                dir.join(format!("{iteration:05}.{PAYLOAD_EXTENSION}")),
+                payload,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the corpus written to `dir` by [`Corpus::save`].
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let seed = hex::decode(fs::read_to_string(dir.join(SEED_FILE))?.trim())
+            .ok()
+            .and_then(|seed| RngSeed::try_from(seed).ok())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid seed in {}", dir.display()),
+                )
+            })?;
+
+        let mut payloads = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(PAYLOAD_EXTENSION) {
+                continue;
+            }
+
+            let iteration = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unexpected payload file {}", path.display()),
+                    )
+                })?;
+            payloads.push((iteration, fs::read(&path)?));
+        }
+        payloads.sort_by_key(|(iteration, _)| *iteration);
+
+        Ok(Self { seed, payloads })
+    }
+}
+
+/// Records the payloads generated by a fuzz test, and writes them to disk if the test fails.
+///
+/// The corpus is written when the recorder is dropped while panicking, i.e. when one of the
+/// test's assertions fails, to `<root>/<test name>-<hex seed>`.
+pub struct CorpusRecorder {
+    test_name: String,
+    root: Option<PathBuf>,
+    corpus: Corpus,
+}
+
+impl CorpusRecorder {
+    /// Creates a recorder for the payloads generated from `seed` by the named test.
+    pub fn new(test_name: &str, seed: RngSeed) -> Self {
+        Self {
+            test_name: test_name.to_owned(),
+            root: None,
+            corpus: Corpus::new(seed),
+        }
+    }
+
+    /// Sets the directory the corpus is written to, [`default_corpus_root`] by default.
+    pub fn with_root(mut self, root: PathBuf) -> Self {
+        self.root = Some(root);
+        self
+    }
+
+    /// Records the payloads, as the next iterations.
+    pub fn with_payloads(mut self, payloads: &[Vec<u8>]) -> Self {
+        self.record(payloads);
+        self
+    }
+
+    /// Records the payloads, as the next iterations.
+    pub fn record(&mut self, payloads: &[Vec<u8>]) {
+        for payload in payloads {
+            self.corpus.push(payload.clone());
+        }
+    }
+
+    /// Returns the directory the corpus is written to.
+    pub fn corpus_dir(&self) -> io::Result<PathBuf> {
+        let root = match &self.root {
+            Some(root) => root.clone(),
+            None => default_corpus_root()?,
+        };
+
+        Ok(root.join(format!(
+            "{}-{}",
+            self.test_name,
+            hex::encode(self.corpus.seed)
+        )))
+    }
+
+    fn save(&self) -> io::Result<PathBuf> {
+        let dir = self.corpus_dir()?;
+        self.corpus.save(&dir)?;
+
+        Ok(dir)
+    }
+}
+
+impl Drop for CorpusRecorder {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            return;
+        }
+
+        match self.save() {
+            Ok(dir) => println!("Fuzz corpus written to {}", dir.display()),
+            Err(e) => println!("Couldn't write the fuzz corpus: {e}"),
+        }
+    }
+}
+
+/// The node's reaction to a replayed payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayOutcome {
+    pub iteration: usize,
+    /// Whether the node terminated the connection within the timeout.
+    pub disconnected: bool,
+}
+
+/// Re-sends the corpus' payloads to the node at `node_addr`, each over a new connection of a
+/// synthetic node built from `builder`, and returns whether the node disconnected after each one.
+///
+/// The builder sets the connection stage the payloads are sent at, e.g. a builder without a
+/// handshake replays payloads sent in place of the `Version`.
+pub async fn replay_corpus(
+    corpus: &Corpus,
+    node_addr: SocketAddr,
+    builder: &SyntheticNodeBuilder,
+    timeout: Duration,
+) -> io::Result<Vec<ReplayOutcome>> {
+    let mut outcomes = Vec::with_capacity(corpus.payloads.len());
+
+    for (iteration, payload) in &corpus.payloads {
+        let mut synthetic_node = builder.build().await?;
+        synthetic_node.connect(node_addr).await?;
+        synthetic_node.send_direct_bytes(node_addr, payload.clone())?;
+
+        let disconnected = synthetic_node
+            .wait_for_disconnect(node_addr, timeout)
+            .await
+            .is_ok();
+        synthetic_node.shut_down().await;
+
+        outcomes.push(ReplayOutcome {
+            iteration: *iteration,
+            disconnected,
+        });
+    }
+
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::fuzzing::{random_bytes, rng_from_seed, seeded_rng_with_seed};
+
+    fn test_root(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ziggurat-corpus-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    #[ignore]
+    fn corpus_round_trip() {
+        let (mut rng, seed) = seeded_rng_with_seed();
+        let mut corpus = Corpus::new(seed);
+        for payload in random_bytes(&mut rng, 12) {
+            corpus.push(payload);
+        }
+
+        let dir = test_root("round-trip");
+        corpus.save(&dir).unwrap();
+        let loaded = Corpus::load(&dir);
+        let _ = fs::remove_dir_all(&dir);
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded, corpus);
+        // The seed regenerates the recorded payloads.
+        let regenerated = random_bytes(&mut rng_from_seed(loaded.seed), 12);
+        assert!(loaded
+            .payloads
+            .iter()
+            .map(|(_, payload)| payload)
+            .eq(regenerated.iter()));
+    }
+
+    #[test]
+    #[ignore]
+    fn corpus_is_only_written_on_failure() {
+        let root = test_root("recorder");
+        let payloads = vec![vec![1, 2, 3], vec![4]];
+
+        let recorder = CorpusRecorder::new("passing", [1; 32])
+            .with_root(root.clone())
+            .with_payloads(&payloads);
+        let passing_dir = recorder.corpus_dir().unwrap();
+        drop(recorder);
+
+        let failing_dir = std::panic::catch_unwind(|| {
+            let recorder = CorpusRecorder::new("failing", [2; 32])
+                .with_root(root.clone())
+                .with_payloads(&payloads);
+            let dir = recorder.corpus_dir().unwrap();
+            panic!("{}", dir.display());
+        })
+        .unwrap_err()
+        .downcast::<String>()
+        .map(|dir| PathBuf::from(*dir))
+        .unwrap();
+
+        let failing = Corpus::load(&failing_dir);
+        let _ = fs::remove_dir_all(&root);
+
+        assert!(!passing_dir.exists());
+        assert_eq!(
+            failing.unwrap().payloads,
+            vec![(0, vec![1, 2, 3]), (1, vec![4])]
+        );
+    }
+}
@@ -2,6 +2,7 @@
 
 pub mod fuzzing;
 pub mod message_filter;
+pub mod scenario;
 pub mod synthetic_node;
 
 use std::time::Duration;
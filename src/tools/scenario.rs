@@ -0,0 +1,147 @@
+//! Scripted message exchanges with a peer.
+
+use std::{
+    io::{self, Error, ErrorKind},
+    net::SocketAddr,
+    time::Duration,
+};
+
+use crate::{
+    protocol::message::Message,
+    tools::{synthetic_node::SyntheticNode, RECV_TIMEOUT},
+};
+
+/// A single step of a [`Scenario`].
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// Sends the message to the target.
+    Send(Message),
+    /// Reads the next message from the target and checks it matches the predicate.
+    Expect(fn(&Message) -> bool),
+    /// Waits for the given duration before moving on to the next step.
+    Sleep(Duration),
+}
+
+/// A message exchanged while running a [`Scenario`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A message sent to the target.
+    Sent(Message),
+    /// A message received from the target.
+    Received(Message),
+}
+
+/// An ordered list of [`Step`]s to run against a target.
+///
+/// Useful for reproducing multi-message interactions, e.g. a manual handshake followed by a
+/// [`Ping`](Message::Ping), and inspecting the resulting transcript.
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    steps: Vec<Step>,
+    expect_timeout: Duration,
+}
+
+impl Scenario {
+    /// Creates a scenario from the given steps, with the default [`RECV_TIMEOUT`] for each
+    /// [`Step::Expect`].
+    pub fn new(steps: Vec<Step>) -> Self {
+        Self {
+            steps,
+            expect_timeout: RECV_TIMEOUT,
+        }
+    }
+
+    /// Sets the time allowed for each [`Step::Expect`] to be met.
+    pub fn with_expect_timeout(mut self, timeout: Duration) -> Self {
+        self.expect_timeout = timeout;
+        self
+    }
+
+    /// Runs the steps in order against `target`, messages from other peers are skipped.
+    ///
+    /// Returns the transcript of the exchanged messages, or an
+    /// [`InvalidData`](ErrorKind::InvalidData) error if an expectation isn't met and a
+    /// [`TimedOut`](ErrorKind::TimedOut) error if no message arrives in time.
+    pub async fn run(
+        &self,
+        synthetic_node: &mut SyntheticNode,
+        target: SocketAddr,
+    ) -> io::Result<Vec<Event>> {
+        let mut transcript = Vec::with_capacity(self.steps.len());
+
+        for (i, step) in self.steps.iter().enumerate() {
+            match step {
+                Step::Send(message) => {
+                    synthetic_node.unicast(target, message.clone())?;
+                    transcript.push(Event::Sent(message.clone()));
+                }
+                Step::Expect(predicate) => {
+                    let now = std::time::Instant::now();
+                    let message = loop {
+                        let remaining = self.expect_timeout.saturating_sub(now.elapsed());
+                        let (source, message) =
+                            synthetic_node.recv_message_timeout(remaining).await?;
+                        if source == target {
+                            break message;
+                        }
+                    };
+
+                    if !predicate(&message) {
+                        return Err(Error::new(
+                            ErrorKind::InvalidData,
+                            format!("unexpected message at step {i} of the scenario: {message:?}"),
+                        ));
+                    }
+
+                    transcript.push(Event::Received(message));
+                }
+                Step::Sleep(duration) => tokio::time::sleep(*duration).await,
+            }
+        }
+
+        Ok(transcript)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::payload::{Nonce, Version};
+
+    #[tokio::test]
+    #[ignore]
+    async fn handshake_and_ping_scenario() {
+        let responder = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_all_auto_reply()
+            .build()
+            .await
+            .unwrap();
+        let responder_addr = responder.listening_addr().unwrap();
+
+        // The initiator doesn't handshake, the scenario does it instead.
+        let mut initiator = SyntheticNode::builder().build().await.unwrap();
+        initiator.connect(responder_addr).await.unwrap();
+
+        let version = Version::new(responder_addr, initiator.listening_addr().unwrap());
+        let nonce = Nonce::default();
+        let transcript = Scenario::new(vec![
+            Step::Send(Message::Version(version)),
+            Step::Expect(|m| matches!(m, Message::Version(..))),
+            Step::Send(Message::Verack),
+            Step::Expect(|m| matches!(m, Message::Verack)),
+            Step::Send(Message::Ping(nonce)),
+            Step::Expect(|m| matches!(m, Message::Pong(..))),
+        ])
+        .run(&mut initiator, responder_addr)
+        .await
+        .unwrap();
+
+        assert_eq!(transcript.len(), 6);
+        assert_eq!(transcript[4], Event::Sent(Message::Ping(nonce)));
+        assert_eq!(transcript[5], Event::Received(Message::Pong(nonce)));
+
+        initiator.shut_down().await;
+        responder.shut_down().await;
+    }
+}
@@ -1,14 +1,16 @@
 //! A lightweight node implementation to be used as peers in tests.
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     io::{self, Error, ErrorKind},
     net::{IpAddr, Ipv4Addr, SocketAddr},
-    sync::Arc,
-    time::Duration,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
-use assert_matches::assert_matches;
 use bytes::{BufMut, BytesMut};
 use futures_util::{sink::SinkExt, TryStreamExt};
 use parking_lot::Mutex;
@@ -16,17 +18,36 @@ use pea2pea::{
     protocols::{Disconnect, Handshake, Reading, Writing},
     Config as NodeConfig, Connection, ConnectionInfo, ConnectionSide, Node, Pea2Pea,
 };
+use tabled::{Table, Tabled};
+use time::OffsetDateTime;
 use tokio::{
-    sync::mpsc::{self, Receiver, Sender},
-    time::timeout,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        watch,
+    },
+    task::JoinHandle,
+    time::{interval, sleep, timeout},
 };
 use tokio_util::codec::{Decoder, Encoder, Framed, LengthDelimitedCodec};
 use tracing::*;
+use ziggurat_core_metrics::tables::fmt_table;
 
 use crate::{
     protocol::{
-        message::{Message, MessageHeader},
-        payload::{codec::Codec, Nonce, Version},
+        message::{
+            constants::{
+                Network, COMMAND_LEN, DEFAULT_NETWORK, MAGIC_LEN, MAX_MESSAGE_LEN,
+                MAX_USER_AGENT_LEN, VERSION_COMMAND,
+            },
+            Message, MessageHeader,
+        },
+        payload::{
+            block::Block,
+            codec::Codec,
+            inv::{InvByKind, InvHash},
+            version::ServiceFlags,
+            Inv, Nonce, ProtocolVersion, Version,
+        },
     },
     tools::message_filter::{Filter, MessageFilter},
 };
@@ -122,6 +143,9 @@ pub fn enable_tracing() {
         .init();
 }
 
+/// The handshake timeout, excluding any configured handshake step delays.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(3);
+
 /// Describes the handshake to be performed by a [`SyntheticNode`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HandshakeKind {
@@ -136,12 +160,106 @@ pub enum HandshakeKind {
     VersionOnly,
 }
 
+/// A callback invoked with every message received by a [`SyntheticNode`] and its source.
+pub type MessageHook = Arc<dyn Fn(SocketAddr, &Message) + Send + Sync>;
+
+/// Wraps a [`MessageHook`], so that the configuration types can still derive `Debug`.
+#[derive(Clone)]
+struct MessageHookWrapper(MessageHook);
+
+impl std::fmt::Debug for MessageHookWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MessageHook")
+    }
+}
+
+/// Overrides for the fields of the [`Version`] sent during the handshake.
+#[derive(Debug, Clone, Default)]
+struct VersionOverrides {
+    template: Option<Version>,
+    user_agent: Option<String>,
+    timestamp: Option<OffsetDateTime>,
+    services: Option<ServiceFlags>,
+    addr_recv: Option<SocketAddr>,
+    protocol_version: Option<ProtocolVersion>,
+    relay: Option<bool>,
+    start_height: Option<i32>,
+}
+
+impl VersionOverrides {
+    /// Applies the overrides to the supplied [`Version`].
+    ///
+    /// The template, if any, replaces every field but the addresses, then the individual
+    /// overrides are applied on top of it.
+    fn apply(&self, mut version: Version) -> Version {
+        if let Some(template) = &self.template {
+            let (addr_recv, addr_from) = (version.addr_recv.addr, version.addr_from.addr);
+            version = template.clone();
+            version.addr_recv.addr = addr_recv;
+            version.addr_from.addr = addr_from;
+        }
+        if let Some(user_agent) = &self.user_agent {
+            version = version.with_user_agent(user_agent.clone());
+        }
+        if let Some(timestamp) = self.timestamp {
+            version.timestamp = timestamp;
+        }
+        if let Some(services) = self.services {
+            version = version.with_services(services);
+        }
+        if let Some(addr_recv) = self.addr_recv {
+            version.addr_recv.addr = addr_recv;
+        }
+        if let Some(protocol_version) = self.protocol_version {
+            version = version.with_version(protocol_version.0);
+        }
+        if let Some(relay) = self.relay {
+            version.relay = relay;
+        }
+        if let Some(start_height) = self.start_height {
+            version = version.with_start_height(start_height);
+        }
+
+        version
+    }
+
+    /// Returns the user agent which will be advertised, if it isn't the default one.
+    fn user_agent(&self) -> Option<&str> {
+        self.user_agent.as_deref().or_else(|| {
+            self.template
+                .as_ref()
+                .map(|version| &version.user_agent.0[..])
+        })
+    }
+}
+
+/// A row of [`SyntheticNode::command_frequency_table`].
+#[derive(Tabled)]
+struct CommandFrequency {
+    command: String,
+    count: u64,
+}
+
 /// A builder for [`SyntheticNode`].
 #[derive(Debug, Clone)]
 pub struct SyntheticNodeBuilder {
     network_config: NodeConfig,
+    network: Network,
     handshake: Option<HandshakeKind>,
     message_filter: MessageFilter,
+    peer_message_filters: HashMap<SocketAddr, MessageFilter>,
+    strict_handshake_panic: bool,
+    auto_getdata: bool,
+    wrong_pong_nonce: bool,
+    served_blocks: Arc<Vec<(InvHash, Block)>>,
+    version_overrides: VersionOverrides,
+    linger: Option<Duration>,
+    handshake_step_delay: Option<Duration>,
+    message_hook: Option<MessageHookWrapper>,
+    message_history: Option<usize>,
+    command_frequencies: bool,
+    idle_timeout: Option<Duration>,
+    listening: bool,
 }
 
 impl Default for SyntheticNodeBuilder {
@@ -152,8 +270,22 @@ impl Default for SyntheticNodeBuilder {
                 listener_ip: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
                 ..Default::default()
             },
+            network: DEFAULT_NETWORK,
             handshake: None,
             message_filter: MessageFilter::with_all_disabled(),
+            peer_message_filters: Default::default(),
+            strict_handshake_panic: false,
+            auto_getdata: false,
+            wrong_pong_nonce: false,
+            served_blocks: Default::default(),
+            version_overrides: Default::default(),
+            linger: None,
+            handshake_step_delay: None,
+            message_hook: None,
+            message_history: None,
+            command_frequencies: false,
+            idle_timeout: None,
+            listening: true,
         }
     }
 }
@@ -161,35 +293,56 @@ impl Default for SyntheticNodeBuilder {
 impl SyntheticNodeBuilder {
     /// Creates a [`SyntheticNode`] with the current configuration.
     pub async fn build(&self) -> io::Result<SyntheticNode> {
+        if let Some(user_agent) = self.version_overrides.user_agent() {
+            if user_agent.len() > MAX_USER_AGENT_LEN {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "user agent is {} bytes long, the maximum is {MAX_USER_AGENT_LEN}",
+                        user_agent.len()
+                    ),
+                ));
+            }
+        }
+
         // Create the pea2pea node from the config.
         let node = Node::new(self.network_config.clone());
 
         // Inbound channel size of 100 messages.
         let (tx, rx) = mpsc::channel(100);
-        let inner_node =
-            InnerNode::new(node, tx, self.message_filter.clone(), self.handshake).await;
+        let inner_node = InnerNode::new(node, tx, self).await;
 
         // Enable the read and write protocols
         inner_node.enable_reading().await;
         inner_node.enable_writing().await;
         inner_node.enable_disconnect().await;
 
-        // Always start listening as inner node expects `listening_addr` to be always available.
-        inner_node.node().start_listening().await?;
+        if self.listening {
+            inner_node.node().start_listening().await?;
+        }
+
+        let idle_task = self.idle_timeout.map(|idle_timeout| {
+            tokio::spawn(inner_node.clone().disconnect_idle_peers(idle_timeout))
+        });
 
         Ok(SyntheticNode {
             inner_node,
             inbound_rx: rx,
+            idle_task,
         })
     }
 
     /// Creates `n` [`SyntheticNode`]'s with the current configuration, and also returns their listening address.
+    ///
+    /// The addresses are omitted if the nodes aren't listening, see [`with_listening`](Self::with_listening).
     pub async fn build_n(&self, n: usize) -> io::Result<(Vec<SyntheticNode>, Vec<SocketAddr>)> {
         let mut nodes = Vec::with_capacity(n);
         let mut addrs = Vec::with_capacity(n);
         for _ in 0..n {
             let node = self.build().await?;
-            addrs.push(node.listening_addr());
+            if let Ok(addr) = node.listening_addr() {
+                addrs.push(addr);
+            }
             nodes.push(node);
         }
 
@@ -214,23 +367,214 @@ impl SyntheticNodeBuilder {
         self
     }
 
+    /// Sets whether the handshake panics on unexpected messages instead of returning an error.
+    ///
+    /// Defaults to `false`, in which case the connection is dropped with an
+    /// [`InvalidData`](ErrorKind::InvalidData) error.
+    pub fn with_strict_handshake_panic(mut self, strict: bool) -> Self {
+        self.strict_handshake_panic = strict;
+        self
+    }
+
+    /// Enables replying to every received [`Inv`] with a [`GetData`] requesting all its entries,
+    /// grouped by kind: blocks, filtered blocks, transactions, then witnessed transactions, each
+    /// in the announced order. Entries of the ignorable `Error` kind aren't requested.
+    ///
+    /// The [`Inv`] is still processed by the [`MessageFilter`] as usual.
+    ///
+    /// [`Inv`]: enum@crate::protocol::message::Message::Inv
+    /// [`GetData`]: enum@crate::protocol::message::Message::GetData
+    pub fn with_auto_getdata(mut self) -> Self {
+        self.auto_getdata = true;
+        self
+    }
+
+    /// Makes the auto-replies to [`Ping`] carry a random [`Nonce`] instead of the [`Ping`]'s
+    /// one, so the [`Pong`] (almost) never matches.
+    ///
+    /// Only takes effect if [`Ping`] is set to [`Filter::AutoReply`] in the [`MessageFilter`].
+    ///
+    /// [`Ping`]: enum@crate::protocol::message::Message::Ping
+    /// [`Pong`]: enum@crate::protocol::message::Message::Pong
+    pub fn with_wrong_pong_nonce(mut self) -> Self {
+        self.wrong_pong_nonce = true;
+        self
+    }
+
+    /// Serves the supplied blocks in response to [`GetData`] requests, e.g. after advertising them
+    /// with an [`Inv`].
+    ///
+    /// A [`GetData`] requesting any of the blocks is answered with those blocks, followed by a
+    /// [`NotFound`] for the rest of its entries, and bypasses the [`MessageFilter`]. Other
+    /// [`GetData`] requests are processed by the [`MessageFilter`] as usual.
+    ///
+    /// [`Inv`]: enum@crate::protocol::message::Message::Inv
+    /// [`GetData`]: enum@crate::protocol::message::Message::GetData
+    /// [`NotFound`]: enum@crate::protocol::message::Message::NotFound
+    pub fn with_served_blocks(mut self, blocks: Vec<Block>) -> Self {
+        self.served_blocks = Arc::new(
+            blocks
+                .into_iter()
+                .map(|block| (block.inv_hash(), block))
+                .collect(),
+        );
+        self
+    }
+
+    /// Sets the user agent advertised in the node's [`Version`] messages.
+    ///
+    /// [`build`](Self::build) fails with [`InvalidInput`](ErrorKind::InvalidInput) if the agent is
+    /// longer than [`MAX_USER_AGENT_LEN`] bytes.
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.version_overrides.user_agent = Some(user_agent);
+        self
+    }
+
+    /// Sets the [`Version`] the node's handshake messages are based on, instead of
+    /// [`Version::new`].
+    ///
+    /// Every field of the template is advertised as is, apart from the addresses, which are
+    /// those of the connection (or [`with_addr_recv`](Self::with_addr_recv)). The other
+    /// `Version` options of the builder take precedence over the template.
+    ///
+    /// [`build`](Self::build) fails with [`InvalidInput`](ErrorKind::InvalidInput) if the
+    /// template's agent is longer than [`MAX_USER_AGENT_LEN`] bytes.
+    pub fn with_version_template(mut self, template: Version) -> Self {
+        self.version_overrides.template = Some(template);
+        self
+    }
+
+    /// Sets the timestamp advertised in the node's [`Version`] messages, instead of the current
+    /// time.
+    pub fn with_version_timestamp(mut self, timestamp: OffsetDateTime) -> Self {
+        self.version_overrides.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Sets the services advertised in the node's [`Version`] messages.
+    pub fn with_services(mut self, services: ServiceFlags) -> Self {
+        self.version_overrides.services = Some(services);
+        self
+    }
+
+    /// Sets the protocol version advertised in the node's [`Version`] messages, instead of the
+    /// current one.
+    pub fn with_protocol_version(mut self, protocol_version: ProtocolVersion) -> Self {
+        self.version_overrides.protocol_version = Some(protocol_version);
+        self
+    }
+
+    /// Sets the `relay` flag of the node's [`Version`] messages, which asks the peer to announce
+    /// its transactions. Not set by default.
+    pub fn with_relay(mut self, relay: bool) -> Self {
+        self.version_overrides.relay = Some(relay);
+        self
+    }
+
+    /// Sets the start height advertised in the node's [`Version`] messages, `0` by default.
+    pub fn with_start_height(mut self, start_height: i32) -> Self {
+        self.version_overrides.start_height = Some(start_height);
+        self
+    }
+
+    /// Sets the `addr_recv` advertised in the node's [`Version`] messages, instead of the actual
+    /// address of the peer.
+    pub fn with_addr_recv(mut self, addr_recv: SocketAddr) -> Self {
+        self.version_overrides.addr_recv = Some(addr_recv);
+        self
+    }
+
+    /// Sets whether the node listens for inbound connections, which it does by default.
+    ///
+    /// A node which doesn't listen doesn't bind a port, which is useful for peers only initiating
+    /// connections.
+    pub fn with_listening(mut self, listening: bool) -> Self {
+        self.listening = listening;
+        self
+    }
+
+    /// Sets the `SO_LINGER` option on every connection's socket once it is established.
+    ///
+    /// A zero duration makes the socket reset the connection (`RST`) when it is closed, instead of
+    /// performing the regular `FIN` shutdown sequence.
+    pub fn with_linger(mut self, linger: Duration) -> Self {
+        self.linger = Some(linger);
+        self
+    }
+
+    /// Sets a delay applied before sending each handshake message.
+    ///
+    /// The handshake timeout is extended by the total delay, so only the peer can time out.
+    pub fn with_handshake_step_delay(mut self, delay: Duration) -> Self {
+        self.handshake_step_delay = Some(delay);
+        self
+    }
+
+    /// Sets a hook invoked with every received message and its source, before it is processed by
+    /// the [`MessageFilter`].
+    ///
+    /// The hook runs on the node's reading task, so it should return quickly.
+    pub fn with_message_hook(mut self, hook: MessageHook) -> Self {
+        self.message_hook = Some(MessageHookWrapper(hook));
+        self
+    }
+
+    /// Keeps a history of the last `len` messages received from each peer, including the ones
+    /// handled by the [`MessageFilter`], see [`SyntheticNode::recent_messages`].
+    pub fn with_message_history(mut self, len: usize) -> Self {
+        self.message_history = Some(len);
+        self
+    }
+
+    /// Counts the messages received from all peers by command, including the ones handled by the
+    /// [`MessageFilter`], see [`SyntheticNode::command_frequencies`].
+    pub fn with_command_frequencies(mut self) -> Self {
+        self.command_frequencies = true;
+        self
+    }
+
+    /// Disconnects peers which haven't sent anything for longer than `idle_timeout`, checked by
+    /// a background task. By default, idle peers are kept.
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
     /// Sets the node's [`MessageFilter`].
     pub fn with_message_filter(mut self, filter: MessageFilter) -> Self {
         self.message_filter = filter;
         self
     }
 
+    /// Sets the [`MessageFilter`] applied to the messages from `addr`, overriding the node's
+    /// filter for that peer.
+    ///
+    /// For peers connecting to the node, `addr` is the address of the connection, not the
+    /// peer's listening address.
+    pub fn with_peer_message_filter(mut self, addr: SocketAddr, filter: MessageFilter) -> Self {
+        self.peer_message_filters.insert(addr, filter);
+        self
+    }
+
     /// Sets the node's [`NodeConfig`].
     pub fn with_network_config(mut self, config: NodeConfig) -> Self {
         self.network_config = config;
         self
     }
+
+    /// Sets the [`Network`] whose magic is used in the headers of the sent messages, instead of
+    /// the [`DEFAULT_NETWORK`].
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
 }
 
 /// Convenient abstraction over a `pea2pea` node.
 pub struct SyntheticNode {
     inner_node: InnerNode,
     inbound_rx: Receiver<(SocketAddr, Message)>,
+    idle_task: Option<JoinHandle<()>>,
 }
 
 impl SyntheticNode {
@@ -244,9 +588,18 @@ impl SyntheticNode {
         self.inner_node.handshake_info(addr)
     }
 
+    /// Returns the protocol version the peer advertised in its handshake [`Version`].
+    pub fn peer_version(&self, addr: &SocketAddr) -> Option<ProtocolVersion> {
+        self.inner_node
+            .handshake_info(addr)
+            .map(|version| version.version)
+    }
+
     /// Returns the listening address of the node.
-    pub fn listening_addr(&self) -> SocketAddr {
-        self.inner_node.node().listening_addr().unwrap()
+    ///
+    /// Fails if the node isn't listening, see [`SyntheticNodeBuilder::with_listening`].
+    pub fn listening_addr(&self) -> io::Result<SocketAddr> {
+        self.inner_node.node().listening_addr()
     }
 
     /// Connects to the target address.
@@ -265,6 +618,29 @@ impl SyntheticNode {
         self.inner_node.node().disconnect(target).await
     }
 
+    /// Pauses the processing of messages received from `addr`.
+    ///
+    /// Messages which are already being processed are unaffected. Subsequent messages (including
+    /// those which would be auto-replied to) are held back in order until
+    /// [`resume_reading`](Self::resume_reading) is called. Once the connection's internal queue is
+    /// full, the node stops reading from the socket, building up the peer's send buffer.
+    pub fn pause_reading(&self, addr: SocketAddr) {
+        self.inner_node
+            .read_gates
+            .lock()
+            .entry(addr)
+            .or_insert_with(|| watch::channel(true).0);
+    }
+
+    /// Resumes the processing of messages received from `addr`, see
+    /// [`pause_reading`](Self::pause_reading).
+    pub fn resume_reading(&self, addr: SocketAddr) {
+        if let Some(gate) = self.inner_node.read_gates.lock().remove(&addr) {
+            // An error only means no messages are currently held back.
+            let _ = gate.send(false);
+        }
+    }
+
     /// Indicates if the `addr` is registered as a connected peer.
     pub fn is_connected(&self, addr: SocketAddr) -> bool {
         self.inner_node.node().is_connected(addr)
@@ -285,6 +661,69 @@ impl SyntheticNode {
         self.inner_node.node().connection_infos()
     }
 
+    /// Returns the info of the active connection with the given address, if any.
+    pub fn peer_info(&self, addr: SocketAddr) -> Option<ConnectionInfo> {
+        self.connected_peer_infos().remove(&addr)
+    }
+
+    /// Returns the total number of messages received from all peers, including the ones handled
+    /// by the [`MessageFilter`].
+    pub fn total_messages_received(&self) -> u64 {
+        self.inner_node.messages_received.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of messages received from all peers, keyed by command.
+    ///
+    /// The messages are only counted if enabled with
+    /// [`SyntheticNodeBuilder::with_command_frequencies`], handshake messages aren't counted.
+    pub fn command_frequencies(&self) -> HashMap<String, u64> {
+        self.inner_node.command_frequencies.lock().clone()
+    }
+
+    /// Renders the [`command_frequencies`](Self::command_frequencies) as a table, most frequent
+    /// commands first.
+    pub fn command_frequency_table(&self) -> String {
+        let mut frequencies = self
+            .command_frequencies()
+            .into_iter()
+            .map(|(command, count)| CommandFrequency { command, count })
+            .collect::<Vec<_>>();
+        frequencies.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.command.cmp(&b.command))
+        });
+
+        fmt_table(Table::new(frequencies))
+    }
+
+    /// Returns up to the last `n` messages received from `addr`, oldest first.
+    ///
+    /// The history is only kept if enabled with [`SyntheticNodeBuilder::with_message_history`],
+    /// and is bounded by the length set there. A peer's history is dropped once it disconnects.
+    pub fn recent_messages(&self, addr: SocketAddr, n: usize) -> Vec<Message> {
+        self.inner_node
+            .recent_messages
+            .lock()
+            .get(&addr)
+            .map(|history| {
+                history
+                    .iter()
+                    .skip(history.len().saturating_sub(n))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the raw bytes (header included) of the last `Version` received from `addr`.
+    ///
+    /// Only messages read after the handshake are captured, so this is meant for nodes without
+    /// a handshake, which receive the peer's `Version` like any other message.
+    pub fn version_bytes(&self, addr: SocketAddr) -> Option<Vec<u8>> {
+        self.inner_node.version_frames.lock().get(&addr).cloned()
+    }
+
     /// Waits until the node has at least one connection, and returns its SocketAddr.
     pub async fn wait_for_connection(&self) -> SocketAddr {
         const SLEEP: Duration = Duration::from_millis(10);
@@ -306,6 +745,60 @@ impl SyntheticNode {
         Ok(())
     }
 
+    /// Sets the [`MessageFilter`] applied to the messages from `addr`, overriding the node's
+    /// filter for that peer, see [`SyntheticNodeBuilder::with_peer_message_filter`].
+    pub fn set_peer_message_filter(&self, addr: SocketAddr, filter: MessageFilter) {
+        self.inner_node
+            .peer_message_filters
+            .lock()
+            .insert(addr, filter);
+    }
+
+    /// Removes the [`MessageFilter`] override of `addr`, the node's filter applies to its
+    /// messages again.
+    pub fn clear_peer_message_filter(&self, addr: SocketAddr) {
+        self.inner_node.peer_message_filters.lock().remove(&addr);
+    }
+
+    /// Sends a message to every connected peer.
+    ///
+    /// Peers which disconnect while the message is being sent are skipped, only failures to send
+    /// to peers which are still connected are errors.
+    pub fn broadcast(&self, message: Message) -> io::Result<()> {
+        let failed = self
+            .inner_node
+            .send_to_each(&self.connected_peers(), &message)
+            .into_iter()
+            .filter(|addr| self.is_connected(*addr))
+            .collect::<Vec<_>>();
+
+        if !failed.is_empty() {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("couldn't broadcast {message} to {failed:?}"),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Sends a message to each of the target addresses.
+    ///
+    /// The message is sent to every reachable target, the ones it couldn't be sent to, e.g.
+    /// because they aren't connected, are listed in the error.
+    pub fn send_to_subset(&self, addrs: &[SocketAddr], message: Message) -> io::Result<()> {
+        let failed = self.inner_node.send_to_each(addrs, &message);
+
+        if !failed.is_empty() {
+            return Err(Error::new(
+                ErrorKind::NotConnected,
+                format!("couldn't send {message} to {failed:?}"),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Sends bytes directly to the target address.
     pub fn send_direct_bytes(&self, target: SocketAddr, data: Vec<u8>) -> io::Result<()> {
         self.inner_node
@@ -355,6 +848,43 @@ impl SyntheticNode {
         }
     }
 
+    /// Reads messages from `target` and checks each matches the corresponding predicate in
+    /// `expected`, in order. Messages from other peers are skipped.
+    ///
+    /// Returns the matching messages, or an [`InvalidData`](ErrorKind::InvalidData) error on the
+    /// first mismatch and a [`TimedOut`](ErrorKind::TimedOut) error if the whole sequence didn't
+    /// arrive within `duration`.
+    pub async fn expect_sequence(
+        &mut self,
+        target: SocketAddr,
+        expected: &[fn(&Message) -> bool],
+        duration: Duration,
+    ) -> io::Result<Vec<Message>> {
+        let now = Instant::now();
+        let mut messages = Vec::with_capacity(expected.len());
+
+        for (i, predicate) in expected.iter().enumerate() {
+            let message = loop {
+                let remaining = duration.saturating_sub(now.elapsed());
+                let (source, message) = self.recv_message_timeout(remaining).await?;
+                if source == target {
+                    break message;
+                }
+            };
+
+            if !predicate(&message) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("unexpected message at position {i} of the sequence: {message:?}"),
+                ));
+            }
+
+            messages.push(message);
+        }
+
+        Ok(messages)
+    }
+
     /// Sends [`Ping`], and expects [`Pong`] with a matching [`Nonce`] in reply.
     ///
     /// Uses polling to check that connection is still alive. Returns a [`PingPongError`] if:
@@ -377,9 +907,22 @@ impl SyntheticNode {
         target: SocketAddr,
         duration: Duration,
     ) -> Result<(), PingPongError> {
+        self.ping_pong_measured(target, duration).await.map(|_| ())
+    }
+
+    /// Same as [`ping_pong_timeout`](SyntheticNode::ping_pong_timeout), but returns the time elapsed
+    /// between sending the [`Ping`] and receiving the matching [`Pong`].
+    ///
+    /// [`Ping`]: enum@crate::protocol::message::Message::Ping
+    /// [`Pong`]: enum@crate::protocol::message::Message::Pong
+    pub async fn ping_pong_measured(
+        &mut self,
+        target: SocketAddr,
+        duration: Duration,
+    ) -> Result<Duration, PingPongError> {
         const SLEEP: Duration = Duration::from_millis(10);
 
-        let now = std::time::Instant::now();
+        let now = Instant::now();
         let ping_nonce = Nonce::default();
         if let Err(err) = self.unicast(target, Message::Ping(ping_nonce)) {
             if !self.is_connected(target) {
@@ -398,7 +941,7 @@ impl SyntheticNode {
                     }
                 }
                 Ok((_, Message::Pong(nonce))) if nonce == ping_nonce => {
-                    return Ok(());
+                    return Ok(now.elapsed());
                 }
                 Ok((_, message)) => {
                     return Err(PingPongError::Unexpected(message.into()));
@@ -427,35 +970,86 @@ impl SyntheticNode {
 
     /// Gracefully shuts down the node.
     pub async fn shut_down(&self) {
+        if let Some(idle_task) = &self.idle_task {
+            idle_task.abort();
+        }
         self.inner_node.node().shut_down().await
     }
 }
 
+impl Drop for SyntheticNode {
+    fn drop(&mut self) {
+        // The task holds a handle to the node, so it would otherwise outlive it.
+        if let Some(idle_task) = &self.idle_task {
+            idle_task.abort();
+        }
+    }
+}
+
 #[derive(Clone)]
 struct InnerNode {
     node: Node,
+    network: Network,
     handshake: Option<HandshakeKind>,
     inbound_tx: Sender<(SocketAddr, Message)>,
     message_filter: MessageFilter,
+    peer_message_filters: Arc<Mutex<HashMap<SocketAddr, MessageFilter>>>,
     handshake_infos: Arc<Mutex<HashMap<SocketAddr, Version>>>,
+    strict_handshake_panic: bool,
+    auto_getdata: bool,
+    wrong_pong_nonce: bool,
+    served_blocks: Arc<Vec<(InvHash, Block)>>,
+    version_overrides: VersionOverrides,
+    linger: Option<Duration>,
+    handshake_step_delay: Option<Duration>,
+    message_hook: Option<MessageHookWrapper>,
+    read_gates: Arc<Mutex<HashMap<SocketAddr, watch::Sender<bool>>>>,
+    messages_received: Arc<AtomicU64>,
+    message_history: Option<usize>,
+    recent_messages: Arc<Mutex<HashMap<SocketAddr, VecDeque<Message>>>>,
+    record_command_frequencies: bool,
+    command_frequencies: Arc<Mutex<HashMap<String, u64>>>,
+    version_frames: VersionFrames,
+    idle_timeout: Option<Duration>,
+    last_received: Arc<Mutex<HashMap<SocketAddr, Instant>>>,
 }
 
 impl InnerNode {
     async fn new(
         node: Node,
         tx: Sender<(SocketAddr, Message)>,
-        message_filter: MessageFilter,
-        handshake: Option<HandshakeKind>,
+        config: &SyntheticNodeBuilder,
     ) -> Self {
         let node = Self {
             node,
-            handshake,
+            network: config.network,
+            handshake: config.handshake,
             inbound_tx: tx,
-            message_filter,
+            message_filter: config.message_filter.clone(),
+            peer_message_filters: Arc::new(Mutex::new(config.peer_message_filters.clone())),
             handshake_infos: Default::default(),
+            strict_handshake_panic: config.strict_handshake_panic,
+            auto_getdata: config.auto_getdata,
+            wrong_pong_nonce: config.wrong_pong_nonce,
+            served_blocks: Arc::clone(&config.served_blocks),
+            version_overrides: config.version_overrides.clone(),
+            linger: config.linger,
+            handshake_step_delay: config.handshake_step_delay,
+            message_hook: config.message_hook.clone(),
+            read_gates: Default::default(),
+            messages_received: Default::default(),
+            message_history: config.message_history,
+            recent_messages: Default::default(),
+            record_command_frequencies: config.command_frequencies,
+            command_frequencies: Default::default(),
+            version_frames: Default::default(),
+            idle_timeout: config.idle_timeout,
+            last_received: Default::default(),
         };
 
-        if handshake.is_some() {
+        // The socket options are applied during the handshake, as it's the only point at which
+        // the stream is accessible.
+        if node.handshake.is_some() || node.linger.is_some() {
             node.enable_handshake().await;
         }
 
@@ -465,6 +1059,129 @@ impl InnerNode {
     fn handshake_info(&self, addr: &SocketAddr) -> Option<Version> {
         Some(self.handshake_infos.lock().get(addr)?.clone())
     }
+
+    /// Returns the [`MessageFilter`] applied to the messages from `addr`.
+    fn message_filter(&self, addr: SocketAddr) -> MessageFilter {
+        self.peer_message_filters
+            .lock()
+            .get(&addr)
+            .unwrap_or(&self.message_filter)
+            .clone()
+    }
+
+    /// Sends the message to each of the addresses, and returns the ones it couldn't be sent to.
+    fn send_to_each(&self, addrs: &[SocketAddr], message: &Message) -> Vec<SocketAddr> {
+        addrs
+            .iter()
+            .copied()
+            .filter(|addr| {
+                self.unicast(*addr, MessageOrBytes::Message(message.clone().into()))
+                    .is_err()
+            })
+            .collect()
+    }
+
+    /// Periodically disconnects the peers which haven't sent anything for longer than
+    /// `idle_timeout`, counting from the connection's creation if nothing was received yet.
+    async fn disconnect_idle_peers(self, idle_timeout: Duration) {
+        let mut check_interval = interval((idle_timeout / 4).max(Duration::from_millis(10)));
+
+        loop {
+            check_interval.tick().await;
+
+            let idle_peers = {
+                let last_received = self.last_received.lock();
+                self.node()
+                    .connection_infos()
+                    .into_iter()
+                    .filter(|(addr, info)| {
+                        let last_active = last_received
+                            .get(addr)
+                            .copied()
+                            .unwrap_or_else(|| info.stats().created());
+                        last_active.elapsed() > idle_timeout
+                    })
+                    .map(|(addr, _)| addr)
+                    .collect::<Vec<_>>()
+            };
+
+            for addr in idle_peers {
+                debug!(parent: self.node().span(), "disconnecting from idle peer {}", addr);
+                self.node().disconnect(addr).await;
+            }
+        }
+    }
+
+    /// Answers the [`GetData`] request with the served blocks it asks for, followed by a
+    /// [`NotFound`] for the rest of its entries. Returns `false` if none of the blocks are served.
+    ///
+    /// [`GetData`]: enum@crate::protocol::message::Message::GetData
+    /// [`NotFound`]: enum@crate::protocol::message::Message::NotFound
+    fn serve_blocks(&self, source: SocketAddr, inv: &Inv) -> io::Result<bool> {
+        let (served, missing): (Vec<_>, Vec<_>) = inv.inventory.iter().partition(|inv_hash| {
+            self.served_blocks
+                .iter()
+                .any(|(served_hash, _)| served_hash == *inv_hash)
+        });
+        if served.is_empty() {
+            return Ok(false);
+        }
+
+        debug!(parent: self.node().span(), "serving {} requested block(s)", served.len());
+        for inv_hash in served {
+            if let Some((_, block)) = self
+                .served_blocks
+                .iter()
+                .find(|(served_hash, _)| served_hash == inv_hash)
+            {
+                self.unicast(
+                    source,
+                    MessageOrBytes::Message(Message::Block(Box::new(block.clone())).into()),
+                )?;
+            }
+        }
+
+        if !missing.is_empty() {
+            self.unicast(
+                source,
+                MessageOrBytes::Message(Message::NotFound(Inv::new(missing)).into()),
+            )?;
+        }
+
+        Ok(true)
+    }
+
+    /// Constructs the [`Version`] message sent during the handshake.
+    fn own_version(&self, addr_recv: SocketAddr, addr_from: SocketAddr) -> Message {
+        let version = Version::new(addr_recv, addr_from);
+        Message::Version(self.version_overrides.apply(version))
+    }
+
+    /// Sleeps for the configured handshake step delay, if any.
+    async fn delay_handshake_step(&self) {
+        if let Some(delay) = self.handshake_step_delay {
+            sleep(delay).await;
+        }
+    }
+
+    /// Logs an unexpected handshake message and returns the matching error, or panics if the
+    /// node was configured with a strict handshake.
+    fn unexpected_handshake_message(&self, expected: &str, received: &Message) -> io::Error {
+        let span = self.node().span().clone();
+        error!(
+            parent: span,
+            "received unexpected message during handshake: {:?}", received
+        );
+
+        if self.strict_handshake_panic {
+            panic!("Expected {expected}, got {received:?}");
+        }
+
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Expected {expected}, got {received:?}"),
+        )
+    }
 }
 
 impl Pea2Pea for InnerNode {
@@ -473,9 +1190,14 @@ impl Pea2Pea for InnerNode {
     }
 }
 
+/// The raw `Version` frames received by a node, per peer.
+type VersionFrames = Arc<Mutex<HashMap<SocketAddr, Vec<u8>>>>;
+
 // TODO: move to protocol
 pub struct MessageCodec {
     codec: LengthDelimitedCodec,
+    network: Network,
+    version_capture: Option<(SocketAddr, VersionFrames)>,
 }
 
 impl Default for MessageCodec {
@@ -486,10 +1208,29 @@ impl Default for MessageCodec {
                 .length_field_offset(16)
                 .little_endian()
                 .num_skip(0)
-                // The biggest currently observed frame was 627412 bytes long, so taking some reserve
-                // to catch frames up to 1MB.
-                .max_frame_length(1048576)
+                // The length field is the body's, so this accepts any message the node may send,
+                // such as blocks bigger than the largest observed so far (627412 bytes).
+                .max_frame_length(MAX_MESSAGE_LEN)
                 .new_codec(),
+            network: DEFAULT_NETWORK,
+            version_capture: None,
+        }
+    }
+}
+
+impl MessageCodec {
+    /// Returns a codec encoding messages with the header magic of the given network.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Returns a codec which stores the raw frame (header included) of any `Version` received
+    /// from `addr` in `frames`, before decoding it.
+    fn with_version_capture(addr: SocketAddr, frames: VersionFrames) -> Self {
+        Self {
+            version_capture: Some((addr, frames)),
+            ..Default::default()
         }
     }
 }
@@ -505,6 +1246,12 @@ impl Decoder for MessageCodec {
             return Ok(None);
         };
 
+        if let Some((addr, frames)) = &self.version_capture {
+            if bytes.get(MAGIC_LEN..MAGIC_LEN + COMMAND_LEN) == Some(&VERSION_COMMAND[..]) {
+                frames.lock().insert(*addr, bytes.to_vec());
+            }
+        }
+
         let header = MessageHeader::decode(&mut bytes)?;
         let message = Message::decode(header.command, &mut bytes)?;
 
@@ -526,7 +1273,7 @@ impl Encoder<Message> for MessageCodec {
     type Error = io::Error;
 
     fn encode(&mut self, message: Message, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        message.encode(dst)
+        message.encode_for_network(self.network, dst)
     }
 }
 
@@ -552,18 +1299,99 @@ impl Reading for InnerNode {
     type Message = Message;
     type Codec = MessageCodec;
 
-    fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        MessageCodec::with_version_capture(addr, Arc::clone(&self.version_frames))
+            .with_network(self.network)
     }
 
     async fn process_message(&self, source: SocketAddr, message: Self::Message) -> io::Result<()> {
         let span = self.node().span().clone();
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+        if self.idle_timeout.is_some() {
+            self.last_received.lock().insert(source, Instant::now());
+        }
+
+        // Hold the message back while reading from the source is paused.
+        let gate = self
+            .read_gates
+            .lock()
+            .get(&source)
+            .map(|gate| gate.subscribe());
+        if let Some(mut gate) = gate {
+            while *gate.borrow() {
+                if gate.changed().await.is_err() {
+                    break;
+                }
+            }
+        }
 
         info!(parent: span.clone(), "processing {:?}", message);
-        match self.message_filter.message_filter_type(&message) {
+
+        if let Some(len) = self.message_history {
+            let mut recent_messages = self.recent_messages.lock();
+            let history = recent_messages.entry(source).or_default();
+            if history.len() == len {
+                history.pop_front();
+            }
+            if len > 0 {
+                history.push_back(message.clone());
+            }
+        }
+
+        if self.record_command_frequencies {
+            *self
+                .command_frequencies
+                .lock()
+                .entry(message.command().to_owned())
+                .or_default() += 1;
+        }
+
+        if let Some(MessageHookWrapper(hook)) = &self.message_hook {
+            hook(source, &message);
+        }
+
+        if self.auto_getdata {
+            if let Message::Inv(inv) = &message {
+                // Every entry but the ignorable ones is requested, grouped by kind.
+                let InvByKind {
+                    txs,
+                    blocks,
+                    filtered_blocks,
+                    wtxs,
+                    ..
+                } = inv.by_kind();
+                let inventory = blocks
+                    .into_iter()
+                    .map(InvHash::Block)
+                    .chain(filtered_blocks.into_iter().map(InvHash::FilteredBlock))
+                    .chain(txs.into_iter().map(InvHash::Tx))
+                    .chain(wtxs.into_iter().map(InvHash::MsgWtx))
+                    .collect::<Vec<_>>();
+
+                if !inventory.is_empty() {
+                    debug!(parent: span.clone(), "requesting the announced inventory");
+                    self.unicast(
+                        source,
+                        MessageOrBytes::Message(Message::GetData(Inv::new(inventory)).into()),
+                    )?;
+                }
+            }
+        }
+
+        if let Message::GetData(inv) = &message {
+            if self.serve_blocks(source, inv)? {
+                return Ok(());
+            }
+        }
+
+        let message_filter = self.message_filter(source);
+        match message_filter.message_filter_type(&message) {
             Filter::AutoReply => {
                 // Autoreply with the appropriate response.
-                let response = self.message_filter.reply_message(&message);
+                let response = match message {
+                    Message::Ping(_) if self.wrong_pong_nonce => Message::Pong(Nonce::default()),
+                    _ => message_filter.reply_message(&message),
+                };
 
                 debug!(parent: span, "auto replying with {:?}", response);
                 self.unicast(source, MessageOrBytes::Message(response.into()))?;
@@ -575,10 +1403,14 @@ impl Reading for InnerNode {
                     parent: span,
                     "sending the message to the node's inbound queue"
                 );
-                self.inbound_tx
-                    .send((source, message))
-                    .await
-                    .map_err(|e| io::Error::new(io::ErrorKind::ConnectionAborted, e))?;
+                if self.inbound_tx.send((source, message)).await.is_err() {
+                    // The receiver is dropped along with the `SyntheticNode`, which may happen
+                    // while its connections are still live, e.g. during teardown.
+                    warn!(
+                        parent: self.node().span(),
+                        "the node's inbound queue is closed, dropping the message"
+                    );
+                }
             }
 
             Filter::Enabled => {
@@ -596,44 +1428,74 @@ impl Writing for InnerNode {
     type Codec = MessageCodec;
 
     fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+        MessageCodec::default().with_network(self.network)
     }
 }
 
 #[async_trait::async_trait]
 impl Handshake for InnerNode {
-    async fn perform_handshake(&self, mut conn: Connection) -> io::Result<Connection> {
+    // The timeout is enforced in `perform_handshake` instead, as it depends on the step delay.
+    const TIMEOUT_MS: u64 = u32::MAX as u64;
+
+    async fn perform_handshake(&self, conn: Connection) -> io::Result<Connection> {
+        // Each side sends at most two messages during the handshake.
+        let delays = 2 * self.handshake_step_delay.unwrap_or_default();
+
+        timeout(
+            HANDSHAKE_TIMEOUT + delays,
+            self.exchange_handshake_messages(conn),
+        )
+        .await
+        .map_err(|_| Error::new(ErrorKind::TimedOut, "handshake timed out"))?
+    }
+}
+
+impl InnerNode {
+    /// Exchanges the handshake messages according to the configured [`HandshakeKind`].
+    async fn exchange_handshake_messages(&self, mut conn: Connection) -> io::Result<Connection> {
         let mut version_data: Option<Version> = None;
         let node_conn_side = !conn.side();
         let conn_addr = conn.addr();
-        let own_listening_addr = self.node().listening_addr().unwrap();
-        let mut framed_stream = Framed::new(self.borrow_stream(&mut conn), MessageCodec::default());
+        // A node which isn't listening advertises the unspecified address.
+        let own_listening_addr = self
+            .node()
+            .listening_addr()
+            .unwrap_or_else(|_| (Ipv4Addr::UNSPECIFIED, 0).into());
+
+        if let Some(linger) = self.linger {
+            self.borrow_stream(&mut conn).set_linger(Some(linger))?;
+        }
+
+        let mut framed_stream = Framed::new(
+            self.borrow_stream(&mut conn),
+            MessageCodec::default().with_network(self.network),
+        );
 
         match (self.handshake, node_conn_side) {
             (Some(HandshakeKind::Full), ConnectionSide::Initiator) => {
                 // Send and receive Version.
-                let own_version = Message::Version(Version::new(conn_addr, own_listening_addr));
+                let own_version = self.own_version(conn_addr, own_listening_addr);
+                self.delay_handshake_step().await;
                 framed_stream.send(own_version).await?;
 
                 let peer_version = framed_stream.try_next().await?;
                 match peer_version {
                     Some(Message::Version(version)) => {
                         // Send and receive Verack.
+                        self.delay_handshake_step().await;
                         framed_stream.send(Message::Verack).await?;
 
-                        let peer_verack = framed_stream.try_next().await?;
-                        assert_matches!(peer_verack, Some(Message::Verack));
+                        match framed_stream.try_next().await? {
+                            Some(Message::Verack) => {}
+                            Some(other) => {
+                                return Err(self.unexpected_handshake_message("Verack", &other))
+                            }
+                            None => return Err(io::ErrorKind::InvalidData.into()),
+                        }
 
                         version_data = Some(version);
                     }
-                    Some(other) => {
-                        let span = self.node().span().clone();
-                        error!(
-                            parent: span,
-                            "received non-version message during handshake: {:?}", other
-                        );
-                        panic!("Expected Version, got {other:?}");
-                    }
+                    Some(other) => return Err(self.unexpected_handshake_message("Version", &other)),
                     None => {
                         // Connection was refused by main node, quietly abort handshake.
                         return Err(io::ErrorKind::ConnectionRefused.into());
@@ -649,41 +1511,34 @@ impl Handshake for InnerNode {
                         version_data = Some(version);
                         addr
                     }
-                    Some(other) => {
-                        let span = self.node().span().clone();
-                        error!(
-                            parent: span,
-                            "received non-version message during handshake: {:?}", other
-                        );
-                        panic!("Expected Version, got {other:?}");
-                    }
+                    Some(other) => return Err(self.unexpected_handshake_message("Version", &other)),
                     None => return Err(io::ErrorKind::InvalidData.into()),
                 };
 
-                let own_version = Message::Version(Version::new(node_addr, own_listening_addr));
+                let own_version = self.own_version(node_addr, own_listening_addr);
+                self.delay_handshake_step().await;
                 framed_stream.send(own_version).await?;
 
                 // Receive and send Verack.
-                let peer_verack = framed_stream.try_next().await?;
-                assert_matches!(peer_verack, Some(Message::Verack));
+                match framed_stream.try_next().await? {
+                    Some(Message::Verack) => {}
+                    Some(other) => return Err(self.unexpected_handshake_message("Verack", &other)),
+                    None => return Err(io::ErrorKind::InvalidData.into()),
+                }
+
+                self.delay_handshake_step().await;
 
                 framed_stream.send(Message::Verack).await?;
             }
             (Some(HandshakeKind::VersionOnly), ConnectionSide::Initiator) => {
-                let own_version = Message::Version(Version::new(conn_addr, own_listening_addr));
+                let own_version = self.own_version(conn_addr, own_listening_addr);
+                self.delay_handshake_step().await;
                 framed_stream.send(own_version).await?;
 
                 let peer_version = framed_stream.try_next().await?;
                 match peer_version {
                     Some(Message::Version(version)) => version_data = Some(version),
-                    Some(other) => {
-                        let span = self.node().span().clone();
-                        error!(
-                            parent: span,
-                            "received non-version message during handshake: {:?}", other
-                        );
-                        panic!("Expected Version, got {other:?}");
-                    }
+                    Some(other) => return Err(self.unexpected_handshake_message("Version", &other)),
                     None => return Err(io::ErrorKind::InvalidData.into()),
                 }
             }
@@ -696,18 +1551,12 @@ impl Handshake for InnerNode {
                         version_data = Some(version);
                         addr
                     }
-                    Some(other) => {
-                        let span = self.node().span().clone();
-                        error!(
-                            parent: span,
-                            "received non-version message during handshake: {:?}", other
-                        );
-                        panic!("Expected Version, got {other:?}");
-                    }
+                    Some(other) => return Err(self.unexpected_handshake_message("Version", &other)),
                     None => return Err(io::ErrorKind::InvalidData.into()),
                 };
 
-                let own_version = Message::Version(Version::new(node_addr, own_listening_addr));
+                let own_version = self.own_version(node_addr, own_listening_addr);
+                self.delay_handshake_step().await;
                 framed_stream.send(own_version).await?;
             }
             (None, _) => {}
@@ -727,5 +1576,1005 @@ impl Handshake for InnerNode {
 impl Disconnect for InnerNode {
     async fn handle_disconnect(&self, addr: SocketAddr) {
         self.handshake_infos.lock().remove(&addr);
+        self.last_received.lock().remove(&addr);
+        self.recent_messages.lock().remove(&addr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{
+        message::constants::HEADER_LEN,
+        payload::tx::{TxOut, TxV4Builder},
+    };
+
+    #[tokio::test]
+    #[ignore]
+    async fn unexpected_handshake_message_is_an_error() {
+        // The responder doesn't handshake, so it can answer with a Verack instead of a Version.
+        let mut responder = SyntheticNode::builder().build().await.unwrap();
+        let responder_addr = responder.listening_addr().unwrap();
+
+        let initiator = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+
+        let handle = tokio::spawn(async move {
+            let addr = responder.wait_for_connection().await;
+            let (_, version) = responder.recv_message().await;
+            assert!(matches!(version, Message::Version(..)));
+            responder.unicast(addr, Message::Verack).unwrap();
+            responder
+        });
+
+        assert!(initiator.connect(responder_addr).await.is_err());
+        assert_eq!(initiator.num_connected(), 0);
+
+        handle.await.unwrap().shut_down().await;
+        initiator.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn auto_getdata_follows_inv() {
+        use crate::protocol::payload::{inv::WtxId, Hash};
+
+        let follower = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_auto_getdata()
+            .build()
+            .await
+            .unwrap();
+
+        let mut announcer = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        announcer
+            .connect(follower.listening_addr().unwrap())
+            .await
+            .unwrap();
+
+        let inv = Inv::new(vec![
+            InvHash::Block(Hash::new([1; 32])),
+            InvHash::Tx(Hash::new([2; 32])),
+        ]);
+        announcer
+            .unicast(
+                follower.listening_addr().unwrap(),
+                Message::Inv(inv.clone()),
+            )
+            .unwrap();
+
+        let (_, reply) = announcer
+            .recv_message_timeout(crate::tools::LONG_TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!(reply, Message::GetData(inv));
+
+        // A mixed inventory is requested in full, grouped by kind in the announced order, without
+        // the ignorable entries.
+        let wtx = InvHash::MsgWtx(WtxId {
+            id: Hash::new([3; 32]),
+            auth_digest: Hash::new([4; 32]),
+        });
+        let mixed = Inv::new(vec![
+            wtx,
+            InvHash::Tx(Hash::new([5; 32])),
+            InvHash::Error,
+            InvHash::Block(Hash::new([6; 32])),
+            InvHash::filtered_block(Hash::new([7; 32])),
+            InvHash::Tx(Hash::new([8; 32])),
+        ]);
+        announcer
+            .unicast(follower.listening_addr().unwrap(), Message::Inv(mixed))
+            .unwrap();
+
+        let (_, reply) = announcer
+            .recv_message_timeout(crate::tools::LONG_TIMEOUT)
+            .await
+            .unwrap();
+        let expected = Inv::new(vec![
+            InvHash::Block(Hash::new([6; 32])),
+            InvHash::filtered_block(Hash::new([7; 32])),
+            InvHash::Tx(Hash::new([5; 32])),
+            InvHash::Tx(Hash::new([8; 32])),
+            wtx,
+        ]);
+        assert_eq!(reply, Message::GetData(expected));
+
+        announcer.shut_down().await;
+        follower.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn served_blocks_answer_getdata() {
+        use crate::tools::LONG_TIMEOUT;
+
+        let blocks = Block::initial_testnet_blocks();
+        let served_block = blocks[1].clone();
+
+        let server = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_all_auto_reply()
+            .with_served_blocks(vec![served_block.clone()])
+            .build()
+            .await
+            .unwrap();
+        let server_addr = server.listening_addr().unwrap();
+
+        let mut requester = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_all_auto_reply()
+            .build()
+            .await
+            .unwrap();
+        requester.connect(server_addr).await.unwrap();
+
+        let unknown = blocks[2].inv_hash();
+        requester
+            .unicast(
+                server_addr,
+                Message::GetData(Inv::new(vec![served_block.inv_hash(), unknown])),
+            )
+            .unwrap();
+
+        let replies = requester
+            .expect_sequence(
+                server_addr,
+                &[
+                    |message| matches!(message, Message::Block(_)),
+                    |message| matches!(message, Message::NotFound(_)),
+                ],
+                LONG_TIMEOUT,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            replies,
+            vec![
+                Message::Block(Box::new(served_block)),
+                Message::NotFound(Inv::new(vec![unknown])),
+            ]
+        );
+
+        requester.shut_down().await;
+        server.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn non_listening_node_handshakes_outbound() {
+        use crate::tools::LONG_TIMEOUT;
+
+        let responder = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_all_auto_reply()
+            .build()
+            .await
+            .unwrap();
+        let responder_addr = responder.listening_addr().unwrap();
+
+        let mut initiator = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_all_auto_reply()
+            .with_listening(false)
+            .build()
+            .await
+            .unwrap();
+        assert!(initiator.listening_addr().is_err());
+
+        initiator.connect(responder_addr).await.unwrap();
+        initiator
+            .ping_pong_timeout(responder_addr, LONG_TIMEOUT)
+            .await
+            .unwrap();
+
+        initiator.shut_down().await;
+        responder.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn custom_user_agent_on_the_wire() {
+        const USER_AGENT: &str = "/Ziggurat:0.1.0/";
+
+        // The capturer doesn't handshake, so it receives the Version as a regular message.
+        let mut capturer = SyntheticNode::builder().build().await.unwrap();
+        let capturer_addr = capturer.listening_addr().unwrap();
+
+        let sender = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_user_agent(USER_AGENT.to_string())
+            .build()
+            .await
+            .unwrap();
+
+        // The handshake never completes, it fails once the capturer shuts down.
+        let handle = tokio::spawn(async move {
+            let _ = sender.connect(capturer_addr).await;
+            sender
+        });
+
+        let (_, version) = capturer
+            .recv_message_timeout(crate::tools::LONG_TIMEOUT)
+            .await
+            .unwrap();
+        match version {
+            Message::Version(version) => assert_eq!(version.user_agent.0, USER_AGENT),
+            other => panic!("expected a Version, got {other:?}"),
+        }
+
+        capturer.shut_down().await;
+        handle.await.unwrap().shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn version_template_on_the_wire() {
+        // A future protocol version, advertising no services.
+        let template = Version::new(
+            "127.0.0.1:1".parse().unwrap(),
+            "127.0.0.1:2".parse().unwrap(),
+        )
+        .with_version(ProtocolVersion::current().0 + 100)
+        .with_services(ServiceFlags::empty())
+        .with_start_height(1_000);
+
+        let mut capturer = SyntheticNode::builder().build().await.unwrap();
+        let capturer_addr = capturer.listening_addr().unwrap();
+
+        let sender = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_version_template(template.clone())
+            .with_relay(true)
+            .build()
+            .await
+            .unwrap();
+        let sender_addr = sender.listening_addr().unwrap();
+
+        let handle = tokio::spawn(async move {
+            let _ = sender.connect(capturer_addr).await;
+            sender
+        });
+
+        let (_, version) = capturer
+            .recv_message_timeout(crate::tools::LONG_TIMEOUT)
+            .await
+            .unwrap();
+        match version {
+            Message::Version(version) => {
+                assert_eq!(version.version, template.version);
+                assert_eq!(version.services, 0);
+                assert_eq!(version.start_height, 1_000);
+                assert_eq!(version.nonce, template.nonce);
+                // The builder's options take precedence over the template.
+                assert!(version.relay);
+                // The addresses are those of the connection.
+                assert_eq!(version.addr_recv.addr, capturer_addr);
+                assert_eq!(version.addr_from.addr, sender_addr);
+            }
+            other => panic!("expected a Version, got {other:?}"),
+        }
+
+        capturer.shut_down().await;
+        handle.await.unwrap().shut_down().await;
+    }
+
+    /// Returns a block padded with a transaction whose output script is `script_len` bytes long.
+    fn padded_block(script_len: usize) -> Block {
+        let mut block = Block::testnet_2();
+        let padding = TxV4Builder::default()
+            .with_output(TxOut::new(0, vec![0x6a; script_len]))
+            .build()
+            .unwrap();
+        block.txs.push(padding);
+
+        block
+    }
+
+    #[test]
+    #[ignore]
+    fn large_block_is_decoded_across_reads() {
+        // Larger than the former 1MB frame limit, within the protocol's limit.
+        let message = Message::Block(Box::new(padded_block(MAX_MESSAGE_LEN * 3 / 4)));
+        let bytes = message.to_vec().unwrap();
+        assert!(bytes.len() > 1024 * 1024);
+
+        // The bytes arrive in 64 KiB reads, the message is only decoded once complete.
+        let mut codec = MessageCodec::default();
+        let mut src = BytesMut::new();
+        let mut decoded = None;
+        for chunk in bytes.chunks(64 * 1024) {
+            assert!(decoded.is_none());
+            src.extend_from_slice(chunk);
+            decoded = codec.decode(&mut src).unwrap();
+        }
+
+        assert_eq!(decoded, Some(message));
+        assert!(src.is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn large_block_is_received() {
+        let block = padded_block(MAX_MESSAGE_LEN * 3 / 4);
+
+        let mut receiver = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        let receiver_addr = receiver.listening_addr().unwrap();
+        let sender = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        sender.connect(receiver_addr).await.unwrap();
+
+        sender
+            .unicast(receiver_addr, Message::Block(Box::new(block.clone())))
+            .unwrap();
+
+        let (_, message) = receiver
+            .recv_message_timeout(crate::tools::LONG_TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!(message, Message::Block(Box::new(block)));
+
+        sender.shut_down().await;
+        receiver.shut_down().await;
+    }
+
+    #[test]
+    #[ignore]
+    fn message_over_the_protocol_limit_is_rejected() {
+        let message = Message::Block(Box::new(padded_block(MAX_MESSAGE_LEN)));
+        let mut src = BytesMut::from(&message.to_vec().unwrap()[..]);
+
+        // The frame is rejected from its header, without waiting for the body.
+        src.truncate(HEADER_LEN);
+        assert!(MessageCodec::default().decode(&mut src).is_err());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn over_length_user_agent_is_rejected() {
+        let result = SyntheticNode::builder()
+            .with_user_agent("a".repeat(MAX_USER_AGENT_LEN + 1))
+            .build()
+            .await;
+
+        assert_eq!(result.err().unwrap().kind(), ErrorKind::InvalidInput);
+
+        // The maximum length itself is accepted.
+        SyntheticNode::builder()
+            .with_user_agent("a".repeat(MAX_USER_AGENT_LEN))
+            .build()
+            .await
+            .unwrap()
+            .shut_down()
+            .await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn dropped_receiver_does_not_break_reading() {
+        // Only Pings are auto-replied to, everything else goes to the inbound queue.
+        let receiver = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_message_filter(
+                MessageFilter::with_all_disabled().with_ping_filter(Filter::AutoReply),
+            )
+            .build()
+            .await
+            .unwrap();
+        let receiver_addr = receiver.listening_addr().unwrap();
+
+        let mut sender = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        sender.connect(receiver_addr).await.unwrap();
+
+        // Drop the receiver without shutting it down, its connection stays live.
+        drop(receiver);
+        sender.unicast(receiver_addr, Message::GetAddr).unwrap();
+
+        // The reading task would no longer reply to Pings if it had panicked.
+        sender
+            .ping_pong_timeout(receiver_addr, crate::tools::LONG_TIMEOUT)
+            .await
+            .unwrap();
+
+        sender.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn zero_linger_resets_the_connection() {
+        use tokio::{io::AsyncReadExt, net::TcpListener};
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let listener_addr = listener.local_addr().unwrap();
+
+        // No handshake, the linger option alone enables the handshake protocol.
+        let synthetic_node = SyntheticNode::builder()
+            .with_linger(Duration::ZERO)
+            .build()
+            .await
+            .unwrap();
+        synthetic_node.connect(listener_addr).await.unwrap();
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        assert!(synthetic_node.disconnect(listener_addr).await);
+
+        // A reset shows up as an error, while a clean shutdown would be a 0-byte read.
+        let mut buf = [0u8; 1];
+        let err = stream.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+
+        synthetic_node.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn paused_reading_delivers_after_resume() {
+        use crate::tools::RECV_TIMEOUT;
+
+        let sender = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        let sender_addr = sender.listening_addr().unwrap();
+
+        let mut receiver = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        receiver.connect(sender_addr).await.unwrap();
+        receiver.pause_reading(sender_addr);
+
+        let receiver_addr = sender.wait_for_connection().await;
+        let nonces = (0..3).map(|_| Nonce::default()).collect::<Vec<_>>();
+        for nonce in &nonces {
+            sender
+                .unicast(receiver_addr, Message::Ping(*nonce))
+                .unwrap();
+        }
+
+        // Nothing is delivered while paused.
+        assert!(receiver.recv_message_timeout(RECV_TIMEOUT).await.is_err());
+
+        // The queued messages are delivered in order once resumed.
+        receiver.resume_reading(sender_addr);
+        for nonce in nonces {
+            let (_, message) = receiver.recv_message_timeout(RECV_TIMEOUT).await.unwrap();
+            assert_eq!(message, Message::Ping(nonce));
+        }
+
+        receiver.shut_down().await;
+        sender.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn expect_sequence_in_order() {
+        use crate::tools::RECV_TIMEOUT;
+
+        let source = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+
+        let mut sink = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        sink.connect(source.listening_addr().unwrap())
+            .await
+            .unwrap();
+        let sink_addr = source.wait_for_connection().await;
+
+        let send_sequence = || {
+            for message in [
+                Message::Verack,
+                Message::GetAddr,
+                Message::Ping(Nonce::default()),
+            ] {
+                source.unicast(sink_addr, message).unwrap();
+            }
+        };
+
+        // The whole sequence matches.
+        send_sequence();
+        let messages = sink
+            .expect_sequence(
+                source.listening_addr().unwrap(),
+                &[
+                    |m| matches!(m, Message::Verack),
+                    |m| matches!(m, Message::GetAddr),
+                    |m| matches!(m, Message::Ping(..)),
+                ],
+                RECV_TIMEOUT,
+            )
+            .await
+            .unwrap();
+        assert_eq!(messages.len(), 3);
+
+        // The second message is out of order.
+        send_sequence();
+        let err = sink
+            .expect_sequence(
+                source.listening_addr().unwrap(),
+                &[
+                    |m| matches!(m, Message::Verack),
+                    |m| matches!(m, Message::Ping(..)),
+                ],
+                RECV_TIMEOUT,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // Only the trailing Ping is left, the sequence times out.
+        let err = sink
+            .expect_sequence(
+                source.listening_addr().unwrap(),
+                &[
+                    |m| matches!(m, Message::Ping(..)),
+                    |m| matches!(m, Message::Pong(..)),
+                ],
+                RECV_TIMEOUT,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+
+        sink.shut_down().await;
+        source.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn multiple_messages_in_a_single_write() {
+        use crate::tools::RECV_TIMEOUT;
+
+        let source = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+
+        let mut sink = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        sink.connect(source.listening_addr().unwrap())
+            .await
+            .unwrap();
+        let sink_addr = source.wait_for_connection().await;
+
+        // Both messages are sent in a single raw write.
+        let nonce = Nonce::default();
+        let mut bytes = Message::Ping(nonce).to_vec().unwrap();
+        bytes.extend(Message::GetAddr.to_vec().unwrap());
+        source.send_direct_bytes(sink_addr, bytes).unwrap();
+
+        let messages = sink
+            .expect_sequence(
+                source.listening_addr().unwrap(),
+                &[
+                    |m| matches!(m, Message::Ping(..)),
+                    |m| matches!(m, Message::GetAddr),
+                ],
+                RECV_TIMEOUT,
+            )
+            .await
+            .unwrap();
+        assert_eq!(messages[0], Message::Ping(nonce));
+
+        sink.shut_down().await;
+        source.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn total_messages_received_counts_ping_pong() {
+        use crate::{tools::LONG_TIMEOUT, wait_until};
+
+        let responder = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_all_auto_reply()
+            .build()
+            .await
+            .unwrap();
+
+        let mut initiator = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        initiator
+            .connect(responder.listening_addr().unwrap())
+            .await
+            .unwrap();
+
+        // Handshake messages aren't counted.
+        assert_eq!(initiator.total_messages_received(), 0);
+        assert_eq!(responder.total_messages_received(), 0);
+
+        initiator
+            .ping_pong_timeout(responder.listening_addr().unwrap(), LONG_TIMEOUT)
+            .await
+            .unwrap();
+
+        // The auto-replied Ping is counted as well.
+        wait_until!(LONG_TIMEOUT, responder.total_messages_received() == 1);
+        assert_eq!(initiator.total_messages_received(), 1);
+
+        initiator.shut_down().await;
+        responder.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn message_hook_observes_ping_pong() {
+        use crate::tools::LONG_TIMEOUT;
+
+        let hook = |observed: Arc<Mutex<Vec<(SocketAddr, Message)>>>| -> MessageHook {
+            Arc::new(move |source, message| observed.lock().push((source, message.clone())))
+        };
+
+        let responder_observed = Arc::new(Mutex::new(Vec::new()));
+        let responder = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_all_auto_reply()
+            .with_message_hook(hook(responder_observed.clone()))
+            .build()
+            .await
+            .unwrap();
+        let responder_addr = responder.listening_addr().unwrap();
+
+        let initiator_observed = Arc::new(Mutex::new(Vec::new()));
+        let mut initiator = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_message_hook(hook(initiator_observed.clone()))
+            .build()
+            .await
+            .unwrap();
+        initiator.connect(responder_addr).await.unwrap();
+
+        initiator
+            .ping_pong_timeout(responder_addr, LONG_TIMEOUT)
+            .await
+            .unwrap();
+
+        // The hook sees the auto-replied Ping, as well as the queued Pong.
+        let responder_observed = responder_observed.lock();
+        assert_eq!(responder_observed.len(), 1);
+        assert!(matches!(responder_observed[0].1, Message::Ping(..)));
+
+        let initiator_observed = initiator_observed.lock();
+        assert_eq!(initiator_observed.len(), 1);
+        assert_eq!(initiator_observed[0].0, responder_addr);
+        assert!(matches!(initiator_observed[0].1, Message::Pong(..)));
+
+        initiator.shut_down().await;
+        responder.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn peer_info_after_handshake() {
+        let responder = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        let responder_addr = responder.listening_addr().unwrap();
+
+        let initiator = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        assert!(initiator.peer_info(responder_addr).is_none());
+
+        initiator.connect(responder_addr).await.unwrap();
+
+        // The connection was only just established.
+        let info = initiator.peer_info(responder_addr).unwrap();
+        assert_eq!(info.addr(), responder_addr);
+        assert!(info.stats().created().elapsed() < Duration::from_secs(5));
+
+        initiator.shut_down().await;
+        responder.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn recent_messages_are_bounded_and_ordered() {
+        use crate::{tools::LONG_TIMEOUT, wait_until};
+
+        const HISTORY_LEN: usize = 3;
+
+        let responder = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_message_history(HISTORY_LEN)
+            .build()
+            .await
+            .unwrap();
+        let responder_addr = responder.listening_addr().unwrap();
+
+        let initiator = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        initiator.connect(responder_addr).await.unwrap();
+        let initiator_addr = responder.wait_for_connection().await;
+
+        let pings = (0..5)
+            .map(|_| Message::Ping(Nonce::default()))
+            .collect::<Vec<_>>();
+        for ping in &pings {
+            initiator.unicast(responder_addr, ping.clone()).unwrap();
+        }
+        wait_until!(LONG_TIMEOUT, responder.total_messages_received() == 5);
+
+        // Only the last messages are kept, oldest first.
+        assert_eq!(
+            responder.recent_messages(initiator_addr, 10),
+            pings[pings.len() - HISTORY_LEN..]
+        );
+        assert_eq!(
+            responder.recent_messages(initiator_addr, 2),
+            pings[pings.len() - 2..]
+        );
+        assert!(responder.recent_messages(responder_addr, 10).is_empty());
+
+        // The history is dropped along with the connection.
+        initiator.shut_down().await;
+        wait_until!(
+            LONG_TIMEOUT,
+            responder.recent_messages(initiator_addr, 10).is_empty()
+        );
+
+        responder.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn silent_peer_is_disconnected_after_idle_timeout() {
+        use crate::{tools::LONG_TIMEOUT, wait_until};
+
+        const IDLE_TIMEOUT: Duration = Duration::from_millis(500);
+
+        let responder = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_idle_timeout(IDLE_TIMEOUT)
+            .build()
+            .await
+            .unwrap();
+        let responder_addr = responder.listening_addr().unwrap();
+
+        // The initiator never sends anything after the handshake.
+        let initiator = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        initiator.connect(responder_addr).await.unwrap();
+        let connected = Instant::now();
+
+        wait_until!(LONG_TIMEOUT, initiator.num_connected() == 0);
+        assert!(connected.elapsed() >= IDLE_TIMEOUT);
+        assert_eq!(responder.num_connected(), 0);
+
+        initiator.shut_down().await;
+        responder.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn command_frequencies_count_ping_pong_addr() {
+        use crate::{protocol::payload::Addr, tools::LONG_TIMEOUT, wait_until};
+
+        let responder = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_command_frequencies()
+            .build()
+            .await
+            .unwrap();
+        let responder_addr = responder.listening_addr().unwrap();
+
+        let initiator = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        initiator.connect(responder_addr).await.unwrap();
+
+        // Handshake messages aren't counted.
+        assert!(responder.command_frequencies().is_empty());
+
+        let messages = [
+            Message::Ping(Nonce::default()),
+            Message::Ping(Nonce::default()),
+            Message::Pong(Nonce::default()),
+            Message::Addr(Addr::empty()),
+            Message::Addr(Addr::empty()),
+            Message::Addr(Addr::empty()),
+        ];
+        for message in messages {
+            initiator.unicast(responder_addr, message).unwrap();
+        }
+        wait_until!(LONG_TIMEOUT, responder.total_messages_received() == 6);
+
+        let expected = HashMap::from([
+            ("ping".to_owned(), 2),
+            ("pong".to_owned(), 1),
+            ("addr".to_owned(), 3),
+        ]);
+        assert_eq!(responder.command_frequencies(), expected);
+        assert!(responder.command_frequency_table().contains("addr"));
+
+        initiator.shut_down().await;
+        responder.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn peer_message_filters_override_the_node_filter() {
+        use crate::tools::LONG_TIMEOUT;
+
+        let mut auto_replied = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        let auto_replied_addr = auto_replied.listening_addr().unwrap();
+        let mut enqueued = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        let enqueued_addr = enqueued.listening_addr().unwrap();
+
+        // Pings are auto-replied to, except the ones from the second peer.
+        let mut node = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_all_auto_reply()
+            .with_peer_message_filter(enqueued_addr, MessageFilter::with_all_disabled())
+            .build()
+            .await
+            .unwrap();
+        let node_addr = node.listening_addr().unwrap();
+        node.connect(auto_replied_addr).await.unwrap();
+        node.connect(enqueued_addr).await.unwrap();
+
+        auto_replied
+            .ping_pong_timeout(node_addr, LONG_TIMEOUT)
+            .await
+            .unwrap();
+
+        let nonce = Nonce::default();
+        enqueued.unicast(node_addr, Message::Ping(nonce)).unwrap();
+        let (source, message) = node.recv_message_timeout(LONG_TIMEOUT).await.unwrap();
+        assert_eq!(source, enqueued_addr);
+        assert_eq!(message, Message::Ping(nonce));
+
+        // Once the override is cleared, the node's filter applies again.
+        node.clear_peer_message_filter(enqueued_addr);
+        enqueued
+            .ping_pong_timeout(node_addr, LONG_TIMEOUT)
+            .await
+            .unwrap();
+
+        node.shut_down().await;
+        auto_replied.shut_down().await;
+        enqueued.shut_down().await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn broadcast_reaches_all_peers() {
+        use crate::tools::LONG_TIMEOUT;
+
+        let mut peers = Vec::new();
+        for _ in 0..3 {
+            peers.push(
+                SyntheticNode::builder()
+                    .with_full_handshake()
+                    .build()
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let broadcaster = SyntheticNode::builder()
+            .with_full_handshake()
+            .build()
+            .await
+            .unwrap();
+        for peer in &peers {
+            broadcaster
+                .connect(peer.listening_addr().unwrap())
+                .await
+                .unwrap();
+        }
+
+        broadcaster.broadcast(Message::GetAddr).unwrap();
+        for peer in &mut peers {
+            let (_, message) = peer.recv_message_timeout(LONG_TIMEOUT).await.unwrap();
+            assert_eq!(message, Message::GetAddr);
+        }
+
+        // Only the selected peers receive the message, unknown targets are reported.
+        let unknown_addr = "127.0.0.1:1".parse().unwrap();
+        let error = broadcaster
+            .send_to_subset(
+                &[peers[0].listening_addr().unwrap(), unknown_addr],
+                Message::Verack,
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotConnected);
+        assert!(error.to_string().contains(&unknown_addr.to_string()));
+
+        let (_, message) = peers[0].recv_message_timeout(LONG_TIMEOUT).await.unwrap();
+        assert_eq!(message, Message::Verack);
+        assert!(peers[1]
+            .recv_message_timeout(Duration::from_millis(100))
+            .await
+            .is_err());
+
+        broadcaster.shut_down().await;
+        for peer in peers {
+            peer.shut_down().await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn messages_are_encoded_for_the_selected_network() {
+        use crate::{protocol::message::constants::MAGIC_REGTEST, tools::LONG_TIMEOUT};
+
+        // Neither node handshakes, so the responder captures the raw `Version` frame.
+        let mut responder = SyntheticNode::builder().build().await.unwrap();
+        let responder_addr = responder.listening_addr().unwrap();
+
+        let initiator = SyntheticNode::builder()
+            .with_network(Network::Regtest)
+            .build()
+            .await
+            .unwrap();
+        initiator.connect(responder_addr).await.unwrap();
+        let initiator_addr = initiator.listening_addr().unwrap();
+        initiator
+            .unicast(
+                responder_addr,
+                Message::Version(Version::new(responder_addr, initiator_addr)),
+            )
+            .unwrap();
+
+        let (source, _) = responder.recv_message_timeout(LONG_TIMEOUT).await.unwrap();
+        let bytes = responder.version_bytes(source).unwrap();
+        assert_eq!(bytes[..MAGIC_REGTEST.len()], MAGIC_REGTEST);
+
+        initiator.shut_down().await;
+        responder.shut_down().await;
     }
 }
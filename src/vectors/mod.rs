@@ -64,3 +64,95 @@ lazy_static! {
     pub static ref BLOCK_TESTNET_1_599_201_BYTES: Vec<u8> =
         <Vec<u8>>::from_hex(include_str!("block-test-1-599-201.txt").trim()).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::payload::block::Block;
+
+    #[test]
+    #[ignore]
+    fn all_vectors_decode_into_blocks() {
+        let vectors: [(&str, &[u8]); 19] = [
+            ("BLOCK_TESTNET_GENESIS_BYTES", &BLOCK_TESTNET_GENESIS_BYTES),
+            (
+                "BLOCK_TESTNET_0_000_001_BYTES",
+                &BLOCK_TESTNET_0_000_001_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_0_000_002_BYTES",
+                &BLOCK_TESTNET_0_000_002_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_0_000_003_BYTES",
+                &BLOCK_TESTNET_0_000_003_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_0_000_004_BYTES",
+                &BLOCK_TESTNET_0_000_004_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_0_000_005_BYTES",
+                &BLOCK_TESTNET_0_000_005_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_0_000_006_BYTES",
+                &BLOCK_TESTNET_0_000_006_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_0_000_007_BYTES",
+                &BLOCK_TESTNET_0_000_007_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_0_000_008_BYTES",
+                &BLOCK_TESTNET_0_000_008_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_0_000_009_BYTES",
+                &BLOCK_TESTNET_0_000_009_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_0_000_010_BYTES",
+                &BLOCK_TESTNET_0_000_010_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_0_207_500_BYTES",
+                &BLOCK_TESTNET_0_207_500_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_0_280_000_BYTES",
+                &BLOCK_TESTNET_0_280_000_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_0_584_000_BYTES",
+                &BLOCK_TESTNET_0_584_000_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_0_903_800_BYTES",
+                &BLOCK_TESTNET_0_903_800_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_1_028_500_BYTES",
+                &BLOCK_TESTNET_1_028_500_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_1_599_199_BYTES",
+                &BLOCK_TESTNET_1_599_199_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_1_599_200_BYTES",
+                &BLOCK_TESTNET_1_599_200_BYTES,
+            ),
+            (
+                "BLOCK_TESTNET_1_599_201_BYTES",
+                &BLOCK_TESTNET_1_599_201_BYTES,
+            ),
+        ];
+
+        for (name, bytes) in vectors {
+            if let Err(e) = Block::from_vector(bytes) {
+                panic!("{name} doesn't decode into a block: {e}");
+            }
+        }
+    }
+}
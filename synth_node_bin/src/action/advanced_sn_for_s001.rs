@@ -46,6 +46,7 @@ impl SynthNodeAction for Action {
                 ..Default::default()
             },
             allow_proper_shutdown: true,
+            linger: None,
         }
     }
 
@@ -0,0 +1,215 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use pea2pea::Config as NodeConfig;
+use ziggurat_zcash::{
+    protocol::{
+        message::Message,
+        payload::{codec::Codec, inv::InvHash, Hash},
+    },
+    tools::{message_filter::MessageFilter, synthetic_node::SyntheticNode},
+};
+
+use super::{ActionCfg, SynthNodeAction};
+
+/// The CSV file the announcements are appended to, in the working directory.
+const LOG_PATH: &str = "mempool_sniffer.csv";
+const LOG_HEADER: &str = "txid,peer,announced_at_unix_ms,delay_ms";
+
+pub(super) struct Action;
+
+pub(super) fn action() -> Box<dyn SynthNodeAction> {
+    Box::new(Action {})
+}
+
+#[async_trait::async_trait]
+impl SynthNodeAction for Action {
+    fn info(&self) -> &str {
+        "a listener node which records when each peer first announces a transaction, and appends
+           the announcements to mempool_sniffer.csv"
+    }
+
+    fn config(&self) -> ActionCfg {
+        ActionCfg {
+            msg_filter: MessageFilter::with_all_auto_reply(),
+            network_cfg: NodeConfig {
+                listener_ip: Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+                max_connections: 1000,
+                ..Default::default()
+            },
+            allow_proper_shutdown: true,
+            linger: None,
+        }
+    }
+
+    #[allow(unused_variables)]
+    async fn run(&self, synth_node: &mut SyntheticNode, addr: Option<SocketAddr>) -> Result<()> {
+        let mut log = open_log(LOG_PATH)?;
+        let mut announcements = TxAnnouncements::default();
+
+        loop {
+            let (src, msg) = synth_node.try_recv_message().await?;
+
+            let inv = match msg {
+                Message::Inv(inv) => inv,
+                _ => continue,
+            };
+
+            let announced_at = SystemTime::now();
+            for txid in inv.inventory.iter().filter_map(announced_txid) {
+                if let Some(announcement) = announcements.record(txid, src, announced_at) {
+                    tracing::info!(
+                        "{} announced by {} after {:?}",
+                        announcement.txid,
+                        announcement.peer,
+                        announcement.delay
+                    );
+                    writeln!(log, "{}", announcement.to_csv())?;
+                }
+            }
+            log.flush()?;
+        }
+    }
+}
+
+/// Opens the log for appending, writing the CSV header if the file is new.
+fn open_log(path: &str) -> io::Result<File> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "{LOG_HEADER}")?;
+    }
+
+    Ok(file)
+}
+
+/// Returns the ID of the announced transaction, if the entry is one.
+fn announced_txid(inv_hash: &InvHash) -> Option<String> {
+    match inv_hash {
+        InvHash::Tx(hash) => Some(txid_hex(hash)),
+        InvHash::MsgWtx(wtx_id) => Some(txid_hex(&wtx_id.id)),
+        _ => None,
+    }
+}
+
+/// Formats the transaction ID like the nodes' RPCs and block explorers do, byte-reversed.
+fn txid_hex(hash: &Hash) -> String {
+    let mut bytes = Vec::with_capacity(32);
+    // Encoding a hash into a vector can't fail.
+    let _ = hash.encode(&mut bytes);
+
+    bytes.iter().rev().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+/// The first announcement of a transaction by a peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Announcement {
+    txid: String,
+    peer: SocketAddr,
+    announced_at: SystemTime,
+    /// The time elapsed since the first announcement of the transaction by any peer.
+    delay: Duration,
+}
+
+impl Announcement {
+    fn to_csv(&self) -> String {
+        let announced_at = self
+            .announced_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        format!(
+            "{},{},{},{}",
+            self.txid,
+            self.peer,
+            announced_at.as_millis(),
+            self.delay.as_millis()
+        )
+    }
+}
+
+/// The transactions announced so far, and the peers which announced them.
+#[derive(Default)]
+struct TxAnnouncements {
+    /// The time of the first announcement of each transaction by any peer, and the peers which
+    /// announced it since.
+    first_seen: HashMap<String, (SystemTime, HashSet<SocketAddr>)>,
+}
+
+impl TxAnnouncements {
+    /// Records the announcement of `txid` by `peer`, and returns it if it's the peer's first one.
+    fn record(
+        &mut self,
+        txid: String,
+        peer: SocketAddr,
+        announced_at: SystemTime,
+    ) -> Option<Announcement> {
+        let (first_announced_at, peers) = self
+            .first_seen
+            .entry(txid.clone())
+            .or_insert_with(|| (announced_at, HashSet::new()));
+
+        if !peers.insert(peer) {
+            return None;
+        }
+
+        Some(Announcement {
+            txid,
+            peer,
+            announced_at,
+            delay: announced_at
+                .duration_since(*first_announced_at)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_first_announcement_per_peer_is_recorded() {
+        let peer_a: SocketAddr = "127.0.0.1:8233".parse().unwrap();
+        let peer_b: SocketAddr = "127.0.0.2:8233".parse().unwrap();
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let txid = txid_hex(&Hash::new([0xab; 32]));
+
+        let mut announcements = TxAnnouncements::default();
+        let first = announcements.record(txid.clone(), peer_a, start).unwrap();
+        assert_eq!(first.delay, Duration::ZERO);
+        assert_eq!(
+            first.to_csv(),
+            format!("{},127.0.0.1:8233,1700000000000,0", "ab".repeat(32))
+        );
+
+        // A repeated announcement by the same peer is ignored.
+        assert!(announcements
+            .record(txid.clone(), peer_a, start + Duration::from_secs(1))
+            .is_none());
+
+        let second = announcements
+            .record(txid, peer_b, start + Duration::from_millis(1500))
+            .unwrap();
+        assert_eq!(second.delay, Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn txids_are_byte_reversed() {
+        let mut bytes = [0u8; 32];
+        bytes[0] = 0x01;
+
+        let txid = txid_hex(&Hash::new(bytes));
+        assert!(txid.ends_with("01"));
+        assert!(txid.starts_with("00"));
+    }
+}
@@ -2,6 +2,7 @@ use std::{
     fmt::{self, Display},
     net::{IpAddr, Ipv4Addr, SocketAddr},
     str::FromStr,
+    time::Duration,
 };
 
 use anyhow::Result;
@@ -10,6 +11,7 @@ use ziggurat_zcash::tools::{message_filter::MessageFilter, synthetic_node::Synth
 
 mod advanced_sn_for_s001;
 mod constantly_ask_for_random_blocks;
+mod mempool_sniffer;
 mod quick_connect_and_then_clean_disconnect;
 mod quick_connect_with_improper_disconnect;
 mod rt_s1_collector;
@@ -47,6 +49,7 @@ pub enum ActionType {
     ConstantlyAskForRandomBlocks,
     RtS1Collector,
     RtS1Tainter,
+    MempoolSniffer,
 }
 
 impl Display for ActionType {
@@ -62,6 +65,7 @@ impl Display for ActionType {
                 Self::ConstantlyAskForRandomBlocks => "ConstantlyAskForRandomBlocks",
                 Self::RtS1Collector => "RtS1Collector",
                 Self::RtS1Tainter => "RtS1Tainter",
+                Self::MempoolSniffer => "MempoolSniffer",
             }
         )
     }
@@ -79,6 +83,7 @@ impl FromStr for ActionType {
             "ConstantlyAskForRandomBlocks" => Ok(Self::ConstantlyAskForRandomBlocks),
             "RtS1Collector" => Ok(Self::RtS1Collector),
             "RtS1Tainter" => Ok(Self::RtS1Tainter),
+            "MempoolSniffer" => Ok(Self::MempoolSniffer),
             _ => Err("Invalid action type"),
         }
     }
@@ -94,6 +99,9 @@ pub struct ActionCfg {
 
     /// When enabled, the shutdown API in synthetic node is skipped.
     pub allow_proper_shutdown: bool,
+
+    /// The `SO_LINGER` option set on the connection sockets, if any.
+    pub linger: Option<Duration>,
 }
 
 impl Default for ActionCfg {
@@ -105,6 +113,7 @@ impl Default for ActionCfg {
                 ..Default::default()
             },
             allow_proper_shutdown: true,
+            linger: None,
         }
     }
 }
@@ -133,6 +142,7 @@ impl ActionHandler {
             ActionType::ConstantlyAskForRandomBlocks => constantly_ask_for_random_blocks::action(),
             ActionType::RtS1Collector => rt_s1_collector::action(),
             ActionType::RtS1Tainter => rt_s1_tainter::action(),
+            ActionType::MempoolSniffer => mempool_sniffer::action(),
         };
         let cfg = action.config();
 
@@ -153,3 +163,48 @@ impl ActionHandler {
         self.action.run(synth_node, addr).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ziggurat_zcash::{
+        protocol::{message::Message, payload::Nonce},
+        tools::LONG_TIMEOUT,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn collector_filter_forwards_getaddr_and_auto_replies_to_pings() {
+        let cfg = rt_s1_collector::action().config();
+
+        let mut collector = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_message_filter(cfg.msg_filter)
+            .build()
+            .await
+            .unwrap();
+        let collector_addr = collector.listening_addr().unwrap();
+
+        let mut peer = SyntheticNode::builder()
+            .with_full_handshake()
+            .with_all_auto_reply()
+            .build()
+            .await
+            .unwrap();
+        peer.connect(collector_addr).await.unwrap();
+
+        // The ping is answered without reaching the action.
+        let nonce = Nonce::default();
+        peer.unicast(collector_addr, Message::Ping(nonce)).unwrap();
+        let (_, pong) = peer.recv_message_timeout(LONG_TIMEOUT).await.unwrap();
+        assert_eq!(pong, Message::Pong(nonce));
+
+        // The GetAddr is forwarded to the action.
+        peer.unicast(collector_addr, Message::GetAddr).unwrap();
+        let (_, getaddr) = collector.recv_message_timeout(LONG_TIMEOUT).await.unwrap();
+        assert_eq!(getaddr, Message::GetAddr);
+
+        peer.shut_down().await;
+        collector.shut_down().await;
+    }
+}
@@ -1,4 +1,20 @@
-use std::net::SocketAddr;
+//! Completes the full handshake and then abruptly resets the connection.
+//!
+//! The connection socket is configured with `SO_LINGER` set to a zero timeout, so closing it makes
+//! the kernel discard any unsent data and send a TCP `RST` instead of going through the regular
+//! `FIN` shutdown sequence. The node thus observes an abrupt peer loss rather than a clean close.
+//!
+//! The reset can be verified on the node side by capturing the connection's traffic, e.g.:
+//!
+//! ```text
+//! tcpdump -i lo 'tcp port <node port> and tcp[tcpflags] & tcp-rst != 0'
+//! ```
+//!
+//! The `RST` should be sent by the synthetic node's address right after the `Verack` exchange,
+//! with no preceding `FIN`. The node's logs should also report the connection as reset by the peer
+//! rather than closed.
+
+use std::{net::SocketAddr, time::Duration};
 
 use anyhow::Result;
 use ziggurat_zcash::tools::synthetic_node::SyntheticNode;
@@ -14,17 +30,18 @@ pub(super) fn action() -> Box<dyn SynthNodeAction> {
 #[async_trait::async_trait]
 impl SynthNodeAction for Action {
     fn info(&self) -> &str {
-        "a synth node which only connects and immediately disconnects improperly"
+        "a synth node which completes the handshake and immediately resets the connection (TCP RST)"
     }
 
     fn config(&self) -> ActionCfg {
         ActionCfg {
             allow_proper_shutdown: false,
+            // A zero linger timeout turns closing the socket into a reset.
+            linger: Some(Duration::ZERO),
             ..Default::default()
         }
     }
 
-    #[allow(unused_variables)]
     async fn run(&self, synth_node: &mut SyntheticNode, addr: Option<SocketAddr>) -> Result<()> {
         let addr = if let Some(addr) = addr {
             addr
@@ -32,12 +49,15 @@ impl SynthNodeAction for Action {
             anyhow::bail!("address not provided");
         };
 
+        // The handshake is completed before the action runs.
         println!("Synthetic node connected to {addr}!");
 
-        // An optional short sleep.
-        //tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        // Dropping the connection closes the socket, which sends an RST due to the linger option.
+        println!("Synthetic node resetting the connection!");
+        if !synth_node.disconnect(addr).await {
+            anyhow::bail!("the connection to {addr} was already closed");
+        }
 
-        println!("Synthetic node disconnecting!");
         Ok(())
     }
 }
@@ -9,7 +9,10 @@ use ziggurat_zcash::{
         message::Message,
         payload::{addr::NetworkAddr, Addr},
     },
-    tools::{message_filter::MessageFilter, synthetic_node::SyntheticNode},
+    tools::{
+        message_filter::{Filter, MessageFilter},
+        synthetic_node::SyntheticNode,
+    },
 };
 
 use super::{ActionCfg, SynthNodeAction};
@@ -29,12 +32,14 @@ pub(super) fn action() -> Box<dyn SynthNodeAction> {
 #[async_trait::async_trait]
 impl SynthNodeAction for Action {
     fn info(&self) -> &str {
-        "a node which sends an Addr message every three seconds containing all connected peers"
+        "a node which sends an Addr message every minute containing all connected peers, and
+           answers GetAddr requests with the same list"
     }
 
     fn config(&self) -> ActionCfg {
         ActionCfg {
-            msg_filter: MessageFilter::with_all_disabled(),
+            // Keep the connections alive, but answer GetAddr with the actual peer list.
+            msg_filter: MessageFilter::with_all_auto_reply().with_getaddr_filter(Filter::Disabled),
             network_cfg: NodeConfig {
                 listener_ip: Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
                 desired_listening_port: Some(18233),
@@ -42,6 +47,7 @@ impl SynthNodeAction for Action {
                 ..Default::default()
             },
             allow_proper_shutdown: true,
+            linger: None,
         }
     }
 
@@ -68,8 +74,12 @@ impl SynthNodeAction for Action {
                     num_connected = num_connected_new;
                     let _ = broadcast_addr_msg(synth_node);
                 },
-                // Clear inbound queue.
-                Ok(_) = synth_node.try_recv_message() => (),
+                // Answer GetAddr requests and clear the rest of the inbound queue.
+                Ok((src, msg)) = synth_node.try_recv_message() => {
+                    if msg == Message::GetAddr {
+                        let _ = synth_node.unicast(src, peer_list_addr_msg(synth_node));
+                    }
+                },
             }
         }
     }
@@ -105,7 +115,8 @@ async fn trace_debug_info(synth_node: &SyntheticNode) {
     tracing::info!("{log}");
 }
 
-fn broadcast_addr_msg(synth_node: &mut SyntheticNode) -> Result<()> {
+/// Returns an Addr message listing up to [`MAX_PEER_LIST_LEN`] of the connected peers.
+fn peer_list_addr_msg(synth_node: &SyntheticNode) -> Message {
     let mut addrs = synth_node.connected_peers();
 
     if addrs.len() > MAX_PEER_LIST_LEN {
@@ -113,7 +124,7 @@ fn broadcast_addr_msg(synth_node: &mut SyntheticNode) -> Result<()> {
         addrs.truncate(MAX_PEER_LIST_LEN);
     }
 
-    let msg = Message::Addr(Addr::new(
+    Message::Addr(Addr::new(
         addrs
             .into_iter()
             .map(|addr| {
@@ -128,7 +139,11 @@ fn broadcast_addr_msg(synth_node: &mut SyntheticNode) -> Result<()> {
                 NetworkAddr::new(SocketAddr::new(ip, port))
             })
             .collect::<Vec<NetworkAddr>>(),
-    ));
+    ))
+}
+
+fn broadcast_addr_msg(synth_node: &mut SyntheticNode) -> Result<()> {
+    let msg = peer_list_addr_msg(synth_node);
 
     for addr in synth_node.connected_peers() {
         if synth_node.unicast(addr, msg.clone()).is_err() {
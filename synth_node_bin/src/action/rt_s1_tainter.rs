@@ -42,6 +42,7 @@ impl SynthNodeAction for Action {
                 ..Default::default()
             },
             allow_proper_shutdown: true,
+            linger: None,
         }
     }
 
@@ -56,7 +57,7 @@ impl SynthNodeAction for Action {
         let mut dbg_info_interval = interval(DBG_INFO_LOG_INTERVAL_SEC);
         let mut broadcast_msgs_interval = interval(BROADCAST_INTERVAL_SEC);
         let mut tainted_addr_msg = Message::Addr(Addr::new(vec![NetworkAddr::new(
-            synth_node.listening_addr(),
+            synth_node.listening_addr()?,
         )]));
 
         loop {
@@ -144,38 +145,19 @@ fn broadcast_periodic_msgs(synth_node: &mut SyntheticNode, addr_msg: &Message) -
 }
 
 fn broadcast_ping_msg(synth_node: &mut SyntheticNode) -> Result<()> {
-    let msg = Message::Ping(Nonce::default());
-
-    for addr in synth_node.connected_peers() {
-        if synth_node.unicast(addr, msg.clone()).is_err() {
-            tracing::error!("failed to send {msg:?} to {addr}\n");
-            anyhow::bail!("connection closed");
-        }
-    }
+    synth_node.broadcast(Message::Ping(Nonce::default()))?;
 
     Ok(())
 }
 
 fn broadcast_get_addr_msg(synth_node: &mut SyntheticNode) -> Result<()> {
-    let msg = Message::GetAddr;
-
-    for addr in synth_node.connected_peers() {
-        if synth_node.unicast(addr, msg.clone()).is_err() {
-            tracing::error!("failed to send {msg:?} to {addr}\n");
-            anyhow::bail!("connection closed");
-        }
-    }
+    synth_node.broadcast(Message::GetAddr)?;
 
     Ok(())
 }
 
 fn broadcast_addr_msg(synth_node: &mut SyntheticNode, addr_msg: &Message) -> Result<()> {
-    for dst in synth_node.connected_peers() {
-        if synth_node.unicast(dst, addr_msg.clone()).is_err() {
-            tracing::warn!("failed to send {addr_msg:?}\n");
-            anyhow::bail!("connection closed");
-        }
-    }
+    synth_node.broadcast(addr_msg.clone())?;
 
     Ok(())
 }
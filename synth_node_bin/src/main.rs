@@ -1,6 +1,6 @@
 //! A synthetic node binary can be used to interact with the node in the
 //! background from a different runtime environment.
-use std::{net::SocketAddr, process::ExitCode};
+use std::{net::SocketAddr, process::ExitCode, time::Duration};
 
 use action::{ActionHandler, ActionType};
 use anyhow::Result;
@@ -11,6 +11,9 @@ use crate::ActionType::SendGetAddrAndForeverSleep;
 
 mod action;
 
+/// The upper bound of the delay between reconnects, however many attempts were made.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(300);
+
 /// A synthetic node which can connect to the node and preform some actions independently.
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -20,9 +23,22 @@ struct CmdArgs {
     node_addr: Option<SocketAddr>,
 
     /// Always reconnect in the case the connection fails - synthetic node never dies.
-    #[arg(short = 's', long, default_value_t = false)]
+    #[arg(
+        short = 's',
+        long,
+        default_value_t = false,
+        conflicts_with = "max_reconnects"
+    )]
     stubborn: bool,
 
+    /// The delay before the first reconnect, doubled on every following attempt (up to 5 minutes).
+    #[arg(long, default_value_t = 1)]
+    reconnect_delay_secs: u64,
+
+    /// Reconnect up to this many times in a row in the case the connection fails, then give up.
+    #[arg(long)]
+    max_reconnects: Option<u32>,
+
     /// Enable tracing.
     #[arg(short = 't', long, default_value_t = false)]
     tracing: bool,
@@ -33,7 +49,8 @@ struct CmdArgs {
 
     /// Possible actions:
     /// SendGetAddrAndForeverSleep / AdvancedSnForS001 / QuickConnectAndThenCleanDisconnect /
-    /// QuickConnectWithImproperDisconnect / ConstantlyAskForRandomBlocks / RtS1Collector / RtS1Tainter
+    /// QuickConnectWithImproperDisconnect / ConstantlyAskForRandomBlocks / RtS1Collector / RtS1Tainter /
+    /// MempoolSniffer
     #[arg(short = 'a', long, default_value_t = SendGetAddrAndForeverSleep)]
     action_type: ActionType,
 }
@@ -53,28 +70,81 @@ async fn main() -> ExitCode {
             .init();
     }
 
+    // Use the stubborn option to run the synth node infinitely.
+    let max_reconnects = if args.stubborn {
+        None
+    } else {
+        Some(args.max_reconnects.unwrap_or(0))
+    };
+    let base_delay = Duration::from_secs(args.reconnect_delay_secs);
+
+    let mut reconnects = 0;
     loop {
         println!("Starting a synthetic node.");
 
-        if let Err(e) =
-            run_synth_node(node_addr, args.action_type, args.desired_listening_port).await
+        let mut handshaken = false;
+        if let Err(e) = run_synth_node(
+            node_addr,
+            args.action_type,
+            args.desired_listening_port,
+            &mut handshaken,
+        )
+        .await
         {
             eprintln!("The synthetic node stopped: {e:?}.");
         }
 
-        // Use the stubborn option to run the synth node infinitely.
-        if !args.stubborn {
-            break;
+        // A successful handshake ends a series of failures, so the backoff starts over.
+        if handshaken {
+            reconnects = 0;
         }
+
+        let delay = match reconnect_delay(base_delay, reconnects, max_reconnects) {
+            Some(delay) => delay,
+            None => {
+                if reconnects > 0 {
+                    println!("Giving up after {reconnects} reconnect(s).");
+                }
+                break;
+            }
+        };
+
+        reconnects += 1;
+        println!("Reconnect attempt {reconnects} in {delay:?}.");
+        tokio::time::sleep(delay).await;
     }
 
     ExitCode::SUCCESS
 }
 
+/// Returns the delay before the next reconnect, or `None` if `reconnects` already reached
+/// `max_reconnects` (unbounded if `None`). The reconnects are counted since the last successful
+/// handshake.
+///
+/// The delay starts at `base_delay` and doubles on every attempt, up to [`MAX_RECONNECT_DELAY`].
+fn reconnect_delay(
+    base_delay: Duration,
+    reconnects: u32,
+    max_reconnects: Option<u32>,
+) -> Option<Duration> {
+    if matches!(max_reconnects, Some(max) if reconnects >= max) {
+        return None;
+    }
+
+    let factor = 2u32.checked_pow(reconnects).unwrap_or(u32::MAX);
+    Some(
+        base_delay
+            .checked_mul(factor)
+            .unwrap_or(MAX_RECONNECT_DELAY)
+            .min(MAX_RECONNECT_DELAY),
+    )
+}
+
 async fn run_synth_node(
     node_addr: Option<SocketAddr>,
     action_type: ActionType,
     desired_listening_port: Option<u16>,
+    handshaken: &mut bool,
 ) -> Result<()> {
     // Select an action.
     let action = ActionHandler::new(action_type);
@@ -86,16 +156,19 @@ async fn run_synth_node(
     }
 
     // Create a synthetic node and enable handshaking.
-    let mut synth_node = SyntheticNode::builder()
+    let mut builder = SyntheticNode::builder()
         .with_network_config(net_cfg)
         .with_full_handshake()
-        .with_message_filter(action.cfg.msg_filter.clone())
-        .build()
-        .await?;
+        .with_message_filter(action.cfg.msg_filter.clone());
+    if let Some(linger) = action.cfg.linger {
+        builder = builder.with_linger(linger);
+    }
+    let mut synth_node = builder.build().await?;
 
     // Perform the handshake.
     if let Some(addr) = node_addr {
         synth_node.connect(addr).await?;
+        *handshaken = true;
     }
 
     // Run the wanted action with the node.
@@ -108,3 +181,47 @@ async fn run_synth_node(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_delay_backs_off_and_gives_up() {
+        let base_delay = Duration::from_secs(1);
+
+        let delays = (0..3)
+            .map(|reconnects| reconnect_delay(base_delay, reconnects, Some(3)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            delays,
+            [1, 2, 4].map(|secs| Some(Duration::from_secs(secs)))
+        );
+        assert_eq!(reconnect_delay(base_delay, 3, Some(3)), None);
+
+        // Without reconnects, the node runs once.
+        assert_eq!(reconnect_delay(base_delay, 0, Some(0)), None);
+    }
+
+    #[test]
+    fn stubborn_reconnect_delay_is_capped() {
+        let base_delay = Duration::from_secs(1);
+
+        assert_eq!(
+            reconnect_delay(base_delay, 8, None),
+            Some(Duration::from_secs(256))
+        );
+        assert_eq!(
+            reconnect_delay(base_delay, 9, None),
+            Some(MAX_RECONNECT_DELAY)
+        );
+        assert_eq!(
+            reconnect_delay(base_delay, u32::MAX, None),
+            Some(MAX_RECONNECT_DELAY)
+        );
+        assert_eq!(
+            reconnect_delay(Duration::ZERO, 5, None),
+            Some(Duration::ZERO)
+        );
+    }
+}